@@ -0,0 +1,85 @@
+//! Builds a Polyglot `.bin` opening book from PGN files.
+//!
+//! ```text
+//! book-builder [--plies N] [--player NAME] [--min-games N] -o book.bin games.pgn...
+//! ```
+
+use std::process::ExitCode;
+
+use chess_engine::book_builder::BookBuilder;
+
+const USAGE: &str =
+    "usage: book-builder [--plies N] [--player NAME] [--min-games N] -o OUT.bin GAMES.pgn...";
+
+struct Args {
+    plies: usize,
+    player: Option<String>,
+    min_games: u32,
+    output: String,
+    inputs: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut plies = 16;
+    let mut player = None;
+    let mut min_games = 1;
+    let mut output = None;
+    let mut inputs = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--plies" => plies = value()?.parse().map_err(|_| "invalid --plies")?,
+            "--player" => player = Some(value()?),
+            "--min-games" => min_games = value()?.parse().map_err(|_| "invalid --min-games")?,
+            "-o" | "--output" => output = Some(value()?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}\n{USAGE}")),
+            _ => inputs.push(arg),
+        }
+    }
+    let output = output.ok_or_else(|| format!("missing -o\n{USAGE}"))?;
+    if inputs.is_empty() {
+        return Err(format!("no PGN files given\n{USAGE}"));
+    }
+    Ok(Args { plies, player, min_games, output, inputs })
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut builder = BookBuilder::new(args.plies).min_games(args.min_games);
+    if let Some(name) = args.player {
+        builder = builder.player(name);
+    }
+    for path in &args.inputs {
+        // PGN files in the wild are often Latin-1; replace invalid bytes.
+        let text = match std::fs::read(path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                eprintln!("{path}: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+        let used = builder.add_pgn(&text);
+        eprintln!("{path}: {used} games used");
+    }
+
+    let book = builder.build();
+    if let Err(e) = std::fs::write(&args.output, book.to_bytes()) {
+        eprintln!("{}: {e}", args.output);
+        return ExitCode::FAILURE;
+    }
+    eprintln!(
+        "wrote {} entries from {} games to {}",
+        book.len(),
+        builder.games_used(),
+        args.output
+    );
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,131 @@
+//! Generates self-play training data.
+//!
+//! ```text
+//! datagen [--games N] [--nodes N] [--random-plies N] [--seed N] [--threads N] [--text OUT.txt] [--binary OUT.bin]
+//! ```
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc;
+
+use chess_engine::datagen::{Datagen, Record};
+
+const USAGE: &str = "usage: datagen [--games N] [--nodes N] [--random-plies N] [--seed N] [--threads N] \
+                     [--text OUT.txt] [--binary OUT.bin]";
+
+struct Args {
+    games: u32,
+    nodes: u64,
+    random_plies: usize,
+    seed: u64,
+    threads: usize,
+    text: Option<String>,
+    binary: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut games = 100;
+    let mut nodes = 5000;
+    let mut random_plies = 8;
+    let mut seed = 1;
+    let mut threads = 1;
+    let mut text = None;
+    let mut binary = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--games" => games = value()?.parse().map_err(|_| "invalid --games")?,
+            "--nodes" => nodes = value()?.parse().map_err(|_| "invalid --nodes")?,
+            "--random-plies" => random_plies = value()?.parse().map_err(|_| "invalid --random-plies")?,
+            "--seed" => seed = value()?.parse().map_err(|_| "invalid --seed")?,
+            "--threads" => threads = value()?.parse().map_err(|_| "invalid --threads")?,
+            "--text" => text = Some(value()?),
+            "--binary" => binary = Some(value()?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ => return Err(format!("unknown argument {arg}\n{USAGE}")),
+        }
+    }
+    if text.is_none() && binary.is_none() {
+        return Err(format!("missing --text or --binary\n{USAGE}"));
+    }
+    Ok(Args { games, nodes, random_plies, seed, threads: threads.max(1), text, binary })
+}
+
+fn create(path: Option<&String>) -> std::io::Result<Option<BufWriter<File>>> {
+    path.map(|path| File::create(path).map(BufWriter::new)).transpose()
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let (mut text, mut binary) = match (create(args.text.as_ref()), create(args.binary.as_ref())) {
+        (Ok(text), Ok(binary)) => (text, binary),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Each thread plays its own games; records are written as games finish.
+    let next_game = AtomicU32::new(0);
+    let (sender, receiver) = mpsc::channel::<Vec<Record>>();
+    let written = std::thread::scope(|scope| {
+        for thread in 0..args.threads {
+            let (sender, next_game, args) = (sender.clone(), &next_game, &args);
+            scope.spawn(move || {
+                let mut datagen =
+                    Datagen::new(args.nodes, args.seed ^ (thread as u64) << 32).random_plies(args.random_plies);
+                while next_game.fetch_add(1, Ordering::Relaxed) < args.games {
+                    if sender.send(datagen.play_game()).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let (mut games, mut positions, mut skipped) = (0u32, 0usize, 0usize);
+        for records in receiver {
+            for record in &records {
+                // Both outputs hold the same records, so one the binary format can't encode is left out of each.
+                let Some(bytes) = record.to_bytes() else {
+                    skipped += 1;
+                    continue;
+                };
+                if let Some(out) = &mut text {
+                    writeln!(out, "{}", record.to_text())?;
+                }
+                if let Some(out) = &mut binary {
+                    out.write_all(&bytes)?;
+                }
+                positions += 1;
+            }
+            games += 1;
+            if games % 10 == 0 || games == args.games {
+                eprintln!("{games}/{} games, {positions} positions", args.games);
+            }
+        }
+        for out in [&mut text, &mut binary].into_iter().flatten() {
+            out.flush()?;
+        }
+        Ok::<_, std::io::Error>((positions, skipped))
+    });
+
+    match written {
+        Ok((positions, skipped)) => {
+            eprintln!("wrote {positions} positions, skipped {skipped} that the binary format cannot encode");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Generates endgame tablebase files for small material signatures.
+//!
+//! ```text
+//! tablebase-gen -o DIR KQK KRK KPK KBNK KQKR...
+//! ```
+//!
+//! Tables the requested ones depend on are generated and written too, one
+//! `<signature>.cetb` file each.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use chess_engine::tablebase::{Signature, Tablebases, EXTENSION};
+
+const USAGE: &str = "usage: tablebase-gen -o DIR SIGNATURE...";
+
+struct Args {
+    output: PathBuf,
+    signatures: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut output = None;
+    let mut signatures = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(args.next().ok_or_else(|| format!("{arg} needs a value"))?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}\n{USAGE}")),
+            _ if Signature::parse(&arg).is_none() => return Err(format!("invalid signature {arg}")),
+            _ => signatures.push(arg),
+        }
+    }
+    let output = output.ok_or_else(|| format!("missing -o\n{USAGE}"))?;
+    if signatures.is_empty() {
+        return Err(format!("no signatures given\n{USAGE}"));
+    }
+    Ok(Args { output: output.into(), signatures })
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut tablebases = Tablebases::new();
+    for signature in &args.signatures {
+        if tablebases.generate(signature).is_none() {
+            eprintln!("{signature}: draw by insufficient material, no table needed");
+        }
+    }
+    if let Err(e) = std::fs::create_dir_all(&args.output) {
+        eprintln!("{}: {e}", args.output.display());
+        return ExitCode::FAILURE;
+    }
+    for table in tablebases.tables() {
+        let path = args.output.join(format!("{}.{EXTENSION}", table.signature()));
+        if let Err(e) = table.save(&path) {
+            eprintln!("{}: {e}", path.display());
+            return ExitCode::FAILURE;
+        }
+        let longest = table.longest_win().map_or_else(|| "none".to_string(), |plies| format!("{plies} plies"));
+        eprintln!("wrote {} ({} entries, longest win {longest})", path.display(), table.len());
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,112 @@
+//! Tunes the evaluation weights on positions labelled with game results.
+//!
+//! ```text
+//! texel-tuner [--passes N] [--step N] [--threads N] [--params FILE] -o params.txt positions.epd...
+//! ```
+//!
+//! The weights are written as JSON when the output name ends in `.json`.
+
+use std::process::ExitCode;
+
+use chess_engine::eval::EvalParams;
+use chess_engine::tuner::Tuner;
+
+const USAGE: &str =
+    "usage: texel-tuner [--passes N] [--step N] [--threads N] [--params FILE] -o OUT.txt POSITIONS...";
+
+struct Args {
+    passes: usize,
+    step: i32,
+    threads: Option<usize>,
+    params: Option<String>,
+    output: String,
+    inputs: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut passes = 100;
+    let mut step = 1;
+    let mut threads = None;
+    let mut params = None;
+    let mut output = None;
+    let mut inputs = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--passes" => passes = value()?.parse().map_err(|_| "invalid --passes")?,
+            "--step" => step = value()?.parse().map_err(|_| "invalid --step")?,
+            "--threads" => threads = Some(value()?.parse().map_err(|_| "invalid --threads")?),
+            "--params" => params = Some(value()?),
+            "-o" | "--output" => output = Some(value()?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}\n{USAGE}")),
+            _ => inputs.push(arg),
+        }
+    }
+    let output = output.ok_or_else(|| format!("missing -o\n{USAGE}"))?;
+    if inputs.is_empty() {
+        return Err(format!("no position files given\n{USAGE}"));
+    }
+    Ok(Args { passes, step, threads, params, output, inputs })
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let start = match &args.params {
+        None => EvalParams::DEFAULT,
+        Some(path) => match EvalParams::load(path) {
+            Ok(params) => params,
+            Err(e) => {
+                eprintln!("{path}: {e}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    let mut tuner = Tuner::new().step(args.step);
+    if let Some(threads) = args.threads {
+        tuner = tuner.threads(threads);
+    }
+    for path in &args.inputs {
+        let text = match std::fs::read(path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                eprintln!("{path}: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+        let used = tuner.add_positions(&text);
+        eprintln!("{path}: {used} positions used");
+    }
+    if tuner.samples().is_empty() {
+        eprintln!("no usable positions");
+        return ExitCode::FAILURE;
+    }
+
+    let k = tuner.fit_k(&start);
+    eprintln!("K = {k:.4}, error {:.6}", tuner.error(&start, k));
+    let json =
+        std::path::Path::new(&args.output).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let mut write_error = None;
+    tuner.tune(start, k, args.passes, |pass, error, params| {
+        eprintln!("pass {pass}: error {error:.6}");
+        // Save after every pass so a long run can be stopped at any time.
+        let text = if json { params.to_json() } else { params.to_text() };
+        if let Err(e) = std::fs::write(&args.output, text) {
+            write_error.get_or_insert(e);
+        }
+    });
+    if let Some(e) = write_error {
+        eprintln!("{}: {e}", args.output);
+        return ExitCode::FAILURE;
+    }
+    eprintln!("wrote {}", args.output);
+    ExitCode::SUCCESS
+}
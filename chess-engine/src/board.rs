@@ -25,6 +25,7 @@
 use crate::types::{
     CastlingRights, Color, GameState, Move, MoveKind, Piece, PieceType, Square,
 };
+use crate::zobrist::{castling_key, piece_key};
 use std::sync::OnceLock;
 
 // ---------------------------------------------------------------------------
@@ -161,7 +162,7 @@ fn lsb_index(bb: u64) -> u8 {
 ///
 /// Directions whose square index *increases* along the ray (N, NE, E, NW)
 /// use `trailing_zeros`; those that *decrease* (SE, S, SW, W) use `leading_zeros`.
-#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_truncation, clippy::manual_ilog2)]
 #[inline]
 fn nearest_blocker(blockers: u64, dir: usize) -> u8 {
     debug_assert!(blockers != 0, "nearest_blocker called with empty bitboard");
@@ -271,6 +272,8 @@ pub struct Board {
     en_passant: Option<Square>,
     halfmove_clock: u16,
     fullmove_number: u16,
+    /// The piece and castling values of the Polyglot key (see [`crate::zobrist`]).
+    base_key: u64,
 }
 
 impl Default for Board {
@@ -316,6 +319,7 @@ impl Board {
             en_passant: None,
             halfmove_clock: 0,
             fullmove_number: 1,
+            base_key: 0,
         };
 
         // Piece placement
@@ -395,6 +399,7 @@ impl Board {
             board.fullmove_number = parts[5].parse().ok()?;
         }
 
+        board.base_key = board.compute_base_key();
         Some(board)
     }
 
@@ -499,7 +504,137 @@ impl Board {
     #[must_use]
     pub const fn fullmove_number(&self) -> u16 { self.fullmove_number }
 
-    fn king_square(&self, color: Color) -> Square {
+    /// The piece and castling values of the [`polyglot_key`](Self::polyglot_key),
+    /// kept up to date as moves are applied.
+    #[inline]
+    pub(crate) const fn base_key(&self) -> u64 { self.base_key }
+
+    /// Bitboard of the given side's pieces of one type.
+    #[inline]
+    #[must_use]
+    pub const fn pieces(&self, color: Color, pt: PieceType) -> u64 {
+        self.pieces[color.index()][pt as usize]
+    }
+
+    /// Bitboard of every piece belonging to `color`.
+    #[inline]
+    #[must_use]
+    pub const fn occupancy(&self, color: Color) -> u64 { self.occupancy[color.index()] }
+
+    /// Bitboard of every occupied square.
+    #[inline]
+    #[must_use]
+    pub const fn occupied(&self) -> u64 { self.all }
+
+    // -----------------------------------------------------------------------
+    // Crate-internal construction (binary decoders, position generators)
+    // -----------------------------------------------------------------------
+
+    /// An empty board with White to move and no rights. Not a valid position
+    /// until both kings are placed; see [`has_valid_kings`](Self::has_valid_kings).
+    pub(crate) const fn empty() -> Self {
+        Self {
+            pieces: [[0; 6]; 2],
+            occupancy: [0; 2],
+            all: 0,
+            side_to_move: Color::White,
+            castling: CastlingRights::NONE,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            base_key: 0,
+        }
+    }
+
+    /// Places a piece on an empty square.
+    pub(crate) fn put_piece(&mut self, sq: Square, piece: Piece) {
+        debug_assert!(self.all & sq.bitboard() == 0, "put_piece on occupied square {sq}");
+        let bb = sq.bitboard();
+        self.pieces[piece.color().index()][piece.piece_type() as usize] |= bb;
+        self.occupancy[piece.color().index()] |= bb;
+        self.all |= bb;
+        self.base_key ^= piece_key(piece.color(), piece.piece_type(), sq);
+    }
+
+    /// Overwrites the non-placement part of the position.
+    pub(crate) fn set_state(
+        &mut self,
+        side_to_move: Color,
+        castling: CastlingRights,
+        en_passant: Option<Square>,
+        halfmove_clock: u16,
+        fullmove_number: u16,
+    ) {
+        self.side_to_move = side_to_move;
+        self.base_key ^= castling_key(self.castling) ^ castling_key(castling);
+        self.castling = castling;
+        self.en_passant = en_passant;
+        self.halfmove_clock = halfmove_clock;
+        self.fullmove_number = fullmove_number;
+    }
+
+    /// `true` if each side has exactly one king — the same check
+    /// [`from_fen`](Self::from_fen) applies.
+    pub(crate) const fn has_valid_kings(&self) -> bool {
+        self.pieces[0][PieceType::King as usize].is_power_of_two()
+            && self.pieces[1][PieceType::King as usize].is_power_of_two()
+    }
+
+    // -----------------------------------------------------------------------
+    // Symmetry transforms
+    // -----------------------------------------------------------------------
+
+    /// Returns the color-flipped position: ranks are reversed, every piece
+    /// changes color, and side to move, castling rights, and the en-passant
+    /// square follow. Clocks are unchanged.
+    ///
+    /// The flipped position is equivalent to the original with the roles of
+    /// White and Black exchanged, so move counts and game state are identical.
+    #[must_use]
+    pub fn flipped(&self) -> Self {
+        let mut pieces = [[0; 6]; 2];
+        for pt in PieceType::ALL {
+            pieces[0][pt as usize] = self.pieces[1][pt as usize].swap_bytes();
+            pieces[1][pt as usize] = self.pieces[0][pt as usize].swap_bytes();
+        }
+        let occupancy = [self.occupancy[1].swap_bytes(), self.occupancy[0].swap_bytes()];
+        let mut board = Self {
+            pieces,
+            occupancy,
+            all: occupancy[0] | occupancy[1],
+            side_to_move: self.side_to_move.opposite(),
+            castling: self.castling.flipped(),
+            en_passant: self.en_passant.map(Square::flipped),
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            base_key: 0,
+        };
+        board.base_key = board.compute_base_key();
+        board
+    }
+
+    /// Returns the file-mirrored position (a-file ↔ h-file).
+    ///
+    /// Castling rights are dropped because castling is not symmetric under
+    /// a file mirror. Everything else is preserved.
+    #[must_use]
+    pub fn mirrored(&self) -> Self {
+        let mirror = |bb: u64| bb.reverse_bits().swap_bytes();
+        let mut board = self.clone();
+        for side in &mut board.pieces {
+            for bb in side.iter_mut() {
+                *bb = mirror(*bb);
+            }
+        }
+        board.occupancy = [mirror(self.occupancy[0]), mirror(self.occupancy[1])];
+        board.all = mirror(self.all);
+        board.castling = CastlingRights::NONE;
+        board.en_passant = self.en_passant.map(Square::mirrored);
+        board.base_key = board.compute_base_key();
+        board
+    }
+
+    pub(crate) fn king_square(&self, color: Color) -> Square {
         let bb = self.pieces[color.index()][PieceType::King as usize];
         debug_assert!(bb != 0, "no king found for {color}");
         Square::from_index(lsb_index(bb))
@@ -569,6 +704,34 @@ impl Board {
         }
     }
 
+    /// Squares attacked by `piece` standing on `sq` given the current
+    /// occupancy, whether or not they hold pieces of either color. Pawns
+    /// attack only their two capture squares.
+    #[must_use]
+    pub fn attacks(&self, piece: Piece, sq: Square) -> u64 {
+        let t = tables();
+        let idx = sq.index();
+        let dirs: &[usize] = match piece.piece_type() {
+            PieceType::Pawn => return t.pawn[piece.color().index()][idx],
+            PieceType::Knight => return t.knight[idx],
+            PieceType::King => return t.king[idx],
+            PieceType::Bishop => &[1, 3, 5, 7],
+            PieceType::Rook => &[0, 2, 4, 6],
+            PieceType::Queen => &[0, 1, 2, 3, 4, 5, 6, 7],
+        };
+        let mut attacks = 0;
+        for &dir in dirs {
+            let ray = t.rays[idx][dir];
+            let blockers = ray & self.all;
+            attacks |= if blockers == 0 {
+                ray
+            } else {
+                ray ^ t.rays[usize::from(nearest_blocker(blockers, dir))][dir]
+            };
+        }
+        attacks
+    }
+
     /// Returns `true` if the given side's king is in check.
     #[must_use]
     pub fn is_in_check(&self, color: Color) -> bool {
@@ -592,7 +755,7 @@ impl Board {
             .collect()
     }
 
-    fn generate_pseudo_legal(&self) -> Vec<Move> {
+    pub(crate) fn generate_pseudo_legal(&self) -> Vec<Move> {
         let mut moves = Vec::with_capacity(256);
         let us = self.side_to_move.index();
         let them = self.side_to_move.opposite().index();
@@ -603,7 +766,8 @@ impl Board {
         let (push, start_rank, promo_rank): (i8, u8, u8) =
             if self.side_to_move == Color::White { (8, 1, 7) } else { (-8, 6, 0) };
 
-        let mut bb = pawns;
+        // A pawn already on its last rank (which FEN allows) has nowhere to go.
+        let mut bb = pawns & !(0xFF << (8 * promo_rank));
         while bb != 0 {
             let from = Square::from_index(lsb_index(bb));
             bb &= bb - 1;
@@ -665,30 +829,17 @@ impl Board {
         self.gen_castling(&mut moves);
 
         // Sliding pieces (bishop, rook, queen)
-        for &(pt, dirs) in &[
-            (PieceType::Bishop, &[1, 3, 5, 7][..]),
-            (PieceType::Rook, &[0, 2, 4, 6][..]),
-            (PieceType::Queen, &[0, 1, 2, 3, 4, 5, 6, 7][..]),
-        ] {
+        for pt in [PieceType::Bishop, PieceType::Rook, PieceType::Queen] {
             let mut bb = self.pieces[us][pt as usize];
             while bb != 0 {
                 let from = Square::from_index(lsb_index(bb));
                 bb &= bb - 1;
-                for &dir in dirs {
-                    let ray = t.rays[from.index()][dir];
-                    let blockers = ray & self.all;
-                    let attacks = if blockers == 0 {
-                        ray
-                    } else {
-                        let bsq = nearest_blocker(blockers, dir);
-                        ray ^ t.rays[usize::from(bsq)][dir]
-                    };
-                    let mut targets = attacks & !self.occupancy[us];
-                    while targets != 0 {
-                        let to = Square::from_index(lsb_index(targets));
-                        targets &= targets - 1;
-                        moves.push(Move::normal(from, to));
-                    }
+                let attacks = self.attacks(Piece::new(pt, self.side_to_move), from);
+                let mut targets = attacks & !self.occupancy[us];
+                while targets != 0 {
+                    let to = Square::from_index(lsb_index(targets));
+                    targets &= targets - 1;
+                    moves.push(Move::normal(from, to));
                 }
             }
         }
@@ -778,11 +929,13 @@ impl Board {
         // Remove from source
         self.pieces[us][moving as usize] ^= from_bb;
         self.occupancy[us] ^= from_bb;
+        self.base_key ^= piece_key(self.side_to_move, moving, mv.from());
 
         // Handle capture at destination
         if self.occupancy[them] & to_bb != 0 {
             if let Some(captured) = self.piece_type_at(mv.to(), self.side_to_move.opposite()) {
                 self.pieces[them][captured as usize] ^= to_bb;
+                self.base_key ^= piece_key(self.side_to_move.opposite(), captured, mv.to());
             }
             self.occupancy[them] ^= to_bb;
         }
@@ -797,6 +950,7 @@ impl Board {
             let cap_bb = cap_sq.bitboard();
             self.pieces[them][PieceType::Pawn as usize] ^= cap_bb;
             self.occupancy[them] ^= cap_bb;
+            self.base_key ^= piece_key(self.side_to_move.opposite(), PieceType::Pawn, cap_sq);
         }
 
         // Place piece at destination (with promotion if applicable)
@@ -806,6 +960,7 @@ impl Board {
         };
         self.pieces[us][placed as usize] |= to_bb;
         self.occupancy[us] |= to_bb;
+        self.base_key ^= piece_key(self.side_to_move, placed, mv.to());
 
         // Rook movement for castling
         if mv.kind() == MoveKind::Castle {
@@ -820,6 +975,8 @@ impl Board {
                 | Square::from_index(rt).bitboard();
             self.pieces[us][PieceType::Rook as usize] ^= rook_move;
             self.occupancy[us] ^= rook_move;
+            self.base_key ^= piece_key(self.side_to_move, PieceType::Rook, Square::from_index(rf))
+                ^ piece_key(self.side_to_move, PieceType::Rook, Square::from_index(rt));
         }
 
         // En passant square
@@ -837,9 +994,13 @@ impl Board {
         // Non-corner squares produce 0xFF (no-op). This avoids branching on piece
         // type or position — any move from/to a corner unconditionally clears the
         // associated right.
+        let castling = self.castling;
         for sq in [mv.from().raw(), mv.to().raw()] {
             self.castling.clear(CastlingRights::mask_for_square(sq));
         }
+        if self.castling != castling {
+            self.base_key ^= castling_key(castling) ^ castling_key(self.castling);
+        }
 
         // Update combined occupancy
         self.all = self.occupancy[0] | self.occupancy[1];
@@ -907,7 +1068,7 @@ impl Board {
 // ---------------------------------------------------------------------------
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
     // ======================== Basic ========================
@@ -1246,7 +1407,7 @@ mod tests {
 
     // ======================== Perft ========================
 
-    fn perft(board: &Board, depth: u32) -> u64 {
+    pub fn perft(board: &Board, depth: u32) -> u64 {
         if depth == 0 {
             return 1;
         }
@@ -1290,7 +1451,7 @@ mod tests {
 
     // Kiwipete: the standard stress-test position for castling, en passant,
     // and promotion interactions.
-    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    pub const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
 
     #[test]
     fn kiwipete_perft_1() {
@@ -1664,4 +1825,149 @@ mod tests {
     fn cpw_pos6_perft_3() {
         assert_eq!(perft(&Board::from_fen(CPW_POS6).unwrap(), 3), 89_890);
     }
+
+    // ======================== Symmetry ========================
+
+    /// Every valid position used elsewhere in this suite. Shared with the
+    /// tests of other modules that need a varied set of positions.
+    pub const TEST_POSITIONS: &[&str] = &[
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 1",
+        "rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+        "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R b KQkq - 0 1",
+        "r3k2r/pppppppp/8/8/4r3/8/PPPP1PPP/R3K2R w KQkq - 0 1",
+        "4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1",
+        "4k1r1/8/8/8/8/8/8/R3K2R w KQ - 0 1",
+        "2r1k3/8/8/8/8/8/8/R3K2R w KQ - 0 1",
+        "3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1",
+        "3k4/8/4N3/6B1/8/8/8/4K3 b - - 0 1",
+        "3rk3/8/8/8/8/8/8/3K4 w - - 0 1",
+        "4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1",
+        "4k3/8/8/8/2n5/8/3p4/4K3 w - - 0 1",
+        "4k3/8/8/8/3q4/8/1B6/K7 w - - 0 1",
+        "4k3/8/8/8/4N3/8/8/4R2K w - - 0 1",
+        "4k3/8/8/8/8/2b5/3p4/4K3 w - - 0 1",
+        "4k3/8/8/8/8/4p3/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/5b2/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/3p4/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/4p3/4K3 w - - 10 1",
+        "4k3/8/8/8/8/8/4q3/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - - 100 50",
+        "4k3/8/8/8/8/8/8/r2NK3 w - - 0 1",
+        "4k3/8/8/8/8/8/r7/4K3 w - - 0 1",
+        "4k3/8/8/b7/8/2N5/8/4K3 w - - 0 1",
+        "4r2k/8/8/8/8/4R3/8/4K3 w - - 0 1",
+        "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+        "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1",
+        "8/8/8/8/8/3k4/8/4K3 w - - 0 1",
+        "8/8/8/8/8/8/4k3/4K3 w - - 0 1",
+        "8/8/8/8/8/8/4k3/4KB2 w - - 0 1",
+        "8/8/8/8/8/8/4k3/4KN2 w - - 0 1",
+        "8/8/8/8/k2Pp2R/8/8/4K3 b - d3 0 1",
+        "8/8/8/8/k2Pp3/8/8/4K3 b - d3 0 1",
+        "k2r4/4P3/8/8/8/8/8/4K3 w - - 0 1",
+        "k6K/8/8/8/8/8/4p3/8 b - - 0 1",
+        "k7/4P3/8/8/8/8/8/4K3 w - - 0 1",
+        KIWIPETE,
+        CPW_POS3,
+        CPW_POS4,
+        CPW_POS5,
+        CPW_POS6,
+    ];
+
+    /// A few draw tests use kings on adjacent squares; searching past the
+    /// first ply there would capture a king, so only count root moves.
+    fn legal_depth(b: &Board) -> u32 {
+        if b.is_in_check(b.side_to_move().opposite()) { 1 } else { 3 }
+    }
+
+    #[test]
+    fn square_transforms() {
+        let e2 = Square::from_algebraic("e2").unwrap();
+        assert_eq!(e2.flipped(), Square::from_algebraic("e7").unwrap());
+        assert_eq!(e2.mirrored(), Square::from_algebraic("d2").unwrap());
+        for i in 0..64 {
+            let sq = Square::from_index(i);
+            assert_eq!(sq.flipped().flipped(), sq);
+            assert_eq!(sq.mirrored().mirrored(), sq);
+        }
+    }
+
+    #[test]
+    fn flipped_board_fen() {
+        let b = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert_eq!(
+            b.flipped().to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1"
+        );
+        let b = Board::from_fen("r3k3/8/8/8/8/8/8/4K2R w Kq - 3 7").unwrap();
+        assert_eq!(b.flipped().to_fen(), "4k2r/8/8/8/8/8/8/R3K3 b Qk - 3 7");
+    }
+
+    #[test]
+    fn mirrored_board_fen() {
+        let b = Board::from_fen("r3k3/1p6/8/8/8/8/8/4K2R w Kq - 3 7").unwrap();
+        assert_eq!(b.mirrored().to_fen(), "3k3r/6p1/8/8/8/8/8/R2K4 w - - 3 7");
+    }
+
+    #[test]
+    fn transforms_are_involutions() {
+        for fen in TEST_POSITIONS {
+            let b = Board::from_fen(fen).unwrap();
+            assert_eq!(b.flipped().flipped().to_fen(), b.to_fen(), "flip: {fen}");
+            let mut no_castle = b.clone();
+            no_castle.castling = CastlingRights::NONE;
+            assert_eq!(b.mirrored().mirrored().to_fen(), no_castle.to_fen(), "mirror: {fen}");
+        }
+    }
+
+    #[test]
+    fn flip_preserves_perft_and_game_state() {
+        for fen in TEST_POSITIONS {
+            let b = Board::from_fen(fen).unwrap();
+            let f = b.flipped();
+            assert_eq!(f.game_state(), b.game_state(), "game state: {fen}");
+            assert_eq!(
+                f.is_in_check(f.side_to_move()),
+                b.is_in_check(b.side_to_move()),
+                "check: {fen}"
+            );
+            assert_eq!(perft(&f, legal_depth(&b)), perft(&b, legal_depth(&b)), "perft: {fen}");
+        }
+    }
+
+    #[test]
+    fn flipped_moves_match_flipped_board() {
+        for fen in TEST_POSITIONS {
+            let b = Board::from_fen(fen).unwrap();
+            let mut expected: Vec<String> =
+                b.generate_legal_moves().iter().map(|m| m.flipped().to_uci()).collect();
+            let mut actual: Vec<String> =
+                b.flipped().generate_legal_moves().iter().map(Move::to_uci).collect();
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected, "{fen}");
+        }
+    }
+
+    #[test]
+    fn mirror_preserves_perft_without_castling() {
+        for fen in TEST_POSITIONS {
+            let mut b = Board::from_fen(fen).unwrap();
+            b.castling = CastlingRights::NONE;
+            let m = b.mirrored();
+            assert_eq!(m.game_state(), b.game_state(), "game state: {fen}");
+            assert_eq!(perft(&m, legal_depth(&b)), perft(&b, legal_depth(&b)), "perft: {fen}");
+            let mut expected: Vec<String> =
+                b.generate_legal_moves().iter().map(|mv| mv.mirrored().to_uci()).collect();
+            let mut actual: Vec<String> =
+                m.generate_legal_moves().iter().map(Move::to_uci).collect();
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected, "{fen}");
+        }
+    }
 }
@@ -0,0 +1,394 @@
+//! Polyglot opening book reader.
+//!
+//! A Polyglot `.bin` book is a flat array of 16-byte big-endian entries,
+//! sorted by position key:
+//!
+//! | Bytes    | Field    | Meaning                                         |
+//! |----------|----------|-------------------------------------------------|
+//! | `0..8`   | `key`    | [`Board::polyglot_key`] of the position         |
+//! | `8..10`  | `move`   | Encoded move (see below)                        |
+//! | `10..12` | `weight` | Relative frequency / preference of the move     |
+//! | `12..16` | `learn`  | Learning data; ignored by this reader           |
+//!
+//! Moves pack `to` in bits 0–5 and `from` in bits 6–11 (both as
+//! `rank * 8 + file`, the same indexing as [`Square`]) and the promotion
+//! piece in bits 12–14 (1 = knight … 4 = queen). Castling is written as the
+//! king capturing its own rook (`e1h1`), which [`decode_move`] translates to
+//! [`Move::castle`].
+//!
+//! Lookups binary-search the sorted entries, so a book is used in place
+//! without building an index.
+
+use std::path::Path;
+
+use crate::board::Board;
+use crate::rng::Rng;
+use crate::types::{Color, Move, MoveKind, PieceType, Square};
+
+/// Size of one book entry on disk.
+pub const ENTRY_LEN: usize = 16;
+
+/// One book entry: a move recommended in the position with the given key.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BookEntry {
+    /// Polyglot key of the position.
+    pub key: u64,
+    /// Move in Polyglot encoding; see [`decode_move`].
+    pub raw_move: u16,
+    /// Relative weight; entries with weight 0 are never picked.
+    pub weight: u16,
+    /// Learning data, preserved but unused.
+    pub learn: u32,
+}
+
+impl BookEntry {
+    /// Parses one 16-byte big-endian entry.
+    #[must_use]
+    pub const fn from_bytes(bytes: &[u8; ENTRY_LEN]) -> Self {
+        let [k0, k1, k2, k3, k4, k5, k6, k7, m0, m1, w0, w1, l0, l1, l2, l3] = *bytes;
+        Self {
+            key: u64::from_be_bytes([k0, k1, k2, k3, k4, k5, k6, k7]),
+            raw_move: u16::from_be_bytes([m0, m1]),
+            weight: u16::from_be_bytes([w0, w1]),
+            learn: u32::from_be_bytes([l0, l1, l2, l3]),
+        }
+    }
+
+    /// Serializes the entry in Polyglot's big-endian layout.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; ENTRY_LEN] {
+        let mut out = [0u8; ENTRY_LEN];
+        out[0..8].copy_from_slice(&self.key.to_be_bytes());
+        out[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        out[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        out[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        out
+    }
+}
+
+/// How [`Book::pick`] chooses among the book moves of a position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BookPick {
+    /// Always the highest-weighted move (first one on ties).
+    Best,
+    /// Random, with probability proportional to weight.
+    Weighted,
+    /// Random among all moves with non-zero weight.
+    Uniform,
+}
+
+/// An opening book loaded into memory.
+#[derive(Clone, Debug, Default)]
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    /// Parses a Polyglot book from its raw bytes.
+    ///
+    /// Returns `None` if the length is not a multiple of [`ENTRY_LEN`].
+    /// Entries are sorted by key if the file is not already sorted.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (chunks, rest) = bytes.as_chunks::<ENTRY_LEN>();
+        if !rest.is_empty() {
+            return None;
+        }
+        let mut entries: Vec<BookEntry> = chunks.iter().map(BookEntry::from_bytes).collect();
+        if !entries.is_sorted_by_key(|e| e.key) {
+            entries.sort_by_key(|e| e.key);
+        }
+        Some(Self { entries })
+    }
+
+    /// Builds a book from entries in any order.
+    #[must_use]
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> Self {
+        entries.sort_by_key(|e| e.key);
+        Self { entries }
+    }
+
+    /// Reads a Polyglot `.bin` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the file cannot be read, or
+    /// [`InvalidData`](std::io::ErrorKind::InvalidData) if its size is not a
+    /// multiple of [`ENTRY_LEN`].
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "not a Polyglot book")
+        })
+    }
+
+    /// Serializes the book in Polyglot format.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(BookEntry::to_bytes).collect()
+    }
+
+    /// Number of entries.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// `true` if the book has no entries.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All entries for a position key, in file order.
+    #[must_use]
+    pub fn entries_for(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|e| e.key < key);
+        let end = start + self.entries[start..].partition_point(|e| e.key == key);
+        &self.entries[start..end]
+    }
+
+    /// The legal book moves for `board` with their weights, in file order.
+    ///
+    /// Entries whose move is not legal in the position (key collisions or
+    /// corrupt books) are skipped.
+    #[must_use]
+    pub fn moves(&self, board: &Board) -> Vec<(Move, u16)> {
+        self.entries_for(board.polyglot_key())
+            .iter()
+            .filter_map(|e| decode_move(board, e.raw_move).map(|m| (m, e.weight)))
+            .collect()
+    }
+
+    /// Chooses a book move for `board`, or `None` if the position is not in
+    /// the book (or every entry has weight 0).
+    pub fn pick(&self, board: &Board, how: BookPick, rng: &mut Rng) -> Option<Move> {
+        let moves: Vec<(Move, u16)> =
+            self.moves(board).into_iter().filter(|&(_, w)| w > 0).collect();
+        match how {
+            BookPick::Best => {
+                let best = moves.iter().map(|&(_, w)| w).max()?;
+                moves.iter().find(|&&(_, w)| w == best).map(|&(m, _)| m)
+            }
+            BookPick::Weighted => {
+                let total: u64 = moves.iter().map(|&(_, w)| u64::from(w)).sum();
+                let mut r = rng.below(total);
+                for &(m, w) in &moves {
+                    if r < u64::from(w) {
+                        return Some(m);
+                    }
+                    r -= u64::from(w);
+                }
+                None
+            }
+            BookPick::Uniform => {
+                let i = usize::try_from(rng.below(moves.len() as u64)).ok()?;
+                moves.get(i).map(|&(m, _)| m)
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Move encoding
+// ---------------------------------------------------------------------------
+
+/// Polyglot promotion codes, indexed by the 3-bit field.
+const PROMOTION_CODES: [Option<PieceType>; 5] = [
+    None,
+    Some(PieceType::Knight),
+    Some(PieceType::Bishop),
+    Some(PieceType::Rook),
+    Some(PieceType::Queen),
+];
+
+/// Translates a Polyglot move into the matching legal move on `board`.
+///
+/// Returns `None` if the encoding is malformed or the move is not legal.
+#[must_use]
+pub fn decode_move(board: &Board, raw: u16) -> Option<Move> {
+    #[allow(clippy::cast_possible_truncation)]
+    let from = Square::from_index(((raw >> 6) & 0x3F) as u8);
+    #[allow(clippy::cast_possible_truncation)]
+    let mut to = Square::from_index((raw & 0x3F) as u8);
+    let promotion = *PROMOTION_CODES.get(usize::from((raw >> 12) & 0x7))?;
+
+    // King-takes-own-rook is Polyglot's castling notation.
+    let king_home = match board.side_to_move() {
+        Color::White => Square::new(4, 0),
+        Color::Black => Square::new(4, 7),
+    };
+    let is_king = board
+        .piece_at(from)
+        .is_some_and(|p| p.piece_type() == PieceType::King && p.color() == board.side_to_move());
+    if is_king && from == king_home && to.rank() == from.rank() {
+        match to.file() {
+            7 => to = Square::new(6, from.rank()),
+            0 => to = Square::new(2, from.rank()),
+            _ => {}
+        }
+    }
+
+    board.generate_legal_moves().into_iter().find(|m| {
+        m.from() == from && m.to() == to && m.promotion_piece() == promotion
+    })
+}
+
+/// Encodes a move in Polyglot format. Castling must be given as
+/// [`MoveKind::Castle`] (as produced by the move generator).
+#[must_use]
+pub fn encode_move(mv: Move) -> u16 {
+    let from = u16::from(mv.from().raw());
+    let mut to = u16::from(mv.to().raw());
+    if mv.kind() == MoveKind::Castle {
+        let rank_base = to & !7;
+        to = if mv.to().file() == 6 { rank_base + 7 } else { rank_base };
+    }
+    let promotion = match mv.promotion_piece() {
+        Some(PieceType::Knight) => 1,
+        Some(PieceType::Bishop) => 2,
+        Some(PieceType::Rook) => 3,
+        Some(PieceType::Queen) => 4,
+        _ => 0,
+    };
+    (promotion << 12) | (from << 6) | to
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(board: &Board, uci: &str, weight: u16) -> BookEntry {
+        let mv = board
+            .generate_legal_moves()
+            .into_iter()
+            .find(|m| m.to_uci() == uci)
+            .unwrap_or_else(|| panic!("{uci} not legal"));
+        BookEntry { key: board.polyglot_key(), raw_move: encode_move(mv), weight, learn: 0 }
+    }
+
+    fn sample_book() -> Book {
+        let start = Board::new();
+        let mut after_e4 = Board::new();
+        after_e4.make_move(Move::from_uci("e2e4").unwrap());
+        let castle = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        Book::from_entries(vec![
+            entry(&start, "e2e4", 60),
+            entry(&start, "d2d4", 30),
+            entry(&start, "c2c4", 10),
+            entry(&start, "a2a3", 0),
+            entry(&after_e4, "c7c5", 5),
+            entry(&castle, "e1g1", 1),
+            entry(&castle, "e1c1", 1),
+        ])
+    }
+
+    #[test]
+    fn entry_byte_layout() {
+        let e = BookEntry { key: 0x0102_0304_0506_0708, raw_move: 0x090A, weight: 0x0B0C, learn: 0x0D0E_0F10 };
+        let bytes = e.to_bytes();
+        assert_eq!(bytes, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+        assert_eq!(BookEntry::from_bytes(&bytes), e);
+    }
+
+    #[test]
+    fn from_bytes_round_trip_and_validation() {
+        let book = sample_book();
+        let bytes = book.to_bytes();
+        assert_eq!(bytes.len(), book.len() * ENTRY_LEN);
+        let back = Book::from_bytes(&bytes).unwrap();
+        assert_eq!(back.entries, book.entries);
+        assert!(Book::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(Book::from_bytes(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unsorted_file_is_sorted_on_load() {
+        let book = sample_book();
+        let mut reversed: Vec<u8> = Vec::new();
+        for e in book.entries.iter().rev() {
+            reversed.extend_from_slice(&e.to_bytes());
+        }
+        let loaded = Book::from_bytes(&reversed).unwrap();
+        assert_eq!(loaded.moves(&Board::new()).len(), 4);
+    }
+
+    #[test]
+    fn lookup_by_position() {
+        let book = sample_book();
+        let moves: Vec<String> = book.moves(&Board::new()).iter().map(|(m, _)| m.to_uci()).collect();
+        assert_eq!(moves, ["e2e4", "d2d4", "c2c4", "a2a3"]);
+        assert!(book.moves(&Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn castling_uses_king_takes_rook() {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let short = board.generate_legal_moves().into_iter().find(|m| m.to_uci() == "e1g1").unwrap();
+        assert_eq!(encode_move(short), (4 << 6) | 7);
+        let moves = sample_book().moves(&board);
+        assert_eq!(moves.len(), 2);
+        assert!(moves.iter().all(|(m, _)| m.kind() == MoveKind::Castle));
+        assert_eq!(decode_move(&board, (4 << 6) | 7), Some(short));
+    }
+
+    #[test]
+    fn promotion_encoding() {
+        let board = Board::from_fen("k7/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let e7 = u16::from(Square::from_algebraic("e7").unwrap().raw());
+        let e8 = u16::from(Square::from_algebraic("e8").unwrap().raw());
+        let mv = decode_move(&board, (4 << 12) | (e7 << 6) | e8).unwrap();
+        assert_eq!(mv.to_uci(), "e7e8q");
+        assert_eq!(encode_move(mv), (4 << 12) | (e7 << 6) | e8);
+        assert_eq!(decode_move(&board, (1 << 12) | (e7 << 6) | e8).unwrap().to_uci(), "e7e8n");
+        // Invalid promotion code
+        assert!(decode_move(&board, (7 << 12) | (e7 << 6) | e8).is_none());
+    }
+
+    #[test]
+    fn illegal_book_moves_are_skipped() {
+        let board = Board::new();
+        let bogus = BookEntry {
+            key: board.polyglot_key(),
+            raw_move: (u16::from(Square::from_algebraic("e2").unwrap().raw()) << 6)
+                | u16::from(Square::from_algebraic("e5").unwrap().raw()),
+            weight: 100,
+            learn: 0,
+        };
+        let book = Book::from_entries(vec![bogus]);
+        assert!(book.moves(&board).is_empty());
+        assert!(book.pick(&board, BookPick::Best, &mut Rng::new(0)).is_none());
+    }
+
+    #[test]
+    fn pick_modes() {
+        let book = sample_book();
+        let board = Board::new();
+        let mut rng = Rng::new(7);
+        assert_eq!(book.pick(&board, BookPick::Best, &mut rng).unwrap().to_uci(), "e2e4");
+
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..2_000 {
+            let m = book.pick(&board, BookPick::Weighted, &mut rng).unwrap();
+            *counts.entry(m.to_uci()).or_insert(0) += 1;
+        }
+        assert!(!counts.contains_key("a2a3"), "zero-weight moves are never picked");
+        assert!(counts["e2e4"] > counts["d2d4"] && counts["d2d4"] > counts["c2c4"]);
+
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..200 {
+            seen.insert(book.pick(&board, BookPick::Uniform, &mut rng).unwrap().to_uci());
+        }
+        assert_eq!(seen.len(), 3);
+    }
+
+    #[test]
+    fn open_reads_file() {
+        let path = std::env::temp_dir().join(format!("chess-engine-book-{}.bin", std::process::id()));
+        std::fs::write(&path, sample_book().to_bytes()).unwrap();
+        let book = Book::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(book.len(), 7);
+        assert!(Book::open(&path).is_err());
+    }
+}
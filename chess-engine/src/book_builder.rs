@@ -0,0 +1,300 @@
+//! Builds Polyglot opening books from PGN game collections.
+//!
+//! [`BookBuilder`] replays each game's main line for the first N plies and
+//! accumulates, per `(position, move)`, how often the move was played and
+//! how the game ended for the side that played it. [`BookBuilder::build`]
+//! turns those statistics into a [`Book`] that can be written as a standard
+//! Polyglot `.bin` file and read back by [`crate::book`] or any other engine.
+//!
+//! The default weight of a move is `2 × wins + draws`, Polyglot's own
+//! convention; [`BookBuilder::result_weights`] changes the points per result.
+//! Weights are scaled down per position when they would overflow `u16`.
+
+use std::collections::HashMap;
+
+use crate::book::{encode_move, Book, BookEntry};
+use crate::pgn::PgnGame;
+use crate::types::Color;
+
+/// Outcome counts for one move in one position, from the mover's view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MoveStats {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MoveStats {
+    /// Total games in which the move was played.
+    #[must_use]
+    pub const fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+/// Accumulates move statistics from games and produces a [`Book`].
+#[derive(Clone, Debug)]
+pub struct BookBuilder {
+    max_plies: usize,
+    player: Option<String>,
+    min_games: u32,
+    points: [u64; 3],
+    stats: HashMap<(u64, u16), MoveStats>,
+    games_used: usize,
+}
+
+impl BookBuilder {
+    /// A builder that records the first `max_plies` half-moves of each game.
+    #[must_use]
+    pub fn new(max_plies: usize) -> Self {
+        Self {
+            max_plies,
+            player: None,
+            min_games: 1,
+            points: [2, 1, 0],
+            stats: HashMap::new(),
+            games_used: 0,
+        }
+    }
+
+    /// Only record moves made by the player with this exact `White`/`Black`
+    /// tag value. Games the player did not take part in are skipped.
+    #[must_use]
+    pub fn player(mut self, name: impl Into<String>) -> Self {
+        self.player = Some(name.into());
+        self
+    }
+
+    /// Drop moves played in fewer than `n` games.
+    #[must_use]
+    pub const fn min_games(mut self, n: u32) -> Self {
+        self.min_games = n;
+        self
+    }
+
+    /// Points a move earns per win, draw, and loss of the side that played it.
+    #[must_use]
+    pub const fn result_weights(mut self, win: u64, draw: u64, loss: u64) -> Self {
+        self.points = [win, draw, loss];
+        self
+    }
+
+    /// Number of games that contributed statistics.
+    #[must_use]
+    pub const fn games_used(&self) -> usize {
+        self.games_used
+    }
+
+    /// Records one game. Returns `false` (recording nothing) if the game has
+    /// no result, does not involve the filtered player, or its first
+    /// `max_plies` moves do not replay legally.
+    pub fn add_game(&mut self, game: &PgnGame) -> bool {
+        let Some(result) = game.result else {
+            return false;
+        };
+        let only = match &self.player {
+            None => None,
+            Some(p) if game.tag("White") == Some(p) => Some(Color::White),
+            Some(p) if game.tag("Black") == Some(p) => Some(Color::Black),
+            Some(_) => return false,
+        };
+        let Some(mut board) = game.start_board() else {
+            return false;
+        };
+
+        let mut recorded = Vec::with_capacity(self.max_plies);
+        for san in game.moves.iter().take(self.max_plies) {
+            let Some(mv) = board.parse_san(san) else {
+                return false;
+            };
+            let mover = board.side_to_move();
+            if only.is_none_or(|c| c == mover) {
+                recorded.push((board.polyglot_key(), encode_move(mv), mover));
+            }
+            board.apply_unchecked(mv);
+        }
+
+        for (key, raw, mover) in recorded {
+            let s = self.stats.entry((key, raw)).or_default();
+            match result.winner() {
+                None => s.draws += 1,
+                Some(c) if c == mover => s.wins += 1,
+                Some(_) => s.losses += 1,
+            }
+        }
+        self.games_used += 1;
+        true
+    }
+
+    /// Records every game in a PGN collection; returns how many were used.
+    pub fn add_pgn(&mut self, text: &str) -> usize {
+        crate::pgn::parse_pgn(text).iter().filter(|g| self.add_game(g)).count()
+    }
+
+    /// Statistics for one position key and Polyglot-encoded move.
+    #[must_use]
+    pub fn stats(&self, key: u64, raw_move: u16) -> Option<MoveStats> {
+        self.stats.get(&(key, raw_move)).copied()
+    }
+
+    /// Produces the book. Entries are sorted by key and, within a position,
+    /// by descending weight; moves with weight 0 are omitted.
+    #[must_use]
+    pub fn build(&self) -> Book {
+        let mut by_key: HashMap<u64, Vec<(u16, u64)>> = HashMap::new();
+        for (&(key, raw), s) in &self.stats {
+            if s.games() < self.min_games {
+                continue;
+            }
+            let weight = self.points[0] * u64::from(s.wins)
+                + self.points[1] * u64::from(s.draws)
+                + self.points[2] * u64::from(s.losses);
+            if weight > 0 {
+                by_key.entry(key).or_default().push((raw, weight));
+            }
+        }
+
+        let mut entries = Vec::with_capacity(self.stats.len());
+        for (key, mut moves) in by_key {
+            moves.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            let max = moves[0].1;
+            for (raw, weight) in moves {
+                let scaled = if max > u64::from(u16::MAX) {
+                    (weight * u64::from(u16::MAX) / max).max(1)
+                } else {
+                    weight
+                };
+                entries.push(BookEntry {
+                    key,
+                    raw_move: raw,
+                    weight: u16::try_from(scaled).unwrap_or(u16::MAX),
+                    learn: 0,
+                });
+            }
+        }
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+        Book::from_entries(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::book::BookPick;
+    use crate::rng::Rng;
+    use crate::types::Move;
+
+    const GAMES: &str = r#"
+[White "Alpha"]
+[Black "Beta"]
+[Result "1-0"]
+1. e4 e5 2. Nf3 Nc6 1-0
+
+[White "Beta"]
+[Black "Alpha"]
+[Result "0-1"]
+1. d4 d5 2. c4 e6 0-1
+
+[White "Alpha"]
+[Black "Beta"]
+[Result "1/2-1/2"]
+1. e4 c5 2. Nf3 d6 1/2-1/2
+
+[White "Gamma"]
+[Black "Beta"]
+[Result "0-1"]
+1. e4 e5 0-1
+
+[White "Gamma"]
+[Black "Beta"]
+1. e4 e5 *
+"#;
+
+    fn book_moves(book: &Book, board: &Board) -> Vec<(String, u16)> {
+        book.moves(board).into_iter().map(|(m, w)| (m.to_uci(), w)).collect()
+    }
+
+    #[test]
+    fn weights_by_result() {
+        let mut builder = BookBuilder::new(4);
+        assert_eq!(builder.add_pgn(GAMES), 4, "the unfinished game is skipped");
+        let book = builder.build();
+        // e4: win (2) + draw (1) + loss (0); d4: loss (0) → dropped.
+        assert_eq!(book_moves(&book, &Board::new()), [("e2e4".to_string(), 3)]);
+
+        let mut after_e4 = Board::new();
+        after_e4.make_move(Move::from_uci("e2e4").unwrap());
+        // e5: loss + win for Black (2); c5: draw (1).
+        assert_eq!(
+            book_moves(&book, &after_e4),
+            [("e7e5".to_string(), 2), ("c7c5".to_string(), 1)]
+        );
+        let stats = builder.stats(after_e4.polyglot_key(), encode_move(after_e4.parse_san("e5").unwrap()));
+        assert_eq!(stats, Some(MoveStats { wins: 1, draws: 0, losses: 1 }));
+    }
+
+    #[test]
+    fn ply_limit() {
+        let mut builder = BookBuilder::new(1);
+        builder.add_pgn(GAMES);
+        let book = builder.build();
+        let mut after_e4 = Board::new();
+        after_e4.make_move(Move::from_uci("e2e4").unwrap());
+        assert!(book.moves(&after_e4).is_empty());
+    }
+
+    #[test]
+    fn player_filter() {
+        let mut builder = BookBuilder::new(10).player("Alpha");
+        assert_eq!(builder.add_pgn(GAMES), 3);
+        let book = builder.build();
+        // Alpha's white games: e4 (win) and e4 (draw).
+        assert_eq!(book_moves(&book, &Board::new()), [("e2e4".to_string(), 3)]);
+        // Alpha's black game: 1. d4 d5 won; Beta's moves are not recorded.
+        let mut after_d4 = Board::new();
+        after_d4.make_move(Move::from_uci("d2d4").unwrap());
+        assert_eq!(book_moves(&book, &after_d4), [("d7d5".to_string(), 2)]);
+        let mut after_d5 = after_d4.clone();
+        after_d5.make_move(Move::from_uci("d7d5").unwrap());
+        assert!(book.moves(&after_d5).is_empty());
+    }
+
+    #[test]
+    fn custom_weights_and_min_games() {
+        let mut builder = BookBuilder::new(2).result_weights(1, 1, 1).min_games(2);
+        builder.add_pgn(GAMES);
+        let book = builder.build();
+        assert_eq!(book_moves(&book, &Board::new()), [("e2e4".to_string(), 3)]);
+        let mut after_e4 = Board::new();
+        after_e4.make_move(Move::from_uci("e2e4").unwrap());
+        assert_eq!(book_moves(&book, &after_e4), [("e7e5".to_string(), 2)]);
+    }
+
+    #[test]
+    fn weights_are_scaled_into_u16() {
+        let mut builder = BookBuilder::new(1);
+        let game = &crate::pgn::parse_pgn("[Result \"1-0\"]\n1. e4 1-0\n[Result \"1-0\"]\n1. d4 1-0")[..];
+        for _ in 0..40_000 {
+            builder.add_game(&game[0]);
+        }
+        builder.add_game(&game[1]);
+        let moves = book_moves(&builder.build(), &Board::new());
+        assert_eq!(moves[0], ("e2e4".to_string(), u16::MAX));
+        assert_eq!(moves[1], ("d2d4".to_string(), 1));
+    }
+
+    #[test]
+    fn built_book_round_trips_through_polyglot_bytes() {
+        let mut builder = BookBuilder::new(8);
+        builder.add_pgn(GAMES);
+        let book = builder.build();
+        let loaded = Book::from_bytes(&book.to_bytes()).unwrap();
+        assert_eq!(loaded.len(), book.len());
+        let mut rng = Rng::new(1);
+        assert_eq!(
+            loaded.pick(&Board::new(), BookPick::Best, &mut rng).unwrap().to_uci(),
+            "e2e4"
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Compact game encoding: each move is stored as its index in the position's
+//! legal move list.
+//!
+//! Two formats are provided:
+//!
+//! - **Byte codec** ([`encode_game`] / [`decode_game`]) — one byte per ply.
+//!   No position has more than 218 legal moves, so an index always fits.
+//! - **Bit-packed codec** ([`encode_game_packed`] / [`decode_game_packed`]) —
+//!   a LEB128 ply count followed by each index written with just enough bits
+//!   for that position's move count (`ceil(log2(n))`). Forced moves cost
+//!   nothing and a typical middlegame ply costs 5–6 bits. Games are limited
+//!   to [`MAX_PACKED_PLIES`] so a corrupt header cannot make the decoder loop
+//!   through a cycle of forced moves that read no input.
+//!
+//! Both formats are only meaningful together with the starting [`Board`];
+//! a game from the standard position needs no header beyond the moves.
+//!
+//! Indices refer to the legal moves sorted by `(from, to, promotion)`, not to
+//! [`Board::generate_legal_moves`] order, so stored games stay decodable if
+//! the generator's internal order ever changes.
+
+use crate::board::Board;
+use crate::types::Move;
+
+/// Legal moves in canonical codec order.
+fn ordered_moves(board: &Board) -> Vec<Move> {
+    let mut moves = board.generate_legal_moves();
+    moves.sort_unstable_by_key(|m| {
+        (m.from().raw(), m.to().raw(), m.promotion_piece().map_or(0, |pt| pt as u8 + 1))
+    });
+    moves
+}
+
+/// Index of `mv` in `moves`, matched by from/to/promotion like
+/// [`Board::make_move`] so UCI-parsed moves are accepted.
+fn index_of(moves: &[Move], mv: Move) -> Option<usize> {
+    moves.iter().position(|m| {
+        m.from() == mv.from() && m.to() == mv.to() && m.promotion_piece() == mv.promotion_piece()
+    })
+}
+
+/// Number of bits needed to store an index into a list of `n` moves.
+const fn index_bits(n: usize) -> u32 {
+    if n <= 1 { 0 } else { usize::BITS - (n - 1).leading_zeros() }
+}
+
+// ---------------------------------------------------------------------------
+// Byte codec
+// ---------------------------------------------------------------------------
+
+/// Encodes `moves` played from `start` as one byte per ply.
+///
+/// Returns `None` if any move is illegal in the position it is played from.
+#[must_use]
+pub fn encode_game(start: &Board, moves: &[Move]) -> Option<Vec<u8>> {
+    let mut board = start.clone();
+    let mut out = Vec::with_capacity(moves.len());
+    for &mv in moves {
+        let legal = ordered_moves(&board);
+        let idx = index_of(&legal, mv)?;
+        out.push(u8::try_from(idx).ok()?);
+        board.apply_unchecked(legal[idx]);
+    }
+    Some(out)
+}
+
+/// Decodes a game produced by [`encode_game`] from the same `start`.
+///
+/// Returns `None` if an index is out of range for its position.
+#[must_use]
+pub fn decode_game(start: &Board, bytes: &[u8]) -> Option<Vec<Move>> {
+    let mut board = start.clone();
+    let mut out = Vec::with_capacity(bytes.len());
+    for &b in bytes {
+        let mv = *ordered_moves(&board).get(usize::from(b))?;
+        board.apply_unchecked(mv);
+        out.push(mv);
+    }
+    Some(out)
+}
+
+// ---------------------------------------------------------------------------
+// Bit-packed codec
+// ---------------------------------------------------------------------------
+
+/// Appends bits least-significant first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit: u32,
+}
+
+impl BitWriter {
+    const fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes, bit: 0 }
+    }
+
+    fn write(&mut self, value: usize, bits: u32) {
+        for i in 0..bits {
+            if self.bit == 0 {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 != 0 {
+                *self.bytes.last_mut().expect("byte pushed above") |= 1 << self.bit;
+            }
+            self.bit = (self.bit + 1) % 8;
+        }
+    }
+}
+
+/// Reads bits in the order [`BitWriter`] wrote them.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, bits: u32) -> Option<usize> {
+        let mut value = 0;
+        for i in 0..bits {
+            let byte = *self.bytes.get(self.pos / 8)?;
+            if (byte >> (self.pos % 8)) & 1 != 0 {
+                value |= 1 << i;
+            }
+            self.pos += 1;
+        }
+        Some(value)
+    }
+}
+
+/// Longest game the bit-packed codec stores; far above any game the 75-move
+/// rule allows.
+pub const MAX_PACKED_PLIES: usize = 1 << 16;
+
+/// Encodes `moves` played from `start` with the variable-width bit format.
+///
+/// Returns `None` if any move is illegal in the position it is played from,
+/// or if there are more than [`MAX_PACKED_PLIES`] moves.
+#[must_use]
+pub fn encode_game_packed(start: &Board, moves: &[Move]) -> Option<Vec<u8>> {
+    if moves.len() > MAX_PACKED_PLIES {
+        return None;
+    }
+    let mut header = Vec::with_capacity(moves.len() / 2 + 3);
+    let mut n = moves.len();
+    loop {
+        #[allow(clippy::cast_possible_truncation)]
+        let low = (n & 0x7F) as u8;
+        n >>= 7;
+        if n == 0 {
+            header.push(low);
+            break;
+        }
+        header.push(low | 0x80);
+    }
+
+    let mut writer = BitWriter::new(header);
+    let mut board = start.clone();
+    for &mv in moves {
+        let legal = ordered_moves(&board);
+        let idx = index_of(&legal, mv)?;
+        writer.write(idx, index_bits(legal.len()));
+        board.apply_unchecked(legal[idx]);
+    }
+    Some(writer.bytes)
+}
+
+/// Decodes a game produced by [`encode_game_packed`] from the same `start`.
+///
+/// Returns `None` if the data is truncated, the ply count exceeds
+/// [`MAX_PACKED_PLIES`], an index is out of range, or there are bytes left
+/// over after the last ply.
+#[must_use]
+pub fn decode_game_packed(start: &Board, bytes: &[u8]) -> Option<Vec<Move>> {
+    let mut plies = 0usize;
+    let mut header_len = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        let shift = 7 * u32::try_from(i).ok()?;
+        let group = usize::from(b & 0x7F);
+        // Reject groups whose set bits would be shifted out, not just shifts past the width.
+        plies |= group.checked_shl(shift).filter(|&v| v >> shift == group)?;
+        if plies > MAX_PACKED_PLIES {
+            return None;
+        }
+        if b & 0x80 == 0 {
+            header_len = i + 1;
+            break;
+        }
+    }
+    if header_len == 0 {
+        return None;
+    }
+
+    let mut reader = BitReader { bytes: &bytes[header_len..], pos: 0 };
+    let mut board = start.clone();
+    let mut out = Vec::with_capacity(plies.min(1024));
+    for _ in 0..plies {
+        let legal = ordered_moves(&board);
+        let idx = reader.read(index_bits(legal.len()))?;
+        let mv = *legal.get(idx)?;
+        board.apply_unchecked(mv);
+        out.push(mv);
+    }
+    if reader.pos.div_ceil(8) != reader.bytes.len() {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::TEST_POSITIONS;
+    use crate::types::GameState;
+
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    /// Plays up to `max_plies` uniformly random legal moves from `start`.
+    fn random_playout(start: &Board, seed: u64, max_plies: usize) -> Vec<Move> {
+        let mut state = seed | 1;
+        let mut board = start.clone();
+        let mut moves = Vec::new();
+        while moves.len() < max_plies && board.game_state() == GameState::InProgress {
+            let legal = board.generate_legal_moves();
+            #[allow(clippy::cast_possible_truncation)]
+            let mv = legal[(next_random(&mut state) % legal.len() as u64) as usize];
+            board.apply_unchecked(mv);
+            moves.push(mv);
+        }
+        moves
+    }
+
+    fn check_round_trip(start: &Board, moves: &[Move]) {
+        let bytes = encode_game(start, moves).unwrap();
+        assert_eq!(bytes.len(), moves.len());
+        assert_eq!(decode_game(start, &bytes).unwrap(), moves);
+
+        let packed = encode_game_packed(start, moves).unwrap();
+        assert!(packed.len() <= bytes.len() + 2);
+        assert_eq!(decode_game_packed(start, &packed).unwrap(), moves);
+    }
+
+    #[test]
+    fn empty_game() {
+        let b = Board::new();
+        assert_eq!(encode_game(&b, &[]).unwrap(), Vec::<u8>::new());
+        assert_eq!(encode_game_packed(&b, &[]).unwrap(), vec![0]);
+        assert_eq!(decode_game_packed(&b, &[0]).unwrap(), Vec::<Move>::new());
+    }
+
+    #[test]
+    fn accepts_uci_parsed_moves() {
+        // Castling and en passant parsed from UCI carry MoveKind::Normal.
+        let start = Board::from_fen("r3k2r/8/8/8/3pP3/8/8/R3K2R b KQkq e3 0 1").unwrap();
+        let ucis = ["d4e3", "e1g1", "e8c8"];
+        let moves: Vec<Move> = ucis.iter().map(|u| Move::from_uci(u).unwrap()).collect();
+        let decoded = decode_game(&start, &encode_game(&start, &moves).unwrap()).unwrap();
+        let back: Vec<String> = decoded.iter().map(Move::to_uci).collect();
+        assert_eq!(back, ucis);
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let b = Board::new();
+        let moves = [Move::from_uci("e2e5").unwrap()];
+        assert!(encode_game(&b, &moves).is_none());
+        assert!(encode_game_packed(&b, &moves).is_none());
+    }
+
+    #[test]
+    fn rejects_bad_data() {
+        let b = Board::new();
+        // Only 20 legal moves from the start position.
+        assert!(decode_game(&b, &[20]).is_none());
+        // Header promises two plies, data holds one.
+        assert!(decode_game_packed(&b, &[2, 0b0_0001]).is_none());
+        // Trailing garbage after the last ply.
+        assert!(decode_game_packed(&b, &[1, 0, 0]).is_none());
+        // Unterminated header.
+        assert!(decode_game_packed(&b, &[0x80]).is_none());
+        assert!(decode_game_packed(&b, &[]).is_none());
+        // Ply counts past the limit, including one whose high bits only a
+        // tenth header byte would carry.
+        assert!(decode_game_packed(&b, &[0x81, 0x80, 0x04]).is_none());
+        let mut header = [0x80; 10];
+        header[9] = 0x7E;
+        assert!(decode_game_packed(&b, &header).is_none());
+    }
+
+    #[test]
+    fn random_playouts_round_trip() {
+        for seed in 0..40 {
+            let moves = random_playout(&Board::new(), 0x9E37_79B9_7F4A_7C15 ^ seed, 300);
+            check_round_trip(&Board::new(), &moves);
+        }
+    }
+
+    #[test]
+    fn playouts_from_test_positions_round_trip() {
+        for (seed, fen) in TEST_POSITIONS.iter().enumerate() {
+            let start = Board::from_fen(fen).unwrap();
+            if start.is_in_check(start.side_to_move().opposite()) {
+                continue;
+            }
+            let moves = random_playout(&start, seed as u64 + 1, 60);
+            check_round_trip(&start, &moves);
+        }
+    }
+
+    /// Around a million plies — run with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore = "slow: run with cargo test --release -- --ignored"]
+    fn many_random_playouts_round_trip() {
+        for seed in 0..5_000 {
+            let moves = random_playout(&Board::new(), seed * 0x2545_F491_4F6C_DD1D + 1, 400);
+            check_round_trip(&Board::new(), &moves);
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! Self-play training data for evaluators.
+//!
+//! [`Datagen::play_game`] plays one game against itself: a few uniformly
+//! random opening moves, then a fixed-node search for every move. Each
+//! position searched becomes a [`Record`] of the position, the search score,
+//! the best move, and the game's final result, unless it is noisy — the side
+//! to move is in check, the best move is a capture or promotion, or a
+//! quiescence search would still capture something — or the score is a mate
+//! score. Scores and results are from White's side.
+//!
+//! Records are written as text, one `fen | result | score | move` line each:
+//!
+//! ```text
+//! rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2 | 0.5 | 34 | b8c6
+//! ```
+//!
+//! or in binary as [`RECORD_LEN`] (35) bytes each:
+//!
+//! | Bytes    | Content                                                        |
+//! |----------|----------------------------------------------------------------|
+//! | `0..30`  | The position, [packed](crate::packed)                          |
+//! | `30..32` | Score, little-endian `i16`                                     |
+//! | `32..34` | Best move, little-endian: `from \| to << 6 \| promotion << 12` |
+//! | `34`     | Result in half points (0, 1, or 2)                             |
+//!
+//! The promotion field is the [`PieceType`] discriminant of the promotion
+//! piece, or 0 for other moves.
+
+use crate::board::Board;
+use crate::packed::PACKED_LEN;
+use crate::rng::Rng;
+use crate::search::{mate_in, SearchLimits, Searcher};
+use crate::types::{Color, GameState, Move, MoveKind, PieceType, Square};
+
+/// Size in bytes of a binary record.
+pub const RECORD_LEN: usize = PACKED_LEN + 5;
+
+/// Plies after which a game is adjudicated.
+const MAX_GAME_PLIES: usize = 400;
+
+/// Search score that adjudicates an unfinished game as a win.
+const ADJUDICATION_MARGIN: i32 = 400;
+
+/// Openings scored beyond this after the random moves are played again.
+const MAX_OPENING_SCORE: i32 = 300;
+
+/// One searched position of a self-play game.
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub board: Board,
+    /// Search score in centipawns from White's side.
+    pub score: i32,
+    pub best_move: Move,
+    /// White's result in half points (0, 1, or 2).
+    pub result: u8,
+}
+
+impl Record {
+    /// The record as one `fen | result | score | move` line, without the
+    /// newline.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let result = ["0.0", "0.5", "1.0"][usize::from(self.result.min(2))];
+        format!("{} | {result} | {} | {}", self.board.to_fen(), self.score, self.best_move)
+    }
+
+    /// Parses a line written by [`to_text`](Self::to_text).
+    #[must_use]
+    pub fn from_text(line: &str) -> Option<Self> {
+        let mut fields = line.split('|').map(str::trim);
+        let board = Board::from_fen(fields.next()?)?;
+        let result = match fields.next()? {
+            "0.0" => 0,
+            "0.5" => 1,
+            "1.0" => 2,
+            _ => return None,
+        };
+        let score = fields.next()?.parse().ok()?;
+        let best_move = legal_move(&board, Move::from_uci(fields.next()?)?)?;
+        if fields.next().is_some() {
+            return None;
+        }
+        Some(Self { board, score, best_move, result })
+    }
+
+    /// Encodes the record into [`RECORD_LEN`] bytes (see the
+    /// [module docs](crate::datagen) for the layout). Scores are clamped to
+    /// the `i16` range.
+    #[must_use]
+    pub fn to_bytes(&self) -> Option<[u8; RECORD_LEN]> {
+        let mut out = [0u8; RECORD_LEN];
+        out[..PACKED_LEN].copy_from_slice(&self.board.to_bytes()?);
+        #[allow(clippy::cast_possible_truncation)]
+        let score = self.score.clamp(i16::MIN.into(), i16::MAX.into()) as i16;
+        out[30..32].copy_from_slice(&score.to_le_bytes());
+        let promotion = self.best_move.promotion_piece().map_or(0, |pt| pt as u16);
+        let mv = u16::from(self.best_move.from().raw())
+            | u16::from(self.best_move.to().raw()) << 6
+            | promotion << 12;
+        out[32..34].copy_from_slice(&mv.to_le_bytes());
+        out[34] = self.result;
+        Some(out)
+    }
+
+    /// Decodes a record produced by [`to_bytes`](Self::to_bytes).
+    ///
+    /// Returns `None` if `bytes` is not exactly [`RECORD_LEN`] long, the
+    /// position is invalid, the move is not legal in it, or the result is
+    /// out of range.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: &[u8; RECORD_LEN] = bytes.try_into().ok()?;
+        let board = Board::from_bytes(&bytes[..PACKED_LEN])?;
+        let score = i16::from_le_bytes([bytes[30], bytes[31]]).into();
+        let mv = u16::from_le_bytes([bytes[32], bytes[33]]);
+        #[allow(clippy::cast_possible_truncation)]
+        let square = |shift: u16| Square::from_index((mv >> shift & 0x3F) as u8);
+        let best_move = match mv >> 12 {
+            0 => Move::normal(square(0), square(6)),
+            pt => Move::promotion(square(0), square(6), promotion_piece(pt)?),
+        };
+        let best_move = legal_move(&board, best_move)?;
+        let result = bytes[34];
+        (result <= 2).then_some(Self { board, score, best_move, result })
+    }
+}
+
+fn promotion_piece(discriminant: u16) -> Option<PieceType> {
+    [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen]
+        .into_iter()
+        .find(|&pt| pt as u16 == discriminant)
+}
+
+/// The legal move of `board` with the from, to, and promotion of `mv`.
+fn legal_move(board: &Board, mv: Move) -> Option<Move> {
+    board.generate_legal_moves().into_iter().find(|legal| {
+        legal.from() == mv.from() && legal.to() == mv.to() && legal.promotion_piece() == mv.promotion_piece()
+    })
+}
+
+/// Plays self-play games and records their positions.
+pub struct Datagen {
+    searcher: Searcher,
+    rng: Rng,
+    nodes: u64,
+    random_plies: usize,
+}
+
+impl Datagen {
+    /// Searches `nodes` nodes per move, with 8 random opening plies.
+    /// `seed` makes the games reproducible.
+    #[must_use]
+    pub fn new(nodes: u64, seed: u64) -> Self {
+        Self { searcher: Searcher::new(), rng: Rng::new(seed), nodes, random_plies: 8 }
+    }
+
+    /// Plays `plies` uniformly random moves before searching.
+    #[must_use]
+    pub const fn random_plies(mut self, plies: usize) -> Self {
+        self.random_plies = plies;
+        self
+    }
+
+    /// Plays one game and returns its quiet positions.
+    ///
+    /// Games end by checkmate, stalemate, insufficient material, the
+    /// fifty-move rule, or threefold repetition, and are adjudicated by the
+    /// last search score after [`MAX_GAME_PLIES`].
+    pub fn play_game(&mut self) -> Vec<Record> {
+        self.searcher.clear();
+        let limits = SearchLimits::nodes(self.nodes);
+        let (mut board, mut keys) = self.opening();
+        let mut records = Vec::new();
+        let mut white_score = 0;
+        let result = loop {
+            match board.game_state() {
+                GameState::Checkmate => break if board.side_to_move() == Color::White { 0 } else { 2 },
+                GameState::Stalemate | GameState::Draw => break 1,
+                GameState::InProgress => {}
+            }
+            if keys.iter().filter(|&&k| k == board.polyglot_key()).count() >= 3 {
+                break 1;
+            }
+            if keys.len() > MAX_GAME_PLIES {
+                break match white_score {
+                    s if s >= ADJUDICATION_MARGIN => 2,
+                    s if s <= -ADJUDICATION_MARGIN => 0,
+                    _ => 1,
+                };
+            }
+            self.searcher.set_game_history(keys[..keys.len() - 1].to_vec());
+            let search = self.searcher.search(&board, limits);
+            let Some(mv) = search.best_move else {
+                break 1;
+            };
+            white_score = if board.side_to_move() == Color::White { search.score } else { -search.score };
+            if mate_in(search.score).is_none() && !self.is_noisy(&board, mv) {
+                records.push(Record { board: board.clone(), score: white_score, best_move: mv, result: 0 });
+            }
+            board.make_move(mv);
+            keys.push(board.polyglot_key());
+        };
+        for record in &mut records {
+            record.result = result;
+        }
+        records
+    }
+
+    /// A position after the random opening plies that is still in progress
+    /// and roughly balanced, with the keys of the positions so far.
+    fn opening(&mut self) -> (Board, Vec<u64>) {
+        loop {
+            let mut board = Board::new();
+            let mut keys = vec![board.polyglot_key()];
+            for _ in 0..self.random_plies {
+                let moves = board.generate_legal_moves();
+                if moves.is_empty() {
+                    break;
+                }
+                #[allow(clippy::cast_possible_truncation)]
+                let mv = moves[self.rng.below(moves.len() as u64) as usize];
+                board.make_move(mv);
+                keys.push(board.polyglot_key());
+            }
+            if board.game_state() != GameState::InProgress {
+                continue;
+            }
+            let score = self.searcher.search(&board, SearchLimits::nodes(self.nodes)).score;
+            if score.abs() <= MAX_OPENING_SCORE {
+                return (board, keys);
+            }
+        }
+    }
+
+    fn is_noisy(&mut self, board: &Board, best_move: Move) -> bool {
+        board.is_in_check(board.side_to_move())
+            || board.piece_at(best_move.to()).is_some()
+            || matches!(best_move.kind(), MoveKind::EnPassant | MoveKind::Promotion(_))
+            || !self.searcher.quiescence(board).1.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fen: &str, mv: &str, score: i32, result: u8) -> Record {
+        let board = Board::from_fen(fen).unwrap();
+        let best_move = legal_move(&board, Move::from_uci(mv).unwrap()).unwrap();
+        Record { board, score, best_move, result }
+    }
+
+    #[test]
+    fn text_and_binary_round_trip() {
+        for r in [
+            record("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4", 25, 1),
+            record("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 3 20", "e8c8", -140, 0),
+            record("8/3P1k2/8/8/8/8/5K2/8 w - - 0 60", "d7d8n", 900, 2),
+            record("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2", "e5d6", 12, 2),
+        ] {
+            let line = r.to_text();
+            assert_eq!(Record::from_text(&line).map(|r| r.to_text()), Some(line.clone()));
+            let bytes = r.to_bytes().unwrap();
+            assert_eq!(Record::from_bytes(&bytes).map(|r| r.to_text()), Some(line));
+        }
+        let r = record("4k3/8/8/8/8/8/8/Q3K3 w - - 0 1", "a1a8", 40_000, 2);
+        assert_eq!(Record::from_bytes(&r.to_bytes().unwrap()).unwrap().score, i32::from(i16::MAX));
+        assert_eq!(
+            record("4k3/8/8/8/8/8/8/4K3 w - - 0 1", "e1e2", 0, 1).to_text(),
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1 | 0.5 | 0 | e1e2"
+        );
+    }
+
+    #[test]
+    fn malformed_records_are_rejected() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        for line in [
+            format!("{start} | 0.5 | 25 | e2e5"),
+            format!("{start} | 0.7 | 25 | e2e4"),
+            format!("{start} | 0.5 | x | e2e4"),
+            format!("{start} | 0.5 | 25"),
+            format!("{start} | 0.5 | 25 | e2e4 | extra"),
+        ] {
+            assert!(Record::from_text(&line).is_none(), "{line}");
+        }
+        let mut bytes = record(start, "e2e4", 25, 1).to_bytes().unwrap();
+        assert!(Record::from_bytes(&bytes[..RECORD_LEN - 1]).is_none());
+        bytes[34] = 3;
+        assert!(Record::from_bytes(&bytes).is_none());
+        bytes[34] = 1;
+        bytes[32] ^= 1;
+        assert!(Record::from_bytes(&bytes).is_none());
+    }
+
+    #[test]
+    fn self_play_records_quiet_positions() {
+        let mut datagen = Datagen::new(300, 49).random_plies(6);
+        let records = datagen.play_game();
+        assert!(!records.is_empty());
+        let result = records[0].result;
+        assert!(result <= 2);
+        for r in &records {
+            assert_eq!(r.result, result);
+            assert!(!r.board.is_in_check(r.board.side_to_move()), "{}", r.to_text());
+            assert!(r.board.piece_at(r.best_move.to()).is_none(), "{}", r.to_text());
+            assert!(r.best_move.promotion_piece().is_none());
+            assert!(mate_in(r.score).is_none());
+            assert!(r.board.generate_legal_moves().contains(&r.best_move));
+        }
+        // The same seed plays the same game.
+        let text = |records: &[Record]| records.iter().map(Record::to_text).collect::<Vec<_>>();
+        assert_eq!(text(&Datagen::new(300, 49).random_plies(6).play_game()), text(&records));
+    }
+}
@@ -0,0 +1,371 @@
+//! Specialised evaluation of endgames the general terms misjudge.
+//!
+//! A registry keyed by material [`Signature`] maps endings such as KBNK,
+//! KRKP, and KQKP to functions that score them for the side with the extra
+//! material; each is registered under the flipped signature too, with the
+//! colours exchanged. Two families covering many signatures are recognised
+//! by their shape instead:
+//!
+//! - KXK, a bare king against at least a rook's worth of pieces: drive the
+//!   king to the edge and bring the attacking king close;
+//! - opposite-coloured bishops, which scale the endgame half of the general
+//!   evaluation down ([`Board::endgame_scale`]).
+//!
+//! Won endings score [`KNOWN_WIN`] plus material and progress, above any
+//! ordinary evaluation and below mate scores, so the search converts them
+//! without having to see the mate. [`Board::evaluate`] asks the registry
+//! first ([`Board::endgame_eval`]).
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::board::Board;
+use crate::eval::EvalParams;
+use crate::tablebase::Signature;
+use crate::types::{Color, PieceType, Square};
+
+/// Base score of an ending known to be won.
+pub const KNOWN_WIN: i32 = 10_000;
+
+/// [`Board::endgame_scale`] of an ending evaluated as usual.
+pub const SCALE_NORMAL: i32 = 64;
+
+/// Scores a position for `strong`, the side with the extra material.
+pub type EndgameFn = fn(&Board, Color) -> i32;
+
+/// Dark squares (a1, c1, …, h8).
+const DARK_SQUARES: u64 = 0xAA55_AA55_AA55_AA55;
+
+/// The first and eighth ranks.
+const BACK_RANKS: u64 = 0xFF00_0000_0000_00FF;
+
+/// Bonus for the kings' distance, closer is better (index = distance).
+const PUSH_CLOSE: [i32; 8] = [0, 0, 100, 80, 60, 40, 20, 10];
+
+/// Endings with a function of their own, the stronger side as White.
+const ENDGAMES: [(&str, EndgameFn); 3] = [("KBNK", kbnk), ("KRKP", krkp), ("KQKP", kqkp)];
+
+fn registry() -> &'static HashMap<Signature, (EndgameFn, Color)> {
+    static REGISTRY: OnceLock<HashMap<Signature, (EndgameFn, Color)>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut map = HashMap::new();
+        for (name, f) in ENDGAMES {
+            let signature = Signature::parse(name).expect("registered signatures are valid");
+            map.insert(signature.flipped(), (f, Color::Black));
+            map.insert(signature, (f, Color::White));
+        }
+        map
+    })
+}
+
+const fn value(pt: PieceType) -> i32 {
+    EvalParams::DEFAULT.material[pt as usize].eg
+}
+
+const fn count(board: &Board, color: Color, pt: PieceType) -> i32 {
+    board.pieces(color, pt).count_ones().cast_signed()
+}
+
+fn non_pawn_material(board: &Board, color: Color) -> i32 {
+    [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen]
+        .into_iter()
+        .map(|pt| count(board, color, pt) * value(pt))
+        .sum()
+}
+
+/// Higher the closer `sq` is to the edge, 120 in a corner.
+fn push_to_edge(sq: Square) -> i32 {
+    let file = sq.file().min(7 - sq.file());
+    let rank = sq.rank().min(7 - sq.rank());
+    20 * (6 - i32::from(file) - i32::from(rank))
+}
+
+fn push_close(a: Square, b: Square) -> i32 {
+    PUSH_CLOSE[usize::from(a.distance(b))]
+}
+
+const fn first_square(bb: u64) -> Square {
+    #[allow(clippy::cast_possible_truncation)]
+    Square::from_index(bb.trailing_zeros() as u8)
+}
+
+/// `sq` as seen by `color`: White's squares unchanged, Black's flipped.
+fn relative(sq: Square, color: Color) -> Square {
+    if color == Color::White { sq } else { sq.flipped() }
+}
+
+/// The defender is to move and has none, out of check.
+fn stalemated(board: &Board, weak: Color) -> bool {
+    board.side_to_move() == weak && !board.is_in_check(weak) && board.generate_legal_moves().is_empty()
+}
+
+/// Mating material against a bare king.
+fn kxk(board: &Board, strong: Color) -> i32 {
+    let weak = strong.opposite();
+    if stalemated(board, weak) {
+        return 0;
+    }
+    let strong_king = board.king_square(strong);
+    let weak_king = board.king_square(weak);
+    let mut score = non_pawn_material(board, strong)
+        + count(board, strong, PieceType::Pawn) * value(PieceType::Pawn)
+        + push_to_edge(weak_king)
+        + push_close(strong_king, weak_king);
+    let bishops = board.pieces(strong, PieceType::Bishop);
+    if count(board, strong, PieceType::Queen) + count(board, strong, PieceType::Rook) > 0
+        || (bishops != 0 && count(board, strong, PieceType::Knight) > 0)
+        || (bishops & DARK_SQUARES != 0 && bishops & !DARK_SQUARES != 0)
+    {
+        score += KNOWN_WIN;
+    }
+    score
+}
+
+/// Bishop and knight: mate only works in a corner of the bishop's colour,
+/// so the defending king is driven towards the nearer of those two.
+fn kbnk(board: &Board, strong: Color) -> i32 {
+    let weak = strong.opposite();
+    if stalemated(board, weak) {
+        return 0;
+    }
+    let weak_king = board.king_square(weak);
+    let corners = if board.pieces(strong, PieceType::Bishop) & DARK_SQUARES != 0 {
+        [Square::new(0, 0), Square::new(7, 7)]
+    } else {
+        [Square::new(0, 7), Square::new(7, 0)]
+    };
+    let corner_distance = corners
+        .iter()
+        .map(|c| weak_king.file().abs_diff(c.file()) + weak_king.rank().abs_diff(c.rank()))
+        .min()
+        .unwrap_or(0);
+    KNOWN_WIN
+        + value(PieceType::Bishop)
+        + value(PieceType::Knight)
+        + push_close(board.king_square(strong), weak_king)
+        + 20 * (14 - i32::from(corner_distance))
+}
+
+/// Rook against pawn: a win when the attacking king stops the pawn or the
+/// defending king is too far away to help it, close to a draw when king and
+/// pawn are far advanced.
+fn krkp(board: &Board, strong: Color) -> i32 {
+    let weak = strong.opposite();
+    // Seen from the side with the rook: the pawn runs towards rank 1.
+    let strong_king = relative(board.king_square(strong), strong);
+    let weak_king = relative(board.king_square(weak), strong);
+    let rook = relative(first_square(board.pieces(strong, PieceType::Rook)), strong);
+    let pawn = relative(first_square(board.pieces(weak, PieceType::Pawn)), strong);
+    let queening = Square::new(pawn.file(), 0);
+    let ahead = Square::new(pawn.file(), pawn.rank() - 1);
+    let distance = |a: Square, b: Square| i32::from(a.distance(b));
+    let strong_to_move = board.side_to_move() == strong;
+
+    let king_in_front = strong_king.file() == pawn.file() && strong_king.rank() < pawn.rank();
+    let defender_too_far = distance(weak_king, pawn) >= 3 + i32::from(!strong_to_move) && distance(weak_king, rook) >= 3;
+    if king_in_front || defender_too_far {
+        value(PieceType::Rook) - distance(strong_king, pawn)
+    } else if weak_king.rank() <= 2
+        && distance(weak_king, pawn) == 1
+        && strong_king.rank() >= 3
+        && distance(strong_king, pawn) > 2 + i32::from(strong_to_move)
+    {
+        80 - 8 * distance(strong_king, pawn)
+    } else {
+        200 - 8 * (distance(strong_king, ahead) - distance(weak_king, ahead) - distance(pawn, queening))
+    }
+}
+
+/// Queen against pawn: winning, except for a bishop or rook pawn on the
+/// seventh supported by its king, which draws.
+fn kqkp(board: &Board, strong: Color) -> i32 {
+    let weak = strong.opposite();
+    let strong_king = board.king_square(strong);
+    let weak_king = board.king_square(weak);
+    let pawn = first_square(board.pieces(weak, PieceType::Pawn));
+    let mut score = push_close(strong_king, weak_king);
+    if relative(pawn, weak).rank() != 6 || weak_king.distance(pawn) != 1 || !matches!(pawn.file(), 0 | 2 | 5 | 7) {
+        score += value(PieceType::Queen) - value(PieceType::Pawn);
+    }
+    score
+}
+
+impl Board {
+    /// The registry's score for this material, from the side to move's point
+    /// of view, or `None` if no specialised function applies.
+    #[must_use]
+    pub fn endgame_eval(&self) -> Option<i32> {
+        let (score, strong) = self.endgame_score()?;
+        Some(if self.side_to_move() == strong { score } else { -score })
+    }
+
+    fn endgame_score(&self) -> Option<(i32, Color)> {
+        // FEN allows pawns on the back ranks, which the registered functions assume away.
+        let pawns = self.pieces(Color::White, PieceType::Pawn) | self.pieces(Color::Black, PieceType::Pawn);
+        if self.occupied().count_ones() <= 4 && pawns & BACK_RANKS == 0 {
+            if let Some(&(f, strong)) = registry().get(&Signature::of(self)) {
+                return Some((f(self, strong), strong));
+            }
+        }
+        [Color::White, Color::Black].into_iter().find_map(|strong| {
+            let weak = strong.opposite();
+            let bare = self.occupancy(weak) == self.pieces(weak, PieceType::King);
+            (bare && non_pawn_material(self, strong) >= value(PieceType::Rook)).then(|| (kxk(self, strong), strong))
+        })
+    }
+
+    /// Factor, out of [`SCALE_NORMAL`], for the endgame half of the general
+    /// evaluation: lower for drawish material. With a bishop each on
+    /// opposite colours and otherwise only pawns, an extra pawn or two
+    /// rarely wins, so only passed pawns lift the factor; other pieces on
+    /// the board dampen the effect.
+    #[must_use]
+    pub fn endgame_scale(&self) -> i32 {
+        let white = self.pieces(Color::White, PieceType::Bishop);
+        let black = self.pieces(Color::Black, PieceType::Bishop);
+        if white.count_ones() != 1 || black.count_ones() != 1 || (white & DARK_SQUARES == 0) == (black & DARK_SQUARES == 0) {
+            return SCALE_NORMAL;
+        }
+        let only_bishops = [Color::White, Color::Black]
+            .into_iter()
+            .all(|c| non_pawn_material(self, c) == value(PieceType::Bishop));
+        if only_bishops {
+            let passed = self.passed_pawns(Color::White).count_ones() + self.passed_pawns(Color::Black).count_ones();
+            (16 + 4 * passed.cast_signed()).min(SCALE_NORMAL)
+        } else {
+            46
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::search::{mate_in, SearchLimits, Searcher};
+    use crate::types::Move;
+
+    fn board(fen: &str) -> Board {
+        Board::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn registry_covers_both_colours() {
+        for fen in [
+            "8/8/8/3k4/8/8/8/1BNK4 w - - 0 1",
+            "8/8/8/3k4/8/2p5/8/R2K4 b - - 0 1",
+            "8/8/8/3k4/8/8/2p5/Q2K4 w - - 0 1",
+            "8/8/8/3k4/8/8/8/R2K4 w - - 0 1",
+            "8/8/8/3k4/8/8/8/1BBK4 b - - 0 1",
+        ] {
+            let b = board(fen);
+            let score = b.endgame_eval().unwrap();
+            assert_eq!(b.flipped().endgame_eval(), Some(score), "{fen}");
+        }
+        assert_eq!(board("8/8/8/3k4/8/8/8/2NK4 w - - 0 1").endgame_eval(), None);
+        assert_eq!(board("8/8/8/3k4/8/8/8/1NNK4 w - - 0 1").endgame_eval().map(|s| s < KNOWN_WIN), Some(true));
+        assert_eq!(board(crate::board::tests::KIWIPETE).endgame_eval(), None);
+    }
+
+    #[test]
+    fn kxk_drives_to_the_edge() {
+        let centre = board("8/8/8/3k4/8/8/8/R2K4 w - - 0 1").endgame_eval().unwrap();
+        let edge = board("3k4/8/8/8/8/8/8/R2K4 w - - 0 1").endgame_eval().unwrap();
+        assert!(centre > KNOWN_WIN && edge > centre);
+        // Black to move and stalemated.
+        assert_eq!(board("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").endgame_eval(), Some(0));
+    }
+
+    #[test]
+    fn kbnk_prefers_the_bishops_corner() {
+        // Dark-squared bishop: a1 and h8 are the mating corners.
+        let right = board("7k/8/5K2/8/8/8/8/2B1N3 w - - 0 1").endgame_eval().unwrap();
+        let wrong = board("k7/8/2K5/8/8/8/8/2B1N3 w - - 0 1").endgame_eval().unwrap();
+        assert!(right > wrong && wrong > KNOWN_WIN);
+    }
+
+    #[test]
+    fn krkp_and_kqkp() {
+        // Rook king in front of the pawn: winning.
+        let won = board("8/8/8/8/3k4/8/2p5/2K1R3 w - - 0 1").endgame_eval().unwrap();
+        // King and pawn far advanced, attacking king far away: drawish.
+        let drawish = board("K7/8/8/8/8/8/1pk5/4R3 w - - 0 1").endgame_eval().unwrap();
+        assert!(won > 400 && drawish < 100, "{won} {drawish}");
+        // Queen against a bishop pawn on the seventh with its king: drawish.
+        let held = board("K7/8/8/8/8/8/1kp5/7Q w - - 0 1").endgame_eval().unwrap();
+        let lost = board("K7/8/8/8/8/8/1k1p4/7Q w - - 0 1").endgame_eval().unwrap();
+        assert!(held < 200 && lost > 700, "{held} {lost}");
+        // A pawn on a back rank is left to the general evaluation.
+        let queening = board("8/8/8/8/8/8/8/k1K1R2p w - - 0 1");
+        assert_eq!(queening.endgame_eval(), None);
+        assert!(Searcher::new().search(&queening, SearchLimits::depth(2)).best_move.is_some());
+    }
+
+    #[test]
+    fn opposite_bishops_scale_down() {
+        assert_eq!(board("4k3/5b2/8/8/8/8/P1P5/2B1K3 w - - 0 1").endgame_scale(), 16 + 4 * 2);
+        assert_eq!(board("4k3/6b1/8/8/8/8/P1P5/2B1K3 w - - 0 1").endgame_scale(), SCALE_NORMAL);
+        assert_eq!(board("4k3/r4b2/8/8/8/8/P1P5/R1B1K3 w - - 0 1").endgame_scale(), 46);
+        let same = board("4k3/6b1/8/8/8/8/P1P5/2B1K3 w - - 0 1").evaluate();
+        let opposite = board("4k3/5b2/8/8/8/8/P1P5/2B1K3 w - - 0 1").evaluate();
+        assert!(opposite < same, "{opposite} {same}");
+    }
+
+    /// Plays the strong side with a fixed-depth search against random
+    /// defence and returns the full moves it took to mate, if it did within
+    /// `bound`.
+    fn play_out(fen: &str, depth: u8, bound: usize, seed: u64) -> Option<usize> {
+        let mut b = board(fen);
+        let strong = b.side_to_move();
+        let mut searcher = Searcher::new();
+        let mut rng = Rng::new(seed);
+        let mut history = Vec::new();
+        for moves in 1..=bound {
+            searcher.set_game_history(history.clone());
+            let mv = searcher.search(&b, SearchLimits::depth(depth)).best_move?;
+            history.push(b.polyglot_key());
+            assert!(b.make_move(mv));
+            let replies: Vec<Move> = b.generate_legal_moves();
+            if replies.is_empty() {
+                return b.is_in_check(strong.opposite()).then_some(moves);
+            }
+            let reply = replies[usize::try_from(rng.below(replies.len() as u64)).unwrap()];
+            history.push(b.polyglot_key());
+            assert!(b.make_move(reply));
+        }
+        None
+    }
+
+    #[test]
+    fn kxk_mates_random_defence() {
+        for seed in 0..3 {
+            assert!(play_out("8/8/8/3k4/8/8/8/2Q1K3 w - - 0 1", 3, 20, seed).is_some(), "KQK seed {seed}");
+            assert!(play_out("8/8/8/3k4/8/8/8/R3K3 w - - 0 1", 3, 40, seed).is_some(), "KRK seed {seed}");
+            assert!(play_out("8/8/8/8/3K4/8/8/3k1q2 b - - 0 1", 3, 20, seed).is_some(), "KQK black seed {seed}");
+        }
+    }
+
+    #[test]
+    fn kbnk_mates_random_defence() {
+        for (fen, seed) in [
+            ("8/8/8/3k4/8/8/8/2B1KN2 w - - 0 1", 0),
+            ("8/8/8/3k4/8/8/8/3BKN2 w - - 0 1", 1),
+            ("2b1kn2/8/8/8/3K4/8/8/8 b - - 0 1", 2),
+        ] {
+            assert!(play_out(fen, 4, 60, seed).is_some(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn kqkp_and_krkp_convert_against_random_defence() {
+        for seed in 0..2 {
+            assert!(play_out("8/8/8/8/8/2k5/1p6/4K2Q w - - 0 1", 3, 40, seed).is_some(), "KQKP seed {seed}");
+            assert!(play_out("8/8/8/4k3/8/3p4/8/R3K3 w - - 0 1", 3, 50, seed).is_some(), "KRKP seed {seed}");
+        }
+    }
+
+    #[test]
+    fn mate_scores_stay_above_known_wins() {
+        assert!(mate_in(KNOWN_WIN + 2000).is_none());
+    }
+}
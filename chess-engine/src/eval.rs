@@ -0,0 +1,755 @@
+//! Static evaluation: tapered material, piece-square tables, and positional terms.
+//!
+//! Every term has a midgame and an endgame value ([`Score`]). The two totals
+//! are blended by game phase — 24 with all minor and major pieces on the
+//! board, 0 with only kings and pawns — so that, for example, the king seeks
+//! shelter in the middlegame and the centre in the endgame.
+//!
+//! Terms, each computed for White minus Black:
+//!
+//! - material and piece-square tables;
+//! - mobility: attacked squares not occupied by own pieces, relative to a
+//!   typical count for the piece type;
+//! - pawn structure: passed pawns by rank, isolated and doubled pawns;
+//! - king safety: pawn shield in front of the king and enemy attacks on the
+//!   squares around it;
+//! - the bishop pair.
+//!
+//! Endings with a specialised function in the [`endgame`](crate::endgame)
+//! registry are scored by it, and drawish material (opposite-coloured
+//! bishops) scales the endgame total down. King and pawn versus king is
+//! scored from the [`kpk`](crate::kpk)
+//! bitbase instead: 0 for a draw, and for a win the usual terms plus
+//! [`KPK_WIN_BONUS`], which keeps promoting worthwhile.
+//!
+//! All weights live in [`EvalParams`], so tuning tools can adjust them and
+//! weights can be loaded at run time from text or JSON
+//! ([`EvalParams::load`]); the hand-set [`EvalParams::DEFAULT`] is what
+//! [`Board::evaluate`] uses.
+
+use std::fmt::Write as _;
+
+use crate::board::Board;
+use crate::endgame::SCALE_NORMAL;
+use crate::types::{Color, Piece, PieceType, Square};
+
+/// A midgame/endgame pair of centipawn values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+/// Shorthand constructor for [`Score`].
+#[must_use]
+pub const fn s(mg: i32, eg: i32) -> Score {
+    Score { mg, eg }
+}
+
+impl std::ops::AddAssign for Score {
+    fn add_assign(&mut self, rhs: Self) {
+        self.mg += rhs.mg;
+        self.eg += rhs.eg;
+    }
+}
+
+impl std::ops::Sub for Score {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        s(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl std::ops::Mul<i32> for Score {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self {
+        s(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+/// Phase contribution of each piece type; the full starting set sums to 24.
+pub const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+/// Phase value of the starting position.
+pub const MAX_PHASE: i32 = 24;
+
+/// Added for the winning side of a won king and pawn versus king position.
+pub const KPK_WIN_BONUS: i32 = 400;
+
+/// Evaluation weights.
+///
+/// Piece-square tables are written as White sees the board — a8 first, h1
+/// last — and mirrored for Black.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalParams {
+    /// Base value per piece type (the king's is unused).
+    pub material: [Score; 6],
+    /// Piece-square bonus per piece type.
+    pub pst: [[Score; 64]; 6],
+    /// Per attacked square above (or below) the typical count, for knight,
+    /// bishop, rook, and queen.
+    pub mobility: [Score; 4],
+    /// Passed pawn bonus by rank, from the pawn owner's side (index 1 = 2nd rank).
+    pub passed_pawn: [Score; 8],
+    /// Per pawn with no friendly pawns on adjacent files.
+    pub isolated_pawn: Score,
+    /// Per pawn beyond the first on a file.
+    pub doubled_pawn: Score,
+    /// For owning both bishops.
+    pub bishop_pair: Score,
+    /// Per friendly pawn on the two ranks in front of the king, on its own
+    /// and adjacent files.
+    pub king_shield: Score,
+    /// Per square next to the king attacked by an enemy knight, bishop,
+    /// rook, or queen.
+    pub king_attack: [Score; 4],
+}
+
+/// Typical mobility for knight, bishop, rook, and queen; mobility terms score
+/// the difference from these.
+const MOBILITY_BASELINE: [i32; 4] = [4, 6, 7, 13];
+
+// Hand-set piece-square tables (centipawns, a8 first). The layouts follow
+// the well-known "simplified evaluation function"; the endgame tables for
+// pawns, rooks, and the king are specific to this engine.
+
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    80, 80, 80, 80, 80, 80, 80, 80,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    30, 30, 30, 30, 30, 30, 30, 30,
+    15, 15, 15, 15, 15, 15, 15, 15,
+     5,  5,  5,  5,  5,  5,  5,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_MG: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const ROOK_EG: [i32; 64] = [
+     5,  5,  5,  5,  5,  5,  5,  5,
+    10, 10, 10, 10, 10, 10, 10, 10,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+/// Pairs a midgame and an endgame table.
+const fn table(mg: [i32; 64], eg: [i32; 64]) -> [Score; 64] {
+    let mut out = [s(0, 0); 64];
+    let mut i = 0;
+    while i < 64 {
+        out[i] = s(mg[i], eg[i]);
+        i += 1;
+    }
+    out
+}
+
+impl EvalParams {
+    /// Hand-set starting weights.
+    pub const DEFAULT: Self = Self {
+        material: [s(100, 120), s(320, 300), s(330, 320), s(500, 530), s(900, 950), s(0, 0)],
+        pst: [
+            table(PAWN_MG, PAWN_EG),
+            table(KNIGHT, KNIGHT),
+            table(BISHOP, BISHOP),
+            table(ROOK_MG, ROOK_EG),
+            table(QUEEN, QUEEN),
+            table(KING_MG, KING_EG),
+        ],
+        mobility: [s(4, 4), s(5, 5), s(2, 4), s(1, 2)],
+        passed_pawn: [
+            s(0, 0), s(5, 10), s(5, 15), s(10, 25), s(20, 45), s(35, 75), s(60, 120), s(0, 0),
+        ],
+        isolated_pawn: s(-10, -15),
+        doubled_pawn: s(-10, -20),
+        bishop_pair: s(30, 50),
+        king_shield: s(12, 0),
+        king_attack: [s(-6, 0), s(-6, 0), s(-8, 0), s(-12, 0)],
+    };
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Mobility and king-attack entries, in array order.
+const MINOR_AND_MAJOR: [PieceType; 4] = [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen];
+
+impl EvalParams {
+    /// Every weight with its name, in a fixed order: `material.pawn`,
+    /// `pst.knight.e4`, `mobility.rook`, `passed_pawn.6` (by rank),
+    /// `isolated_pawn`, `doubled_pawn`, `bishop_pair`, `king_shield`, and
+    /// `king_attack.queen`.
+    pub fn named_mut(&mut self) -> Vec<(String, &mut Score)> {
+        let mut named = Vec::new();
+        for (pt, score) in PieceType::ALL.iter().zip(&mut self.material) {
+            named.push((format!("material.{pt}"), score));
+        }
+        for (pt, table) in PieceType::ALL.iter().zip(&mut self.pst) {
+            for (i, score) in table.iter_mut().enumerate() {
+                #[allow(clippy::cast_possible_truncation)]
+                let sq = Square::new(i as u8 % 8, 7 - i as u8 / 8);
+                named.push((format!("pst.{pt}.{sq}"), score));
+            }
+        }
+        for (pt, score) in MINOR_AND_MAJOR.iter().zip(&mut self.mobility) {
+            named.push((format!("mobility.{pt}"), score));
+        }
+        for (rank, score) in self.passed_pawn.iter_mut().enumerate() {
+            named.push((format!("passed_pawn.{}", rank + 1), score));
+        }
+        named.push(("isolated_pawn".to_string(), &mut self.isolated_pawn));
+        named.push(("doubled_pawn".to_string(), &mut self.doubled_pawn));
+        named.push(("bishop_pair".to_string(), &mut self.bishop_pair));
+        named.push(("king_shield".to_string(), &mut self.king_shield));
+        for (pt, score) in MINOR_AND_MAJOR.iter().zip(&mut self.king_attack) {
+            named.push((format!("king_attack.{pt}"), score));
+        }
+        named
+    }
+
+    /// The weights as text, one `name mg eg` line each (see
+    /// [`named_mut`](Self::named_mut)); [`from_text`](Self::from_text)
+    /// reads it back.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut params = self.clone();
+        let mut text = String::from("# name mg eg\n");
+        for (name, score) in params.named_mut() {
+            let _ = writeln!(text, "{name} {} {}", score.mg, score.eg);
+        }
+        text
+    }
+
+    /// Parses `name mg eg` lines over [`DEFAULT`](Self::DEFAULT), so a file
+    /// may set only some weights. Blank lines and `#` comments are skipped.
+    /// `None` for an unknown name or a malformed line.
+    #[must_use]
+    pub fn from_text(text: &str) -> Option<Self> {
+        let lines = text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#'));
+        Self::with_weights(lines.map(|line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [name, mg, eg] => Some((name, s(mg.parse().ok()?, eg.parse().ok()?))),
+            _ => None,
+        }))
+    }
+
+    /// The weights as a JSON object mapping each name to `[mg, eg]`;
+    /// [`from_json`](Self::from_json) reads it back.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut params = self.clone();
+        let lines: Vec<String> = params
+            .named_mut()
+            .into_iter()
+            .map(|(name, score)| format!("  \"{name}\": [{}, {}]", score.mg, score.eg))
+            .collect();
+        format!("{{\n{}\n}}\n", lines.join(",\n"))
+    }
+
+    /// Parses a JSON object of `"name": [mg, eg]` members over
+    /// [`DEFAULT`](Self::DEFAULT), like [`from_text`](Self::from_text).
+    #[must_use]
+    pub fn from_json(json: &str) -> Option<Self> {
+        let mut rest = json.trim().strip_prefix('{')?.strip_suffix('}')?.trim();
+        let mut weights = Vec::new();
+        while !rest.is_empty() {
+            let (name, after) = rest.strip_prefix('"')?.split_once('"')?;
+            let (pair, after) = after.trim_start().strip_prefix(':')?.trim_start().strip_prefix('[')?.split_once(']')?;
+            let (mg, eg) = pair.split_once(',')?;
+            weights.push(Some((name, s(mg.trim().parse().ok()?, eg.trim().parse().ok()?))));
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after.trim_start();
+                if rest.is_empty() {
+                    return None;
+                }
+            } else if !rest.is_empty() {
+                return None;
+            }
+        }
+        Self::with_weights(weights)
+    }
+
+    /// Parses either format: JSON if the text starts with `{`, otherwise
+    /// `name mg eg` lines.
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        if text.trim_start().starts_with('{') { Self::from_json(text) } else { Self::from_text(text) }
+    }
+
+    /// Reads a weights file in either format (see [`parse`](Self::parse)).
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if the file cannot be read, or
+    /// [`InvalidData`](std::io::ErrorKind::InvalidData) if it does not parse.
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid evaluation parameters"))
+    }
+
+    /// [`DEFAULT`](Self::DEFAULT) with the named weights replaced, or `None`
+    /// if any entry is `None` or names no weight.
+    fn with_weights<'a>(weights: impl IntoIterator<Item = Option<(&'a str, Score)>>) -> Option<Self> {
+        let mut params = Self::DEFAULT;
+        let mut named: std::collections::HashMap<String, &mut Score> = params.named_mut().into_iter().collect();
+        for (name, score) in weights.into_iter().collect::<Option<Vec<_>>>()? {
+            **named.get_mut(name)? = score;
+        }
+        drop(named);
+        Some(params)
+    }
+}
+
+/// Bitboard of file `f`.
+const fn file_mask(f: u8) -> u64 {
+    0x0101_0101_0101_0101 << f
+}
+
+/// Bitboard of rank `r`, or empty when off the board.
+const fn rank_mask(r: i32) -> u64 {
+    if 0 <= r && r < 8 { 0xFF << (8 * r) } else { 0 }
+}
+
+/// Bitboard of file `f` and its neighbours.
+const fn adjacent_files(f: u8) -> u64 {
+    let mut mask = file_mask(f);
+    if f > 0 {
+        mask |= file_mask(f - 1);
+    }
+    if f < 7 {
+        mask |= file_mask(f + 1);
+    }
+    mask
+}
+
+/// Squares on ranks strictly in front of `sq` from `color`'s point of view.
+const fn ranks_ahead(sq: Square, color: Color) -> u64 {
+    match color {
+        Color::White if sq.rank() == 7 => 0,
+        Color::White => !0u64 << (8 * (sq.rank() + 1)),
+        Color::Black => !(!0u64 << (8 * sq.rank())),
+    }
+}
+
+/// Rank of `sq` counted from `color`'s back rank (0..8).
+const fn relative_rank(sq: Square, color: Color) -> usize {
+    match color {
+        Color::White => sq.rank() as usize,
+        Color::Black => 7 - sq.rank() as usize,
+    }
+}
+
+/// Iterates the squares of a bitboard.
+fn squares(mut bb: u64) -> impl Iterator<Item = Square> {
+    std::iter::from_fn(move || {
+        (bb != 0).then(|| {
+            #[allow(clippy::cast_possible_truncation)]
+            let sq = Square::from_index(bb.trailing_zeros() as u8);
+            bb &= bb - 1;
+            sq
+        })
+    })
+}
+
+impl Board {
+    /// Game phase between 0 (pawn endgame) and [`MAX_PHASE`] (all pieces).
+    #[must_use]
+    pub fn phase(&self) -> i32 {
+        let mut phase = 0;
+        for pt in PieceType::ALL {
+            let count = (self.pieces(Color::White, pt) | self.pieces(Color::Black, pt)).count_ones();
+            phase += PHASE_WEIGHTS[pt as usize] * count.cast_signed();
+        }
+        phase.min(MAX_PHASE)
+    }
+
+    /// Static evaluation in centipawns from the side to move's point of view,
+    /// using [`EvalParams::DEFAULT`].
+    #[must_use]
+    pub fn evaluate(&self) -> i32 {
+        self.evaluate_with(&EvalParams::DEFAULT)
+    }
+
+    /// Static evaluation with custom weights.
+    #[must_use]
+    pub fn evaluate_with(&self, params: &EvalParams) -> i32 {
+        if let Some(score) = self.endgame_eval() {
+            return score;
+        }
+        let total = self.eval_side(Color::White, params) - self.eval_side(Color::Black, params);
+        let phase = self.phase();
+        let eg = total.eg * self.endgame_scale() / SCALE_NORMAL;
+        let mut score = (total.mg * phase + eg * (MAX_PHASE - phase)) / MAX_PHASE;
+        if let Some(win) = self.kpk_probe() {
+            let white_strong = self.pieces(Color::White, PieceType::Pawn) != 0;
+            score = match (win, white_strong) {
+                (false, _) => 0,
+                (true, true) => score.max(0) + KPK_WIN_BONUS,
+                (true, false) => score.min(0) - KPK_WIN_BONUS,
+            };
+        }
+        if self.side_to_move() == Color::White { score } else { -score }
+    }
+
+    /// All terms for one side, as a midgame/endgame pair.
+    fn eval_side(&self, us: Color, p: &EvalParams) -> Score {
+        let them = us.opposite();
+        let own = self.occupancy(us);
+        let mut score = Score::default();
+
+        for pt in PieceType::ALL {
+            for sq in squares(self.pieces(us, pt)) {
+                // Tables are laid out a8-first from White's side.
+                let idx = if us == Color::White { sq.flipped().index() } else { sq.index() };
+                score += p.material[pt as usize];
+                score += p.pst[pt as usize][idx];
+
+                if !matches!(pt, PieceType::Pawn | PieceType::King) {
+                    let i = pt as usize - 1;
+                    let reach = (self.attacks(Piece::new(pt, us), sq) & !own).count_ones();
+                    score += p.mobility[i] * (reach.cast_signed() - MOBILITY_BASELINE[i]);
+                }
+            }
+        }
+
+        score += self.pawn_structure(us, p);
+
+        if self.pieces(us, PieceType::Bishop).count_ones() >= 2 {
+            score += p.bishop_pair;
+        }
+
+        // King safety: pawn shield and attacks on the king's neighbourhood.
+        if let Some(king) = squares(self.pieces(us, PieceType::King)).next() {
+            let zone = self.attacks(Piece::new(PieceType::King, us), king);
+            let rank = i32::from(king.rank());
+            let step = if us == Color::White { 1 } else { -1 };
+            let near = (rank_mask(rank + step) | rank_mask(rank + 2 * step)) & adjacent_files(king.file());
+            let shield = (near & self.pieces(us, PieceType::Pawn)).count_ones();
+            score += p.king_shield * shield.cast_signed();
+
+            for (i, pt) in [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen]
+                .into_iter()
+                .enumerate()
+            {
+                for sq in squares(self.pieces(them, pt)) {
+                    let hits = (self.attacks(Piece::new(pt, them), sq) & zone).count_ones();
+                    score += p.king_attack[i] * hits.cast_signed();
+                }
+            }
+        }
+        score
+    }
+
+    /// `us`'s pawns with no enemy pawn ahead on their own or adjacent files.
+    pub(crate) fn passed_pawns(&self, us: Color) -> u64 {
+        let theirs = self.pieces(us.opposite(), PieceType::Pawn);
+        squares(self.pieces(us, PieceType::Pawn))
+            .filter(|&sq| theirs & adjacent_files(sq.file()) & ranks_ahead(sq, us) == 0)
+            .fold(0, |bb, sq| bb | sq.bitboard())
+    }
+
+    fn pawn_structure(&self, us: Color, p: &EvalParams) -> Score {
+        let ours = self.pieces(us, PieceType::Pawn);
+        let passed = self.passed_pawns(us);
+        let mut score = Score::default();
+
+        for f in 0..8 {
+            let on_file = (ours & file_mask(f)).count_ones();
+            if on_file > 1 {
+                score += p.doubled_pawn * (on_file - 1).cast_signed();
+            }
+        }
+
+        for sq in squares(ours) {
+            let f = sq.file();
+            if ours & (adjacent_files(f) & !file_mask(f)) == 0 {
+                score += p.isolated_pawn;
+            }
+            if passed & sq.bitboard() != 0 {
+                score += p.passed_pawn[relative_rank(sq, us)];
+            }
+        }
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::TEST_POSITIONS;
+
+    fn eval(fen: &str) -> i32 {
+        Board::from_fen(fen).unwrap().evaluate()
+    }
+
+    #[test]
+    fn start_position_is_balanced() {
+        assert_eq!(eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), 0);
+        assert_eq!(Board::new().phase(), MAX_PHASE);
+    }
+
+    #[test]
+    fn symmetric_under_color_flip() {
+        for fen in TEST_POSITIONS {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(board.evaluate(), board.flipped().evaluate(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn side_to_move_view() {
+        let white = eval("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let black = eval("4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+        assert!(white > 800);
+        assert_eq!(white, -black);
+    }
+
+    #[test]
+    fn phase_tapers_king_placement() {
+        // With queens on, the castled king is better; in a pawn ending the
+        // centralized one is.
+        let mg_castled = eval("rn1qk1nr/8/8/8/8/8/8/RN1Q1RK1 w - - 0 1");
+        let mg_central = eval("rn1qk1nr/8/8/8/4K3/8/8/RN1Q1R2 w - - 0 1");
+        assert!(mg_castled > mg_central);
+        let eg_castled = eval("4k3/8/8/8/8/8/8/6K1 w - - 0 1");
+        let eg_central = eval("4k3/8/8/8/4K3/8/8/8 w - - 0 1");
+        assert!(eg_central > eg_castled);
+        assert_eq!(Board::from_fen("4k3/pp6/8/8/8/8/PP6/4K3 w - - 0 1").unwrap().phase(), 0);
+    }
+
+    #[test]
+    fn passed_pawns_grow_with_rank() {
+        let p = EvalParams::DEFAULT;
+        let b = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let base = b.pawn_structure(Color::White, &p);
+        assert_eq!(base, Score::default());
+
+        let passed = |fen: &str| Board::from_fen(fen).unwrap().pawn_structure(Color::White, &p);
+        // Lone pawns are passed and isolated.
+        let on_4th = passed("4k3/8/8/8/3P4/8/8/4K3 w - - 0 1");
+        let on_6th = passed("4k3/8/3P4/8/8/8/8/4K3 w - - 0 1");
+        assert!(on_6th.eg > on_4th.eg);
+        // A black pawn on an adjacent file ahead stops it being passed.
+        let blocked = passed("4k3/8/4p3/8/3P4/8/8/4K3 w - - 0 1");
+        assert_eq!(blocked, p.isolated_pawn);
+        // Black's passers are counted from Black's side.
+        let black = Board::from_fen("4k3/8/8/8/8/3p4/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(black.pawn_structure(Color::Black, &p), on_6th);
+    }
+
+    #[test]
+    fn kpk_from_bitbase() {
+        // Drawn: the defender holds the opposition.
+        assert_eq!(eval("8/8/4k3/8/4K3/4P3/8/8 w - - 0 1"), 0);
+        // Won: the attacker has it.
+        let won = -eval("8/8/4k3/8/4K3/4P3/8/8 b - - 0 1");
+        assert!(won > KPK_WIN_BONUS);
+        assert_eq!(eval("8/8/4p3/4k3/8/4K3/8/8 w - - 0 1"), -won);
+        // A queen is still worth more than a won pawn.
+        assert!(eval("4Q3/8/4k3/8/4K3/8/8/8 b - - 0 1") < -won);
+    }
+
+    #[test]
+    fn doubled_and_isolated_pawns() {
+        let p = EvalParams::DEFAULT;
+        let b = Board::from_fen("4k3/3p4/3p4/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut expected = p.doubled_pawn;
+        expected += p.isolated_pawn * 2;
+        // Passed pawns only look at enemy pawns, so both count.
+        expected += p.passed_pawn[1];
+        expected += p.passed_pawn[2];
+        assert_eq!(b.pawn_structure(Color::Black, &p), expected);
+    }
+
+    #[test]
+    fn bishop_pair_bonus() {
+        let p = EvalParams { bishop_pair: s(1, 0), ..zero() };
+        let side = |fen: &str| Board::from_fen(fen).unwrap().eval_side(Color::White, &p).mg;
+        assert_eq!(side("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"), 1);
+        assert_eq!(side("4k3/8/8/8/8/8/8/2N1KB2 w - - 0 1"), 0);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut p = EvalParams::DEFAULT;
+        p.pst[PieceType::Knight as usize][36] = s(-7, 11);
+        p.king_attack[3] = s(-20, 3);
+        let text = p.to_text();
+        assert!(text.contains("pst.knight.e4 -7 11\n") && text.contains("king_attack.queen -20 3\n"));
+        assert_eq!(EvalParams::from_text(&text), Some(p));
+        assert_eq!(EvalParams::DEFAULT.clone().named_mut().len(), 6 + 6 * 64 + 4 + 8 + 4 + 4);
+
+        let partial = EvalParams::from_text("# tweak\n\nbishop_pair 1 2\n").unwrap();
+        assert_eq!(partial, EvalParams { bishop_pair: s(1, 2), ..EvalParams::DEFAULT });
+        assert_eq!(EvalParams::from_text("bishop_pairs 1 2"), None);
+        assert_eq!(EvalParams::from_text("bishop_pair 1"), None);
+        assert_eq!(EvalParams::from_text("bishop_pair 1 x"), None);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut p = EvalParams::DEFAULT;
+        p.material[PieceType::Rook as usize] = s(480, 560);
+        let json = p.to_json();
+        assert!(json.starts_with("{\n  \"material.pawn\": [100, 120],\n"));
+        assert_eq!(EvalParams::from_json(&json), Some(p.clone()));
+        assert_eq!(EvalParams::parse(&json), Some(p.clone()));
+        assert_eq!(EvalParams::parse(&p.to_text()), Some(p));
+
+        let partial = EvalParams::parse(" {\"bishop_pair\":[1,2], \"doubled_pawn\" : [ -3 , -4 ]}").unwrap();
+        assert_eq!(partial, EvalParams { bishop_pair: s(1, 2), doubled_pawn: s(-3, -4), ..EvalParams::DEFAULT });
+        assert_eq!(EvalParams::from_json("{}"), Some(EvalParams::DEFAULT));
+        for bad in [
+            "",
+            "{\"bishop_pair\": [1, 2],}",
+            "{\"bishop_pair\": [1]}",
+            "{\"bishop_pairs\": [1, 2]}",
+            "{\"bishop_pair\": [1, 2] \"doubled_pawn\": [1, 2]}",
+            "{\"bishop_pair\": 1}",
+        ] {
+            assert_eq!(EvalParams::from_json(bad), None, "{bad}");
+        }
+    }
+
+    /// Parameters with every weight zero.
+    fn zero() -> EvalParams {
+        EvalParams {
+            material: [Score::default(); 6],
+            pst: [[Score::default(); 64]; 6],
+            mobility: [Score::default(); 4],
+            passed_pawn: [Score::default(); 8],
+            isolated_pawn: Score::default(),
+            doubled_pawn: Score::default(),
+            bishop_pair: Score::default(),
+            king_shield: Score::default(),
+            king_attack: [Score::default(); 4],
+        }
+    }
+
+    #[test]
+    fn king_shield() {
+        let p = EvalParams { king_shield: s(1, 0), ..zero() };
+        let shield = |fen: &str| Board::from_fen(fen).unwrap().eval_side(Color::White, &p).mg;
+        assert_eq!(shield("6k1/8/8/8/8/8/5PPP/6K1 w - - 0 1"), 3);
+        assert_eq!(shield("6k1/8/8/8/8/5P1P/6P1/6K1 w - - 0 1"), 3);
+        assert_eq!(shield("6k1/8/8/8/5PPP/8/8/6K1 w - - 0 1"), 0);
+        assert_eq!(shield("6k1/8/8/8/8/8/PPP5/6K1 w - - 0 1"), 0);
+        // Black's shield is in front of it, towards rank 1.
+        let black = Board::from_fen("6k1/5ppp/8/8/8/8/8/6K1 w - - 0 1").unwrap();
+        assert_eq!(black.eval_side(Color::Black, &p).mg, 3);
+    }
+
+    #[test]
+    fn king_attacks() {
+        let p = EvalParams { king_attack: [s(1, 0); 4], ..zero() };
+        // The queen on e1 hits f1 and f2 next to the king.
+        let b = Board::from_fen("6k1/8/8/8/8/8/5PPP/4q1K1 w - - 0 1").unwrap();
+        assert_eq!(b.eval_side(Color::White, &p).mg, 2);
+        let far = Board::from_fen("6k1/8/8/8/8/8/5PPP/q5K1 w - - 0 1").unwrap();
+        assert_eq!(far.eval_side(Color::White, &p).mg, 1);
+    }
+
+    #[test]
+    fn custom_params() {
+        let mut p = EvalParams::DEFAULT;
+        p.material[PieceType::Queen as usize] = s(0, 0);
+        // Pawns keep it out of the KXK endgame function, which ignores params.
+        let b = Board::from_fen("4k3/7p/8/8/8/8/7P/3QK3 w - - 0 1").unwrap();
+        assert!(b.evaluate_with(&p) < 200);
+        assert!(b.evaluate() > 800);
+    }
+}
@@ -0,0 +1,270 @@
+//! King and pawn versus king bitbase.
+//!
+//! One bit per position says whether the side with the pawn wins. The
+//! bitbase is generated on first use (a fraction of a second in release
+//! builds) and then shared by every thread; [`Board::kpk_probe`] looks
+//! positions up and the evaluation uses it to score these endings exactly.
+//!
+//! Positions are normalised so that the pawn is White's and stands on files
+//! a–d, and indexed as
+//!
+//! ```text
+//! stm + 2 * (black_king + 64 * (white_king + 64 * pawn))
+//! ```
+//!
+//! where `stm` is 0 when the pawn's side is to move and `pawn` numbers the
+//! 24 squares a2–d7 as `(rank - 1) * 4 + file`. Generation marks illegal
+//! positions, immediate wins (a safe promotion) and immediate draws
+//! (stalemate, or the pawn lost), then repeats passes over the rest until
+//! nothing changes: the attacker wins if some move wins, the defender draws
+//! if some move draws, and a position whose every move is decided takes the
+//! other result. Promotion counts as a win unless the new queen is lost at
+//! once, which is exact for this material. Whatever stays unresolved is a
+//! draw.
+
+use std::sync::OnceLock;
+
+use crate::board::Board;
+use crate::types::{Color, PieceType};
+
+/// Pawn squares a2–d7.
+const PAWN_SQUARES: usize = 24;
+
+/// Number of positions: side to move, two kings, the pawn.
+const SIZE: usize = 2 * 64 * 64 * PAWN_SQUARES;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Result {
+    Invalid,
+    Unknown,
+    Draw,
+    Win,
+}
+
+const fn index(attacker_to_move: bool, black_king: usize, white_king: usize, pawn: usize) -> usize {
+    let pawn_idx = ((pawn >> 3) - 1) * 4 + (pawn & 7);
+    (!attacker_to_move as usize) + 2 * (black_king + 64 * (white_king + 64 * pawn_idx))
+}
+
+const fn distance(a: usize, b: usize) -> usize {
+    let files = (a & 7).abs_diff(b & 7);
+    let ranks = (a >> 3).abs_diff(b >> 3);
+    if files > ranks { files } else { ranks }
+}
+
+/// Squares a king on `sq` attacks.
+fn king_moves(sq: usize) -> impl Iterator<Item = usize> {
+    (0..64).filter(move |&to| distance(sq, to) == 1)
+}
+
+/// Whether a White pawn on `pawn` attacks `sq`.
+const fn pawn_attacks(pawn: usize, sq: usize) -> bool {
+    sq >> 3 == (pawn >> 3) + 1 && (sq & 7).abs_diff(pawn & 7) == 1
+}
+
+fn bitbase() -> &'static [u64] {
+    static BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+    BITBASE.get_or_init(generate)
+}
+
+fn generate() -> Vec<u64> {
+    let mut db = vec![Result::Invalid; SIZE];
+    for pawn in (8..56).filter(|sq| sq & 7 < 4) {
+        for wk in 0..64 {
+            for bk in 0..64 {
+                for attacker_to_move in [true, false] {
+                    db[index(attacker_to_move, bk, wk, pawn)] = initial(attacker_to_move, bk, wk, pawn);
+                }
+            }
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for pawn in (8..56).filter(|sq| sq & 7 < 4) {
+            for wk in 0..64 {
+                for bk in 0..64 {
+                    for attacker_to_move in [true, false] {
+                        let idx = index(attacker_to_move, bk, wk, pawn);
+                        if db[idx] == Result::Unknown {
+                            db[idx] = classify(&db, attacker_to_move, bk, wk, pawn);
+                            changed |= db[idx] != Result::Unknown;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut bits = vec![0u64; SIZE / 64];
+    for (i, result) in db.iter().enumerate() {
+        if *result == Result::Win {
+            bits[i / 64] |= 1 << (i % 64);
+        }
+    }
+    bits
+}
+
+/// The result that needs no lookahead, or `Unknown`.
+fn initial(attacker_to_move: bool, bk: usize, wk: usize, pawn: usize) -> Result {
+    if distance(wk, bk) <= 1 || wk == pawn || bk == pawn || (attacker_to_move && pawn_attacks(pawn, bk)) {
+        return Result::Invalid;
+    }
+    let queening = pawn + 8;
+    if attacker_to_move
+        && pawn >> 3 == 6
+        && wk != queening
+        && bk != queening
+        && (distance(bk, queening) > 1 || distance(wk, queening) == 1)
+    {
+        return Result::Win;
+    }
+    if !attacker_to_move {
+        let safe = |sq: usize| distance(sq, wk) > 1 && !pawn_attacks(pawn, sq);
+        if !king_moves(bk).any(safe) {
+            // No move: stalemate, since the side with the pawn just moved.
+            return Result::Draw;
+        }
+        if distance(bk, pawn) == 1 && distance(wk, pawn) > 1 {
+            return Result::Draw;
+        }
+    }
+    Result::Unknown
+}
+
+/// The result from the successors' results so far.
+fn classify(db: &[Result], attacker_to_move: bool, bk: usize, wk: usize, pawn: usize) -> Result {
+    let mut any_good = false;
+    let mut any_unknown = false;
+    let (good, bad) = if attacker_to_move { (Result::Win, Result::Draw) } else { (Result::Draw, Result::Win) };
+    let mut visit = |result: Result| {
+        any_good |= result == good;
+        any_unknown |= result == Result::Unknown;
+    };
+    if attacker_to_move {
+        for to in king_moves(wk) {
+            visit(db[index(false, bk, to, pawn)]);
+        }
+        if pawn >> 3 < 6 && pawn + 8 != wk && pawn + 8 != bk {
+            visit(db[index(false, bk, wk, pawn + 8)]);
+            if pawn >> 3 == 1 && pawn + 16 != wk && pawn + 16 != bk {
+                visit(db[index(false, bk, wk, pawn + 16)]);
+            }
+        }
+    } else {
+        for to in king_moves(bk) {
+            visit(db[index(true, to, wk, pawn)]);
+        }
+    }
+    if any_good {
+        good
+    } else if any_unknown {
+        Result::Unknown
+    } else {
+        bad
+    }
+}
+
+impl Board {
+    /// Whether the side with the pawn wins this king and pawn versus king
+    /// position, or `None` for any other material or a pawn on its first or
+    /// last rank. Castling rights and the fifty-move counter are ignored.
+    #[must_use]
+    pub fn kpk_probe(&self) -> Option<bool> {
+        if self.occupied().count_ones() != 3 {
+            return None;
+        }
+        let white_pawns = self.pieces(Color::White, PieceType::Pawn);
+        let black_pawns = self.pieces(Color::Black, PieceType::Pawn);
+        let (strong, pawns) = match (white_pawns.count_ones(), black_pawns.count_ones()) {
+            (1, 0) => (Color::White, white_pawns),
+            (0, 1) => (Color::Black, black_pawns),
+            _ => return None,
+        };
+        let square = |bb: u64| bb.trailing_zeros() as usize;
+        let mut pawn = square(pawns);
+        let mut wk = square(self.pieces(strong, PieceType::King));
+        let mut bk = square(self.pieces(strong.opposite(), PieceType::King));
+        if strong == Color::Black {
+            (pawn, wk, bk) = (pawn ^ 0b11_1000, wk ^ 0b11_1000, bk ^ 0b11_1000);
+        }
+        // FEN allows pawns on the back ranks, which the bitbase does not cover.
+        if !(1..=6).contains(&(pawn >> 3)) {
+            return None;
+        }
+        if pawn & 7 > 3 {
+            (pawn, wk, bk) = (pawn ^ 0b111, wk ^ 0b111, bk ^ 0b111);
+        }
+        let idx = index(self.side_to_move() == strong, bk, wk, pawn);
+        Some(bitbase()[idx / 64] >> (idx % 64) & 1 != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::rng::Rng;
+    use crate::tablebase::{Tablebases, Wdl};
+    use crate::types::{CastlingRights, Color, Piece, Square};
+
+    fn probe(fen: &str) -> Option<bool> {
+        Board::from_fen(fen).unwrap().kpk_probe()
+    }
+
+    #[test]
+    fn known_positions() {
+        // King in front of the pawn on the sixth rank wins either way.
+        assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), Some(true));
+        assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Some(true));
+        // Opposition in front of the pawn: the side to move decides.
+        assert_eq!(probe("8/8/4k3/8/4K3/4P3/8/8 w - - 0 1"), Some(false));
+        assert_eq!(probe("8/8/4k3/8/4K3/4P3/8/8 b - - 0 1"), Some(true));
+        // Stalemate with the pawn on the seventh.
+        assert_eq!(probe("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"), Some(false));
+        // A rook pawn with the defender in the corner.
+        assert_eq!(probe("k7/8/K7/P7/8/8/8/8 w - - 0 1"), Some(false));
+        // The same for Black's pawn, and for the h-file.
+        assert_eq!(probe("8/8/8/8/4p3/4k3/8/4K3 w - - 0 1"), Some(true));
+        assert_eq!(probe("8/8/8/8/4p3/4k3/8/4K3 b - - 0 1"), Some(true));
+        assert_eq!(probe("7k/8/7K/7P/8/8/8/8 b - - 0 1"), Some(false));
+        // Other material.
+        assert_eq!(probe("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), None);
+        assert_eq!(probe("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1"), None);
+        assert_eq!(probe("4k3/8/8/8/8/8/4P3/3NK3 w - - 0 1"), None);
+        // Pawns on the back ranks are not covered, and do not break the evaluation.
+        for fen in ["8/8/8/8/8/8/8/k1K4P w - - 0 1", "7P/8/8/8/8/8/8/k1K5 b - - 0 1", "K1k4p/8/8/8/8/8/8/8 w - - 0 1"] {
+            assert_eq!(probe(fen), None, "{fen}");
+            let _ = Board::from_fen(fen).unwrap().evaluate();
+        }
+    }
+
+    /// Random positions agree with the exact tablebase.
+    #[test]
+    fn agrees_with_tablebase() {
+        let mut tb = Tablebases::new();
+        tb.generate("KPK").unwrap();
+        let mut rng = Rng::new(46);
+        let mut checked = 0;
+        while checked < 5000 {
+            let square = |rng: &mut Rng| Square::from_index(u8::try_from(rng.below(64)).unwrap());
+            let (wk, bk, pawn) = (square(&mut rng), square(&mut rng), square(&mut rng));
+            if wk == bk || wk == pawn || bk == pawn || pawn.rank() == 0 || pawn.rank() == 7 {
+                continue;
+            }
+            let strong = if rng.below(2) == 0 { Color::White } else { Color::Black };
+            let stm = if rng.below(2) == 0 { Color::White } else { Color::Black };
+            let mut board = Board::empty();
+            board.put_piece(wk, Piece::new(crate::types::PieceType::King, strong));
+            board.put_piece(bk, Piece::new(crate::types::PieceType::King, strong.opposite()));
+            board.put_piece(pawn, Piece::new(crate::types::PieceType::Pawn, strong));
+            board.set_state(stm, CastlingRights::NONE, None, 0, 1);
+            let Some(exact) = board.probe_tablebase(&tb) else {
+                continue;
+            };
+            let wins = exact.wdl == if stm == strong { Wdl::Win } else { Wdl::Loss };
+            assert_eq!(board.kpk_probe(), Some(wins), "{}", board.to_fen());
+            checked += 1;
+        }
+    }
+}
@@ -5,16 +5,76 @@
 //! - [`types`] — Domain types: [`Square`](types::Square), [`Piece`](types::Piece),
 //!   [`Move`](types::Move), [`CastlingRights`](types::CastlingRights), etc.
 //! - [`board`] — All game logic: move generation, legality, application, game state.
+//! - [`zobrist`] — Polyglot-compatible Zobrist keys ([`Board::polyglot_key`](board::Board::polyglot_key)).
+//! - [`book`] — Polyglot `.bin` opening book reader.
+//! - [`book_builder`] — Builds Polyglot books from PGN collections.
+//! - [`san`] — Standard Algebraic Notation parsing and formatting.
+//! - [`pgn`] — Minimal PGN collection reader.
+//! - [`eval`] — Tapered static evaluation ([`Board::evaluate`](board::Board::evaluate)).
+//! - `nnue` — Efficiently updatable neural network evaluator, behind the
+//!   optional `nnue` feature (`768 → N → 1`, documented weight file format).
+//! - [`endgame`] — Registry of specialised endgame evaluators keyed by material signature.
+//! - [`kpk`] — King and pawn versus king win/draw bitbase ([`Board::kpk_probe`](board::Board::kpk_probe)).
+//! - [`mate`] — Exhaustive mate-in-N solver with key moves, solution trees, and cook detection.
+//! - [`problem`] — Stipulation-driven problem solver (`#N`, `=N`, `s#N`, `h#N`).
+//! - [`search`] — Iterative-deepening alpha-beta (PVS + quiescence) search.
+//! - [`tablebase`] — Retrograde distance-to-mate endgame tablebases for up to four pieces.
+//! - `syzygy` — Syzygy WDL/DTZ tablebase probing, behind the optional
+//!   `syzygy` feature (WDL in the search, DTZ ranking of root moves).
+//! - [`skill`] — Strength limiting by skill level or nominal Elo, with a calibration match harness.
+//! - [`timeman`] — Time manager turning a chess clock into soft and hard search limits.
+//! - [`tt`] — Transposition table used by the search.
+//! - [`tuner`] — Texel tuning of the evaluation weights against labelled positions.
+//! - `uci` — UCI protocol session behind the native `chess-engine` binary
+//!   (not built for `wasm32`).
+//! - `xboard` — `XBoard`/CECP protocol session, the binary's alternative to
+//!   UCI (not built for `wasm32`).
+//! - [`rng`] — Small deterministic PRNG for randomized features.
+//! - [`codec`] — Compact game storage as legal-move indices (byte or bit-packed).
+//! - [`datagen`] — Self-play training data: quiet positions with search scores, best moves, and results.
+//! - [`packed`] — Fixed-size 30-byte binary encoding of a [`Board`](board::Board).
 //! - [`wasm`] — WASM Component Model bindings via `wit_bindgen`.
+//! - `serde_impls` — `Serialize`/`Deserialize` for the public types, behind the
+//!   optional `serde` feature (squares as `"e4"`, moves as UCI, boards as FEN).
 //!
 //! The engine uses precomputed attack tables (knight, king, pawn, and ray attacks)
 //! initialized lazily via `OnceLock`. Move generation produces pseudo-legal moves,
 //! then filters for legality by testing each move against king safety.
 
 pub mod board;
+pub mod book;
+pub mod book_builder;
+pub mod codec;
+pub mod datagen;
+pub mod endgame;
+pub mod eval;
+pub mod kpk;
+pub mod mate;
+#[cfg(feature = "nnue")]
+pub mod nnue;
+pub mod packed;
+pub mod pgn;
+pub mod problem;
+pub mod rng;
+pub mod san;
+pub mod search;
+pub mod skill;
+#[cfg(feature = "syzygy")]
+pub mod syzygy;
+pub mod tablebase;
+pub mod timeman;
+pub mod tt;
+pub mod tuner;
 pub mod types;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod uci;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod xboard;
+#[cfg(feature = "serde")]
+mod serde_impls;
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
+pub mod zobrist;
 
 pub use board::Board;
 pub use types::{CastlingRights, Color, GameState, Move, MoveKind, Piece, PieceType, Square};
@@ -0,0 +1,28 @@
+//! Native engine on stdin/stdout: `XBoard`/CECP when the first command is
+//! `xboard`, UCI otherwise.
+//!
+//! ```text
+//! chess-engine
+//! ```
+
+use std::io::BufRead;
+
+use chess_engine::uci::Uci;
+use chess_engine::xboard::Xboard;
+
+fn main() {
+    let mut input = std::io::stdin().lock();
+    let mut first = String::new();
+    if input.read_line(&mut first).is_err() {
+        return;
+    }
+    if first.trim() == "xboard" {
+        let mut xboard = Xboard::new(std::io::stdout());
+        xboard.run(input);
+    } else {
+        let mut uci = Uci::new(std::io::stdout());
+        if uci.handle(&first) {
+            uci.run(input);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Exhaustive mate-in-N solver for problem verification.
+//!
+//! [`Board::solve_mate`] proves or refutes that the side to move can force
+//! checkmate within N of its own moves, using only the legal move generator —
+//! no evaluation, pruning heuristics, or depth extensions — so the answer is
+//! exact. A proof comes with every key move (more than one is a *cook*) and
+//! the solution tree: for each key, every defence, and for each defence the
+//! attacker's continuations that mate soonest (more than one is a *dual*).
+//!
+//! Results of sub-problems are cached by Zobrist key, so transpositions are
+//! solved once. The cost still grows exponentially with N; problems up to
+//! mate in 3 or 4 with ordinary material solve in seconds.
+
+use std::collections::HashMap;
+
+use crate::board::Board;
+use crate::types::Move;
+
+/// A move in a solution tree and the opponent's answers to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MateNode {
+    /// The move played.
+    pub mv: Move,
+    /// After an attacker move: every legal defence (empty if `mv` mates).
+    /// After a defence: the attacker moves that mate soonest.
+    pub replies: Vec<Self>,
+}
+
+/// A proven forced mate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MateSolution {
+    /// Length of the shortest forced mate, in attacker moves.
+    pub moves: u32,
+    /// Every first move that forces mate within the requested number of moves.
+    pub key_moves: Vec<Move>,
+    /// Solution tree, one root per key move, in `key_moves` order.
+    pub tree: Vec<MateNode>,
+}
+
+impl MateSolution {
+    /// More than one key move solves the problem.
+    #[must_use]
+    pub const fn is_cooked(&self) -> bool {
+        self.key_moves.len() > 1
+    }
+}
+
+/// Legal moves, with the position after each.
+pub(crate) fn legal_children(board: &Board) -> Vec<(Move, Board)> {
+    let us = board.side_to_move();
+    board
+        .generate_pseudo_legal()
+        .into_iter()
+        .filter_map(|mv| {
+            let mut child = board.clone();
+            child.apply_unchecked(mv);
+            (!child.is_in_check(us)).then_some((mv, child))
+        })
+        .collect()
+}
+
+/// Does the side to move have any legal move?
+fn has_legal_move(board: &Board) -> bool {
+    let us = board.side_to_move();
+    board.generate_pseudo_legal().into_iter().any(|mv| {
+        let mut child = board.clone();
+        child.apply_unchecked(mv);
+        !child.is_in_check(us)
+    })
+}
+
+pub(crate) fn is_checkmate(board: &Board) -> bool {
+    board.is_in_check(board.side_to_move()) && !has_legal_move(board)
+}
+
+/// What the attacker must bring about.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Goal {
+    /// Checkmate the defender.
+    Mate,
+    /// Stalemate the defender.
+    Stalemate,
+    /// Force the defender to checkmate the attacker (selfmate).
+    SelfMate,
+}
+
+/// Memoized proof search. Keys pair a position with the moves allowed.
+pub(crate) struct Solver {
+    goal: Goal,
+    cache: HashMap<(u64, u32), bool>,
+}
+
+impl Solver {
+    pub(crate) fn new(goal: Goal) -> Self {
+        Self { goal, cache: HashMap::new() }
+    }
+
+    /// Can the side to move force the goal within `n` moves?
+    fn attacker_wins(&mut self, board: &Board, n: u32) -> bool {
+        if n == 0 {
+            return false;
+        }
+        let key = (board.polyglot_key(), n);
+        if let Some(&known) = self.cache.get(&key) {
+            return known;
+        }
+        let wins = legal_children(board).iter().any(|(_, child)| self.move_wins(child, n));
+        self.cache.insert(key, wins);
+        wins
+    }
+
+    /// After an attacker move leading to `child`, is the goal forced within
+    /// the `n` moves (counting that one)?
+    fn move_wins(&mut self, child: &Board, n: u32) -> bool {
+        let in_check = child.is_in_check(child.side_to_move());
+        if self.goal != Goal::SelfMate && !has_legal_move(child) {
+            return in_check == (self.goal == Goal::Mate);
+        }
+        if self.goal != Goal::SelfMate && n == 1 {
+            return false;
+        }
+        let defences = legal_children(child);
+        if self.goal == Goal::SelfMate && defences.iter().all(|(_, reply)| is_checkmate(reply)) {
+            // Also fails when the defender has no move at all.
+            return !defences.is_empty();
+        }
+        // A selfmate defender never mates voluntarily.
+        n > 1
+            && defences
+                .iter()
+                .all(|(_, reply)| self.ends_in_goal(reply) || self.attacker_wins(reply, n - 1))
+    }
+
+    /// Does the defence leading to `reply` fulfil a selfmate on the spot?
+    fn ends_in_goal(&self, reply: &Board) -> bool {
+        self.goal == Goal::SelfMate && is_checkmate(reply)
+    }
+
+    /// Shortest forced goal within `n` moves.
+    fn shortest(&mut self, board: &Board, n: u32) -> Option<u32> {
+        (1..=n).find(|&k| self.attacker_wins(board, k))
+    }
+
+    /// The tree below attacker move `mv` (leading to `child`), which forces
+    /// the goal within `n` moves.
+    fn node(&mut self, mv: Move, child: &Board, n: u32) -> MateNode {
+        let mut replies = Vec::new();
+        for (defence, reply) in legal_children(child) {
+            let continuations = if self.ends_in_goal(&reply) {
+                Vec::new()
+            } else {
+                let k = self.shortest(&reply, n - 1).expect("defence of a proven line");
+                self.continuations(&reply, k)
+            };
+            replies.push(MateNode { mv: defence, replies: continuations });
+        }
+        MateNode { mv, replies }
+    }
+
+    /// Trees for every move forcing the goal within `n` moves.
+    fn continuations(&mut self, board: &Board, n: u32) -> Vec<MateNode> {
+        let mut nodes = Vec::new();
+        for (mv, child) in legal_children(board) {
+            if self.move_wins(&child, n) {
+                nodes.push(self.node(mv, &child, n));
+            }
+        }
+        nodes
+    }
+
+    /// Every key move forcing the goal within `n` moves, with its tree.
+    pub(crate) fn solve(&mut self, board: &Board, n: u32) -> Option<MateSolution> {
+        let moves = self.shortest(board, n)?;
+        let mut key_moves = Vec::new();
+        let mut tree = Vec::new();
+        for (mv, child) in legal_children(board) {
+            // Each key's tree follows that key's own fastest solution.
+            if let Some(k) = (1..=n).find(|&k| self.move_wins(&child, k)) {
+                key_moves.push(mv);
+                tree.push(self.node(mv, &child, k));
+            }
+        }
+        Some(MateSolution { moves, key_moves, tree })
+    }
+}
+
+impl Board {
+    /// Proves that the side to move can force checkmate within `n` moves,
+    /// returning every key move and the solution tree, or `None` if the
+    /// defence can avoid mate for `n` moves (or `n` is 0).
+    ///
+    /// Key moves include those that mate faster than `n`; the tree for each
+    /// key continues with the fastest mates after every defence.
+    #[must_use]
+    pub fn solve_mate(&self, n: u32) -> Option<MateSolution> {
+        Solver::new(Goal::Mate).solve(self, n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(fen: &str, n: u32) -> Option<Vec<String>> {
+        let solution = Board::from_fen(fen).unwrap().solve_mate(n)?;
+        Some(solution.key_moves.iter().map(Move::to_uci).collect())
+    }
+
+    /// Checks that an attacker node's tree really forces mate in `n`.
+    fn assert_forces_mate(board: &Board, node: &MateNode, n: u32) {
+        assert!(n > 0, "line too long at {}", node.mv);
+        let mut child = board.clone();
+        assert!(child.make_move(node.mv));
+        if node.replies.is_empty() {
+            assert!(is_checkmate(&child), "{} does not mate", node.mv);
+            return;
+        }
+        let defences: Vec<Move> = child.generate_legal_moves();
+        assert_eq!(node.replies.len(), defences.len(), "all defences listed after {}", node.mv);
+        for defence in &node.replies {
+            assert!(defences.contains(&defence.mv));
+            assert!(!defence.replies.is_empty());
+            let mut reply = child.clone();
+            reply.make_move(defence.mv);
+            for attack in &defence.replies {
+                assert_forces_mate(&reply, attack, n - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn unique_mate_in_one() {
+        let solution = Board::from_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1").unwrap().solve_mate(1).unwrap();
+        assert_eq!(solution.moves, 1);
+        assert_eq!(solution.key_moves, [Move::from_uci("h1h8").unwrap()]);
+        assert!(!solution.is_cooked());
+        assert!(solution.tree[0].replies.is_empty());
+    }
+
+    #[test]
+    fn cooked_mate_in_one() {
+        let solution = Board::from_fen("k7/8/1K6/8/8/8/8/6RR w - - 0 1").unwrap().solve_mate(1).unwrap();
+        assert!(solution.is_cooked());
+        let mut found: Vec<String> = solution.key_moves.iter().map(Move::to_uci).collect();
+        found.sort();
+        assert_eq!(found, ["g1g8", "h1h8"]);
+    }
+
+    #[test]
+    fn stalemate_is_not_mate() {
+        // Qf7 stalemates; Qf8 mates.
+        let found = keys("7k/8/6K1/8/8/8/8/5Q2 w - - 0 1", 1).unwrap();
+        assert!(found.contains(&"f1f8".to_string()));
+        assert!(!found.contains(&"f1f7".to_string()));
+    }
+
+    #[test]
+    fn refutes_missing_mates() {
+        assert_eq!(keys("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 2), None);
+        assert_eq!(keys("k7/8/1K6/8/8/8/8/7R w - - 0 1", 0), None);
+        // Black to move is mated, not mating.
+        assert_eq!(keys("k7/8/1K6/8/8/8/8/7R b - - 0 1", 2), None);
+    }
+
+    #[test]
+    fn mate_in_two_tree() {
+        let fen = "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1";
+        let board = Board::from_fen(fen).unwrap();
+        assert!(board.solve_mate(1).is_none());
+        let solution = board.solve_mate(2).unwrap();
+        assert_eq!(solution.moves, 2);
+        assert!(solution.key_moves.contains(&Move::from_uci("d5f6").unwrap()));
+        for node in &solution.tree {
+            assert_forces_mate(&board, node, 2);
+        }
+    }
+
+    #[test]
+    fn shorter_mates_count_within_n() {
+        let solution = Board::from_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1").unwrap().solve_mate(2).unwrap();
+        assert_eq!(solution.moves, 1);
+        assert!(solution.key_moves.contains(&Move::from_uci("h1h8").unwrap()));
+        assert!(solution.key_moves.len() > 1, "waiting moves also mate in two");
+        let board = Board::from_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1").unwrap();
+        for node in &solution.tree {
+            assert_forces_mate(&board, node, 2);
+        }
+    }
+}
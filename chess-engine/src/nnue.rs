@@ -0,0 +1,447 @@
+//! NNUE evaluation (optional `nnue` feature).
+//!
+//! A perspective network with architecture `768 → N → 1`:
+//!
+//! - **Inputs**: one feature per (piece colour relative to the perspective,
+//!   piece type, square), with squares flipped vertically for Black's
+//!   perspective — 2 × 6 × 64 = 768 features, index
+//!   `(relative_colour * 6 + piece_type) * 64 + relative_square`.
+//! - **Feature transformer**: each perspective has an [`Accumulator`] of `N`
+//!   `i16` sums: the bias plus the weight columns of its active features.
+//!   Making a move only adds and subtracts the columns of the pieces that
+//!   moved ([`Network::update`]) instead of recomputing from scratch.
+//! - **Output**: the side to move's accumulator, then the opponent's, each
+//!   clipped to `0..=QA` (`CReLU`), dotted with `2N` output weights; plus the
+//!   output bias, scaled by `SCALE / (QA * QB)` to centipawns.
+//!
+//! Natively on `x86_64` the vector loops use SSE2; every other target,
+//! including `wasm32`, uses the scalar loops. Both produce identical results.
+//!
+//! # File format
+//!
+//! All integers little-endian:
+//!
+//! | Bytes            | Content                                           |
+//! |------------------|---------------------------------------------------|
+//! | 4                | magic `b"CENN"`                                   |
+//! | 2                | format version, `1`                               |
+//! | 2                | hidden size `N` (a non-zero multiple of 16)       |
+//! | 768 × N × 2      | feature weights, `i16`, feature-major             |
+//! | N × 2            | feature biases, `i16`                             |
+//! | 2N × 2           | output weights, `i16` (side to move's half first) |
+//! | 4                | output bias, `i32`, in `QA * QB` units            |
+//!
+//! Weights are quantized with [`QA`] for the feature transformer and [`QB`]
+//! for the output layer.
+
+use std::path::Path;
+
+use crate::board::Board;
+use crate::types::{Color, Move, MoveKind, Piece, PieceType, Square};
+
+/// Number of input features.
+pub const INPUTS: usize = 768;
+
+/// Feature transformer quantization (and `CReLU` ceiling).
+pub const QA: i32 = 255;
+
+/// Output layer quantization.
+pub const QB: i32 = 64;
+
+/// Converts the network output to centipawns.
+pub const SCALE: i32 = 400;
+
+const MAGIC: &[u8; 4] = b"CENN";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 8;
+
+/// Hidden sizes must be a multiple of this (SIMD lane groups).
+const LANE_MULTIPLE: usize = 16;
+
+/// A loaded network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Network {
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_bias: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+/// Feature transformer outputs for both perspectives, indexed by [`Color`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Accumulator {
+    values: [Vec<i16>; 2],
+}
+
+/// Input feature of `piece` on `sq` as seen from `perspective`.
+fn feature(perspective: Color, piece: Piece, sq: Square) -> usize {
+    let (relative_color, relative_sq) = if perspective == Color::White {
+        (piece.color().index(), sq)
+    } else {
+        (piece.color().opposite().index(), sq.flipped())
+    };
+    (relative_color * 6 + piece.piece_type() as usize) * 64 + relative_sq.index()
+}
+
+/// Up to two pieces placed on or lifted from squares.
+type PieceDeltas = [Option<(Piece, Square)>; 2];
+
+/// Pieces removed from and added to the board by `mv`.
+fn deltas(board: &Board, mv: Move) -> (PieceDeltas, PieceDeltas) {
+    let us = board.side_to_move();
+    let Some(moving) = board.piece_at(mv.from()) else {
+        return ([None; 2], [None; 2]);
+    };
+    let placed = mv.promotion_piece().map_or(moving, |pt| Piece::new(pt, us));
+    let mut removed = [Some((moving, mv.from())), None];
+    let mut added = [Some((placed, mv.to())), None];
+
+    match mv.kind() {
+        MoveKind::EnPassant => {
+            let captured = Square::new(mv.to().file(), mv.from().rank());
+            removed[1] = Some((Piece::new(PieceType::Pawn, us.opposite()), captured));
+        }
+        MoveKind::Castle => {
+            let rank = mv.from().rank();
+            let (rook_from, rook_to) = if mv.to().file() == 6 { (7, 5) } else { (0, 3) };
+            let rook = Piece::new(PieceType::Rook, us);
+            removed[1] = Some((rook, Square::new(rook_from, rank)));
+            added[1] = Some((rook, Square::new(rook_to, rank)));
+        }
+        MoveKind::Normal | MoveKind::Promotion(_) => {
+            removed[1] = board.piece_at(mv.to()).map(|p| (p, mv.to()));
+        }
+    }
+    (removed, added)
+}
+
+impl Network {
+    /// Parses a network in the documented file format.
+    ///
+    /// Returns `None` if the magic, version, hidden size, or length is wrong.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (header, body) = bytes.split_at_checked(HEADER_LEN)?;
+        if &header[..4] != MAGIC || u16::from_le_bytes([header[4], header[5]]) != VERSION {
+            return None;
+        }
+        let hidden = usize::from(u16::from_le_bytes([header[6], header[7]]));
+        if hidden == 0 || hidden % LANE_MULTIPLE != 0 {
+            return None;
+        }
+        let shorts = INPUTS * hidden + hidden + 2 * hidden;
+        if body.len() != shorts * 2 + 4 {
+            return None;
+        }
+
+        let (shorts_bytes, bias_bytes) = body.split_at(shorts * 2);
+        let (pairs, _) = shorts_bytes.as_chunks::<2>();
+        let mut values = pairs.iter().map(|&b| i16::from_le_bytes(b));
+        let feature_weights: Vec<i16> = values.by_ref().take(INPUTS * hidden).collect();
+        let feature_bias: Vec<i16> = values.by_ref().take(hidden).collect();
+        let output_weights: Vec<i16> = values.collect();
+        let output_bias = i32::from_le_bytes(bias_bytes.try_into().ok()?);
+        Some(Self { hidden, feature_weights, feature_bias, output_weights, output_bias })
+    }
+
+    /// Reads a network file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if the file cannot be read, or
+    /// [`InvalidData`](std::io::ErrorKind::InvalidData) if it is not a valid network.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid NNUE file"))
+    }
+
+    /// Serializes the network in the documented file format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hidden size does not fit the format's `u16` field, which
+    /// [`from_bytes`](Self::from_bytes) never produces.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let hidden = u16::try_from(self.hidden).expect("hidden size fits u16");
+        let mut out = Vec::with_capacity(HEADER_LEN + (INPUTS + 3) * self.hidden * 2 + 4);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&hidden.to_le_bytes());
+        for w in self.feature_weights.iter().chain(&self.feature_bias).chain(&self.output_weights) {
+            out.extend_from_slice(&w.to_le_bytes());
+        }
+        out.extend_from_slice(&self.output_bias.to_le_bytes());
+        out
+    }
+
+    /// Hidden layer size `N`.
+    #[must_use]
+    pub const fn hidden_size(&self) -> usize {
+        self.hidden
+    }
+
+    fn column(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+
+    /// Computes both accumulators from scratch.
+    #[must_use]
+    pub fn refresh(&self, board: &Board) -> Accumulator {
+        let mut acc = Accumulator { values: [self.feature_bias.clone(), self.feature_bias.clone()] };
+        for color in [Color::White, Color::Black] {
+            for pt in PieceType::ALL {
+                let mut bb = board.pieces(color, pt);
+                while bb != 0 {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let sq = Square::from_index(bb.trailing_zeros() as u8);
+                    bb &= bb - 1;
+                    let piece = Piece::new(pt, color);
+                    for perspective in [Color::White, Color::Black] {
+                        let column = self.column(feature(perspective, piece, sq));
+                        simd::add(&mut acc.values[perspective.index()], column);
+                    }
+                }
+            }
+        }
+        acc
+    }
+
+    /// Updates `acc` (which must match `board`) for playing the pseudo-legal
+    /// move `mv` on `board`.
+    pub fn update(&self, acc: &mut Accumulator, board: &Board, mv: Move) {
+        let (removed, added) = deltas(board, mv);
+        for perspective in [Color::White, Color::Black] {
+            let values = &mut acc.values[perspective.index()];
+            for &(piece, sq) in removed.iter().flatten() {
+                simd::sub(values, self.column(feature(perspective, piece, sq)));
+            }
+            for &(piece, sq) in added.iter().flatten() {
+                simd::add(values, self.column(feature(perspective, piece, sq)));
+            }
+        }
+    }
+
+    /// Evaluates an accumulator in centipawns from `side_to_move`'s view.
+    #[must_use]
+    pub fn evaluate(&self, acc: &Accumulator, side_to_move: Color) -> i32 {
+        let (ours, theirs) = self.output_weights.split_at(self.hidden);
+        let sum = simd::crelu_dot(&acc.values[side_to_move.index()], ours)
+            .wrapping_add(simd::crelu_dot(&acc.values[side_to_move.opposite().index()], theirs));
+        let scaled = (i64::from(sum) + i64::from(self.output_bias)) * i64::from(SCALE)
+            / i64::from(QA * QB);
+        i32::try_from(scaled).unwrap_or(if scaled > 0 { i32::MAX } else { i32::MIN })
+    }
+
+    /// Evaluates `board` without an incremental accumulator.
+    #[must_use]
+    pub fn evaluate_board(&self, board: &Board) -> i32 {
+        self.evaluate(&self.refresh(board), board.side_to_move())
+    }
+}
+
+/// Vector kernels. All slices have equal length, a multiple of 16.
+// `_mm_loadu_si128`/`_mm_storeu_si128` accept unaligned pointers.
+#[allow(clippy::cast_ptr_alignment)]
+mod simd {
+    use super::QA;
+
+    #[cfg(target_arch = "x86_64")]
+    pub fn add(acc: &mut [i16], column: &[i16]) {
+        use std::arch::x86_64::{__m128i, _mm_add_epi16, _mm_loadu_si128, _mm_storeu_si128};
+        for (a, c) in acc.chunks_exact_mut(8).zip(column.chunks_exact(8)) {
+            // SAFETY: both chunks hold exactly 8 i16 (16 bytes); unaligned
+            // loads and stores are used. SSE2 is part of the x86_64 baseline.
+            unsafe {
+                let sum = _mm_add_epi16(
+                    _mm_loadu_si128(a.as_ptr().cast::<__m128i>()),
+                    _mm_loadu_si128(c.as_ptr().cast::<__m128i>()),
+                );
+                _mm_storeu_si128(a.as_mut_ptr().cast::<__m128i>(), sum);
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    pub fn sub(acc: &mut [i16], column: &[i16]) {
+        use std::arch::x86_64::{__m128i, _mm_loadu_si128, _mm_storeu_si128, _mm_sub_epi16};
+        for (a, c) in acc.chunks_exact_mut(8).zip(column.chunks_exact(8)) {
+            // SAFETY: as in `add`.
+            unsafe {
+                let diff = _mm_sub_epi16(
+                    _mm_loadu_si128(a.as_ptr().cast::<__m128i>()),
+                    _mm_loadu_si128(c.as_ptr().cast::<__m128i>()),
+                );
+                _mm_storeu_si128(a.as_mut_ptr().cast::<__m128i>(), diff);
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    pub fn crelu_dot(acc: &[i16], weights: &[i16]) -> i32 {
+        use std::arch::x86_64::{
+            __m128i, _mm_add_epi32, _mm_loadu_si128, _mm_madd_epi16, _mm_max_epi16,
+            _mm_min_epi16, _mm_set1_epi16, _mm_setzero_si128, _mm_storeu_si128,
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        // SAFETY: SSE2 is part of the x86_64 baseline.
+        let (ceiling, zero, mut total) =
+            unsafe { (_mm_set1_epi16(QA as i16), _mm_setzero_si128(), _mm_setzero_si128()) };
+        for (a, w) in acc.chunks_exact(8).zip(weights.chunks_exact(8)) {
+            // SAFETY: as in `add`; only loads.
+            unsafe {
+                let clipped =
+                    _mm_min_epi16(_mm_max_epi16(_mm_loadu_si128(a.as_ptr().cast::<__m128i>()), zero), ceiling);
+                let products = _mm_madd_epi16(clipped, _mm_loadu_si128(w.as_ptr().cast::<__m128i>()));
+                total = _mm_add_epi32(total, products);
+            }
+        }
+        let mut lanes = [0i32; 4];
+        // SAFETY: `lanes` is 16 bytes; unaligned store.
+        unsafe { _mm_storeu_si128(lanes.as_mut_ptr().cast::<__m128i>(), total) };
+        lanes.iter().fold(0, |s, &v| s.wrapping_add(v))
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    pub use scalar::{add, crelu_dot, sub};
+
+    /// Portable kernels, used on wasm32 and other targets, and as the
+    /// reference for the SIMD versions in tests.
+    pub mod scalar {
+        use super::QA;
+
+        #[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+        pub fn add(acc: &mut [i16], column: &[i16]) {
+            for (a, &c) in acc.iter_mut().zip(column) {
+                *a = a.wrapping_add(c);
+            }
+        }
+
+        #[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+        pub fn sub(acc: &mut [i16], column: &[i16]) {
+            for (a, &c) in acc.iter_mut().zip(column) {
+                *a = a.wrapping_sub(c);
+            }
+        }
+
+        #[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+        pub fn crelu_dot(acc: &[i16], weights: &[i16]) -> i32 {
+            acc.iter().zip(weights).fold(0i32, |sum, (&a, &w)| {
+                sum.wrapping_add(i32::from(a).clamp(0, QA) * i32::from(w))
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::TEST_POSITIONS;
+    use crate::rng::Rng;
+
+    /// An untrained network with small random weights.
+    fn random_network(hidden: usize, seed: u64) -> Network {
+        let mut rng = Rng::new(seed);
+        let mut small = |range: u64| {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let v = rng.below(2 * range + 1) as i16 - range as i16;
+            v
+        };
+        Network {
+            hidden,
+            feature_weights: (0..INPUTS * hidden).map(|_| small(40)).collect(),
+            feature_bias: (0..hidden).map(|_| small(60)).collect(),
+            output_weights: (0..2 * hidden).map(|_| small(60)).collect(),
+            output_bias: 1234,
+        }
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let net = random_network(32, 1);
+        let bytes = net.to_bytes();
+        assert_eq!(bytes.len(), 8 + (768 + 3) * 32 * 2 + 4);
+        assert_eq!(Network::from_bytes(&bytes), Some(net));
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let bytes = random_network(16, 2).to_bytes();
+        assert!(Network::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert!(Network::from_bytes(&bad_magic).is_none());
+        let mut bad_hidden = bytes;
+        bad_hidden[6] = 17;
+        assert!(Network::from_bytes(&bad_hidden).is_none());
+        assert!(Network::from_bytes(b"CENN").is_none());
+    }
+
+    #[test]
+    fn incremental_updates_match_refresh() {
+        let net = random_network(32, 3);
+        let mut rng = Rng::new(4);
+        for fen in TEST_POSITIONS {
+            let mut board = Board::from_fen(fen).unwrap();
+            let mut acc = net.refresh(&board);
+            for _ in 0..40 {
+                let moves = board.generate_legal_moves();
+                if moves.is_empty() || board.is_in_check(board.side_to_move().opposite()) {
+                    break;
+                }
+                let mv = moves[usize::try_from(rng.below(moves.len() as u64)).unwrap()];
+                net.update(&mut acc, &board, mv);
+                board.apply_unchecked(mv);
+                assert_eq!(acc, net.refresh(&board), "{fen} after {mv}");
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_under_color_flip() {
+        let net = random_network(16, 5);
+        for fen in TEST_POSITIONS {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(net.evaluate_board(&board), net.evaluate_board(&board.flipped()), "{fen}");
+        }
+    }
+
+    #[test]
+    fn simd_matches_scalar() {
+        let net = random_network(48, 6);
+        let board = Board::from_fen(crate::board::tests::KIWIPETE).unwrap();
+        let acc = net.refresh(&board);
+        let mut scalar_acc = Accumulator { values: [net.feature_bias.clone(), net.feature_bias.clone()] };
+        for values in &mut scalar_acc.values {
+            simd::scalar::add(values, net.column(100));
+            simd::scalar::sub(values, net.column(7));
+        }
+        let mut simd_acc = Accumulator { values: [net.feature_bias.clone(), net.feature_bias.clone()] };
+        for values in &mut simd_acc.values {
+            simd::add(values, net.column(100));
+            simd::sub(values, net.column(7));
+        }
+        assert_eq!(scalar_acc, simd_acc);
+        for values in &acc.values {
+            assert_eq!(
+                simd::crelu_dot(values, &net.output_weights[..48]),
+                simd::scalar::crelu_dot(values, &net.output_weights[..48])
+            );
+        }
+    }
+
+    #[test]
+    fn output_scaling() {
+        // Zero weights leave only the bias: QA * QB units are SCALE centipawns.
+        let net = Network {
+            hidden: 16,
+            feature_weights: vec![0; INPUTS * 16],
+            feature_bias: vec![0; 16],
+            output_weights: vec![0; 32],
+            output_bias: QA * QB,
+        };
+        assert_eq!(net.evaluate_board(&Board::new()), SCALE);
+    }
+}
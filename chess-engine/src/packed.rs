@@ -0,0 +1,236 @@
+//! Fixed-size binary encoding of a [`Board`] for position datasets.
+//!
+//! A packed position is [`PACKED_LEN`] (30) bytes — about half the size of a
+//! FEN string and decoded without any text parsing.
+//!
+//! ## Layout
+//!
+//! | Bytes    | Content                                                        |
+//! |----------|----------------------------------------------------------------|
+//! | `0..8`   | Occupancy bitboard, little-endian (bit `n` = square index `n`) |
+//! | `8..24`  | One nibble per occupied square, in ascending square order      |
+//! | `24`     | Bit 0: side to move (1 = Black); bits 1–4: castling `KQkq`     |
+//! | `25`     | En-passant square index, or `0xFF` for none                    |
+//! | `26..28` | Halfmove clock, little-endian                                  |
+//! | `28..30` | Fullmove number, little-endian                                 |
+//!
+//! Each piece nibble is `color << 3 | piece_type`, using the discriminants
+//! of [`Color`] and [`PieceType`]. The first piece occupies the low nibble
+//! of byte 8. Unused nibbles are zero.
+//!
+//! Decoding rejects anything [`Board::from_fen`] would reject (missing or
+//! extra kings, en passant off ranks 3/6) plus malformed encodings: more than
+//! 32 pieces, unknown piece nibbles, non-zero padding, or stray flag bits.
+
+use crate::board::Board;
+use crate::types::{CastlingRights, Color, Piece, PieceType, Square};
+
+/// Size in bytes of a packed position.
+pub const PACKED_LEN: usize = 30;
+
+/// Most pieces a packed position can hold (16 bytes of nibbles).
+const MAX_PIECES: u32 = 32;
+
+const NO_EN_PASSANT: u8 = 0xFF;
+
+impl Board {
+    /// Encodes the position into [`PACKED_LEN`] bytes (see the
+    /// [module docs](crate::packed) for the layout).
+    ///
+    /// Returns `None` if the board holds more than 32 pieces, which cannot
+    /// happen in a position reachable from a real game.
+    #[must_use]
+    pub fn to_bytes(&self) -> Option<[u8; PACKED_LEN]> {
+        let occupied = self.occupied();
+        if occupied.count_ones() > MAX_PIECES {
+            return None;
+        }
+
+        let mut out = [0u8; PACKED_LEN];
+        out[0..8].copy_from_slice(&occupied.to_le_bytes());
+
+        let mut bb = occupied;
+        let mut n = 0;
+        while bb != 0 {
+            #[allow(clippy::cast_possible_truncation)]
+            let sq = Square::from_index(bb.trailing_zeros() as u8);
+            bb &= bb - 1;
+            let piece = self.piece_at(sq)?;
+            let nibble = ((piece.color() as u8) << 3) | piece.piece_type() as u8;
+            out[8 + n / 2] |= nibble << (4 * (n % 2));
+            n += 1;
+        }
+
+        out[24] = (self.side_to_move() as u8) | (self.castling().0 << 1);
+        out[25] = self.en_passant().map_or(NO_EN_PASSANT, Square::raw);
+        out[26..28].copy_from_slice(&self.halfmove_clock().to_le_bytes());
+        out[28..30].copy_from_slice(&self.fullmove_number().to_le_bytes());
+        Some(out)
+    }
+
+    /// Decodes a position produced by [`to_bytes`](Self::to_bytes).
+    ///
+    /// Returns `None` if `bytes` is not exactly [`PACKED_LEN`] long or does
+    /// not describe a valid position.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: &[u8; PACKED_LEN] = bytes.try_into().ok()?;
+
+        let occupied = u64::from_le_bytes(bytes[0..8].try_into().ok()?);
+        let count = occupied.count_ones();
+        if count > MAX_PIECES {
+            return None;
+        }
+
+        let mut board = Self::empty();
+        let mut bb = occupied;
+        let mut n = 0usize;
+        while bb != 0 {
+            #[allow(clippy::cast_possible_truncation)]
+            let sq = Square::from_index(bb.trailing_zeros() as u8);
+            bb &= bb - 1;
+            let nibble = (bytes[8 + n / 2] >> (4 * (n % 2))) & 0x0F;
+            board.put_piece(sq, piece_from_nibble(nibble)?);
+            n += 1;
+        }
+        // Nibbles past the last piece must be zero so every position has
+        // exactly one encoding.
+        let pieces = &bytes[8..24];
+        if n % 2 == 1 && pieces[n / 2] >> 4 != 0 {
+            return None;
+        }
+        if pieces[n.div_ceil(2)..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        if !board.has_valid_kings() {
+            return None;
+        }
+
+        let flags = bytes[24];
+        if flags >> 5 != 0 {
+            return None;
+        }
+        let side = if flags & 1 == 0 { Color::White } else { Color::Black };
+        let castling = CastlingRights((flags >> 1) & 0x0F);
+
+        let en_passant = match bytes[25] {
+            NO_EN_PASSANT => None,
+            i if i < 64 => {
+                let sq = Square::from_index(i);
+                if sq.rank() != 2 && sq.rank() != 5 {
+                    return None;
+                }
+                Some(sq)
+            }
+            _ => return None,
+        };
+
+        let halfmove = u16::from_le_bytes([bytes[26], bytes[27]]);
+        let fullmove = u16::from_le_bytes([bytes[28], bytes[29]]);
+        board.set_state(side, castling, en_passant, halfmove, fullmove);
+        Some(board)
+    }
+}
+
+/// Inverse of the `color << 3 | piece_type` nibble used by the encoder.
+fn piece_from_nibble(nibble: u8) -> Option<Piece> {
+    let color = if nibble & 0b1000 == 0 { Color::White } else { Color::Black };
+    let pt = *PieceType::ALL.get(usize::from(nibble & 0b0111))?;
+    Some(Piece::new(pt, color))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::TEST_POSITIONS;
+
+    fn round_trip(fen: &str) {
+        let board = Board::from_fen(fen).unwrap();
+        let bytes = board.to_bytes().unwrap();
+        let decoded = Board::from_bytes(&bytes).unwrap_or_else(|| panic!("decode failed: {fen}"));
+        assert_eq!(decoded.to_fen(), board.to_fen(), "{fen}");
+    }
+
+    #[test]
+    fn round_trips_every_test_position() {
+        for fen in TEST_POSITIONS {
+            round_trip(fen);
+            round_trip(&Board::from_fen(fen).unwrap().flipped().to_fen());
+        }
+    }
+
+    #[test]
+    fn round_trips_large_clocks() {
+        round_trip("4k3/8/8/8/8/8/8/4K3 b - - 65535 65535");
+    }
+
+    #[test]
+    fn start_position_layout() {
+        let bytes = Board::new().to_bytes().unwrap();
+        assert_eq!(bytes.len(), PACKED_LEN);
+        assert_eq!(&bytes[0..8], &0xFFFF_0000_0000_FFFFu64.to_le_bytes());
+        // a1 = white rook (3), b1 = white knight (1)
+        assert_eq!(bytes[8], 0x13);
+        // g8 = black knight (8|1), h8 = black rook (8|3)
+        assert_eq!(bytes[23], 0xB9);
+        assert_eq!(bytes[24], 0b1_1110);
+        assert_eq!(bytes[25], NO_EN_PASSANT);
+        assert_eq!(&bytes[26..30], &[0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        let bytes = Board::new().to_bytes().unwrap();
+        assert!(Board::from_bytes(&bytes[..PACKED_LEN - 1]).is_none());
+        let mut long = bytes.to_vec();
+        long.push(0);
+        assert!(Board::from_bytes(&long).is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_encodings() {
+        let valid = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap().to_bytes().unwrap();
+        assert!(Board::from_bytes(&valid).is_some());
+
+        // Unknown piece type (6) in the first nibble
+        let mut b = valid;
+        b[8] = (b[8] & 0xF0) | 0x06;
+        assert!(Board::from_bytes(&b).is_none());
+
+        // Both kings turned white
+        let mut b = valid;
+        b[8] = 0x05;
+        assert!(Board::from_bytes(&b).is_none());
+
+        // Garbage in an unused nibble
+        let mut b = valid;
+        b[20] = 0x01;
+        assert!(Board::from_bytes(&b).is_none());
+
+        // Stray flag bits
+        let mut b = valid;
+        b[24] |= 0x80;
+        assert!(Board::from_bytes(&b).is_none());
+
+        // En passant on rank 4, and out of range
+        let mut b = valid;
+        b[25] = Square::from_algebraic("e4").unwrap().raw();
+        assert!(Board::from_bytes(&b).is_none());
+        b[25] = 64;
+        assert!(Board::from_bytes(&b).is_none());
+
+        // Empty board (no kings)
+        assert!(Board::from_bytes(&[0; PACKED_LEN]).is_none());
+
+        // More than 32 occupied squares
+        let mut b = valid;
+        b[0..8].copy_from_slice(&0x0000_FFFF_FFFF_FFFFu64.to_le_bytes());
+        assert!(Board::from_bytes(&b).is_none());
+    }
+
+    #[test]
+    fn to_bytes_rejects_overfull_board() {
+        let board = Board::from_fen("kqqqqqqq/qqqqqqqq/qqqqqqqq/qqqqqqqq/8/8/QQQQQQQQ/KQQQQQQQ w - - 0 1").unwrap();
+        assert!(board.to_bytes().is_none());
+    }
+}
@@ -0,0 +1,281 @@
+//! Minimal PGN (Portable Game Notation) reader.
+//!
+//! [`parse_pgn`] splits a PGN collection into [`PgnGame`]s with their tag
+//! pairs, main-line SAN moves, and result. Comments (`{…}` and `;` to end of
+//! line), recursive variations (`(…)`), NAGs (`$1`), and move numbers are
+//! skipped. Moves are kept as text; [`PgnGame::replay`] resolves them against
+//! a [`Board`] and reports the first move that fails.
+
+use crate::board::Board;
+use crate::types::{Color, Move};
+
+/// Final result recorded in a PGN game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PgnResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl PgnResult {
+    /// Parses a PGN result token (`1-0`, `0-1`, `1/2-1/2`). `*` and anything
+    /// else yield `None`.
+    #[must_use]
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(Self::WhiteWins),
+            "0-1" => Some(Self::BlackWins),
+            "1/2-1/2" => Some(Self::Draw),
+            _ => None,
+        }
+    }
+
+    /// The winner, or `None` for a draw.
+    #[must_use]
+    pub const fn winner(self) -> Option<Color> {
+        match self {
+            Self::WhiteWins => Some(Color::White),
+            Self::BlackWins => Some(Color::Black),
+            Self::Draw => None,
+        }
+    }
+}
+
+impl std::fmt::Display for PgnResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::WhiteWins => "1-0",
+            Self::BlackWins => "0-1",
+            Self::Draw => "1/2-1/2",
+        })
+    }
+}
+
+/// One game from a PGN collection.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PgnGame {
+    /// Tag pairs in file order, e.g. `("White", "Smart Bot")`.
+    pub tags: Vec<(String, String)>,
+    /// Main-line moves in SAN, without move numbers or annotations.
+    pub moves: Vec<String>,
+    /// Result from the movetext terminator, falling back to the `Result` tag.
+    pub result: Option<PgnResult>,
+}
+
+impl PgnGame {
+    /// Value of the first tag named `name`.
+    #[must_use]
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Starting position: the `FEN` tag if present, otherwise the standard one.
+    ///
+    /// Returns `None` if the `FEN` tag is not a valid position.
+    #[must_use]
+    pub fn start_board(&self) -> Option<Board> {
+        self.tag("FEN").map_or_else(|| Some(Board::new()), Board::from_fen)
+    }
+
+    /// Plays the moves from the starting position.
+    ///
+    /// # Errors
+    ///
+    /// Returns the index of the first move that cannot be parsed or is
+    /// illegal (`0` also covers an invalid `FEN` tag).
+    pub fn replay(&self) -> Result<Vec<Move>, usize> {
+        let mut board = self.start_board().ok_or(0usize)?;
+        let mut out = Vec::with_capacity(self.moves.len());
+        for (i, san) in self.moves.iter().enumerate() {
+            let mv = board.parse_san(san).ok_or(i)?;
+            board.apply_unchecked(mv);
+            out.push(mv);
+        }
+        Ok(out)
+    }
+}
+
+/// Parses every game in a PGN collection.
+///
+/// The reader is lenient: text it does not understand inside movetext is
+/// kept as a move token and surfaces later as a [`PgnGame::replay`] error.
+#[must_use]
+pub fn parse_pgn(text: &str) -> Vec<PgnGame> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    let mut in_movetext = false;
+
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '[' => {
+                // A tag section after movetext starts the next game.
+                if in_movetext {
+                    finish(&mut games, &mut game);
+                    in_movetext = false;
+                }
+                chars.next();
+                let line: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                if let Some((name, value)) = parse_tag(&line) {
+                    game.tags.push((name, value));
+                }
+            }
+            '{' => {
+                chars.by_ref().take_while(|&c| c != '}').for_each(drop);
+            }
+            ';' => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+            }
+            '(' => {
+                let mut depth = 0;
+                for c in chars.by_ref() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                in_movetext = true;
+                let mut token = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '{' | '(' | ')' | ';' | '[') {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                if token.is_empty() {
+                    // Stray closing parenthesis.
+                    chars.next();
+                    continue;
+                }
+                if token == "*" {
+                    finish(&mut games, &mut game);
+                    in_movetext = false;
+                } else if let Some(result) = PgnResult::from_token(&token) {
+                    game.result = Some(result);
+                    finish(&mut games, &mut game);
+                    in_movetext = false;
+                } else if !token.starts_with('$') {
+                    // Strip a move number prefix such as "12." or "12...".
+                    // Digit-zero castling ("0-0") has no dot and is kept whole.
+                    let san = match token.rfind('.') {
+                        Some(i) if token[..i].bytes().all(|b| b.is_ascii_digit() || b == b'.') => {
+                            &token[i + 1..]
+                        }
+                        _ => token.as_str(),
+                    };
+                    if !san.is_empty() {
+                        game.moves.push(san.to_string());
+                    }
+                }
+            }
+        }
+    }
+    finish(&mut games, &mut game);
+    games
+}
+
+/// Parses the inside of a `[Name "Value"]` tag.
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let (name, rest) = line.trim().split_once(char::is_whitespace)?;
+    let value = rest.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name.to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\")))
+}
+
+/// Pushes `game` if it has any content and resets it.
+fn finish(games: &mut Vec<PgnGame>, game: &mut PgnGame) {
+    if game.tags.is_empty() && game.moves.is_empty() {
+        return;
+    }
+    let mut done = std::mem::take(game);
+    if done.result.is_none() {
+        done.result = done.tag("Result").and_then(PgnResult::from_token);
+    }
+    games.push(done);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_GAMES: &str = r#"
+[Event "Bot match"]
+[White "Smart Bot"]
+[Black "Random Bot"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2. Qh5 Nc6 (2... g6 3. Qxe5+) 3. Bc4 $2 Nf6?? 4. Qxf7# 1-0
+
+[Event "Bot match"]
+[White "Random Bot"]
+[Black "Smart Bot"]
+[Result "1/2-1/2"]
+
+1. d4 d5 ; a comment to end of line
+2. c4 1/2-1/2
+"#;
+
+    #[test]
+    fn parses_tags_moves_and_results() {
+        let games = parse_pgn(TWO_GAMES);
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("White"), Some("Smart Bot"));
+        assert_eq!(games[0].moves, ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6??", "Qxf7#"]);
+        assert_eq!(games[0].result, Some(PgnResult::WhiteWins));
+        assert_eq!(games[1].moves, ["d4", "d5", "c4"]);
+        assert_eq!(games[1].result, Some(PgnResult::Draw));
+    }
+
+    #[test]
+    fn replay_resolves_moves() {
+        let games = parse_pgn(TWO_GAMES);
+        let moves = games[0].replay().unwrap();
+        assert_eq!(moves.last().unwrap().to_uci(), "h5f7");
+    }
+
+    #[test]
+    fn replay_reports_bad_move() {
+        let games = parse_pgn("1. e4 e5 2. Ke3 *");
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].result, None);
+        assert_eq!(games[0].replay(), Err(2));
+    }
+
+    #[test]
+    fn fen_tag_sets_start_position() {
+        let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 Kd7 *";
+        let game = &parse_pgn(pgn)[0];
+        assert_eq!(game.replay().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn result_tag_used_when_movetext_unterminated() {
+        let games = parse_pgn("[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4#\n\n[Result \"*\"]\n\n1. e4 *");
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].result, Some(PgnResult::BlackWins));
+        assert_eq!(games[0].moves.len(), 4);
+        assert_eq!(games[1].result, None);
+    }
+
+    #[test]
+    fn move_numbers_and_digit_castling() {
+        let game = &parse_pgn("1.e4 e5 2.Nf3 Nc6 3.Bc4 Bc5 4.0-0 4...Nf6 *")[0];
+        assert_eq!(game.moves, ["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5", "0-0", "Nf6"]);
+        assert_eq!(game.replay().unwrap()[6].to_uci(), "e1g1");
+    }
+
+    #[test]
+    fn escaped_tag_values() {
+        let game = &parse_pgn(r#"[Event "The \"Big\" One"]"#)[0];
+        assert_eq!(game.tag("Event"), Some("The \"Big\" One"));
+    }
+}
@@ -0,0 +1,302 @@
+//! Chess problem solving from stipulation strings.
+//!
+//! [`Board::solve_problem`] accepts the usual stipulations, with the side to
+//! move in the FEN moving first:
+//!
+//! - `#N` — direct mate: the side to move forces mate in N ([`Board::solve_mate`]).
+//! - `=N` — direct stalemate: the side to move forces stalemate in N.
+//! - `s#N` — selfmate: the side to move forces the opponent to give mate in N.
+//! - `h#N` — helpmate: both sides cooperate so that the side to move is
+//!   mated by the opponent's Nth move (2N plies).
+//!
+//! The search is exhaustive over the legal move generator. A problem is
+//! *sound* when it has exactly one solution: one key move for the directed
+//! stipulations, one move sequence for helpmates.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::board::Board;
+use crate::mate::{is_checkmate, legal_children, Goal, MateNode, Solver};
+use crate::types::Move;
+
+/// A problem's goal and length in moves (at least 1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stipulation {
+    /// `#N`
+    Mate(u32),
+    /// `=N`
+    Stalemate(u32),
+    /// `s#N`
+    SelfMate(u32),
+    /// `h#N`
+    HelpMate(u32),
+}
+
+impl Stipulation {
+    /// Parses `#N`, `=N`, `s#N`, or `h#N` (case-insensitive prefix).
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_ascii_lowercase();
+        let (make, n): (fn(u32) -> Self, &str) = if let Some(n) = s.strip_prefix("h#") {
+            (Self::HelpMate, n)
+        } else if let Some(n) = s.strip_prefix("s#") {
+            (Self::SelfMate, n)
+        } else if let Some(n) = s.strip_prefix('#') {
+            (Self::Mate, n)
+        } else {
+            (Self::Stalemate, s.strip_prefix('=')?)
+        };
+        let n = n.parse().ok().filter(|&n| n > 0)?;
+        Some(make(n))
+    }
+
+    /// Number of moves of the side to move.
+    #[must_use]
+    pub const fn moves(self) -> u32 {
+        match self {
+            Self::Mate(n) | Self::Stalemate(n) | Self::SelfMate(n) | Self::HelpMate(n) => n,
+        }
+    }
+}
+
+impl fmt::Display for Stipulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mate(n) => write!(f, "#{n}"),
+            Self::Stalemate(n) => write!(f, "={n}"),
+            Self::SelfMate(n) => write!(f, "s#{n}"),
+            Self::HelpMate(n) => write!(f, "h#{n}"),
+        }
+    }
+}
+
+/// Every solution of a problem, as a tree of moves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProblemSolution {
+    pub stipulation: Stipulation,
+    /// For directed stipulations, one root per key move, laid out like
+    /// [`MateSolution::tree`](crate::mate::MateSolution::tree). For
+    /// helpmates, the cooperative moves of every solution, sharing common
+    /// prefixes. Empty if the problem has no solution.
+    pub tree: Vec<MateNode>,
+}
+
+/// Every path from the roots of `nodes` to a leaf.
+fn paths(nodes: &[MateNode], prefix: &mut Vec<Move>, out: &mut Vec<Vec<Move>>) {
+    for node in nodes {
+        prefix.push(node.mv);
+        if node.replies.is_empty() {
+            out.push(prefix.clone());
+        } else {
+            paths(&node.replies, prefix, out);
+        }
+        prefix.pop();
+    }
+}
+
+impl ProblemSolution {
+    /// The first move of every solution.
+    #[must_use]
+    pub fn key_moves(&self) -> Vec<Move> {
+        self.tree.iter().map(|node| node.mv).collect()
+    }
+
+    /// Every line of the tree from a key move to the end of the solution.
+    /// For helpmates these are the solutions themselves.
+    #[must_use]
+    pub fn lines(&self) -> Vec<Vec<Move>> {
+        let mut out = Vec::new();
+        paths(&self.tree, &mut Vec::new(), &mut out);
+        out
+    }
+
+    /// Number of solutions: key moves, or helpmate lines.
+    #[must_use]
+    pub fn solution_count(&self) -> usize {
+        match self.stipulation {
+            Stipulation::HelpMate(_) => self.lines().len(),
+            _ => self.tree.len(),
+        }
+    }
+
+    /// The stipulation can be fulfilled.
+    #[must_use]
+    pub const fn is_solved(&self) -> bool {
+        !self.tree.is_empty()
+    }
+
+    /// Exactly one solution.
+    #[must_use]
+    pub fn is_sound(&self) -> bool {
+        self.solution_count() == 1
+    }
+}
+
+/// Helpmate search: both sides cooperate so that the side to move after
+/// `plies` more plies is checkmated, with the original side to move mated.
+#[derive(Default)]
+struct HelpSolver {
+    /// Positions (with plies left) known to have no solution.
+    dead: HashSet<(u64, u32)>,
+}
+
+impl HelpSolver {
+    fn solve(&mut self, board: &Board, plies: u32) -> Vec<MateNode> {
+        let key = (board.polyglot_key(), plies);
+        if self.dead.contains(&key) {
+            return Vec::new();
+        }
+        let mut nodes = Vec::new();
+        for (mv, child) in legal_children(board) {
+            if plies == 1 {
+                if is_checkmate(&child) {
+                    nodes.push(MateNode { mv, replies: Vec::new() });
+                }
+                continue;
+            }
+            let replies = self.solve(&child, plies - 1);
+            if !replies.is_empty() {
+                nodes.push(MateNode { mv, replies });
+            }
+        }
+        if nodes.is_empty() {
+            self.dead.insert(key);
+        }
+        nodes
+    }
+}
+
+impl Board {
+    /// Solves the problem described by a stipulation string (`#N`, `=N`,
+    /// `s#N`, or `h#N`) exhaustively, or returns `None` if the string is
+    /// not a valid stipulation.
+    #[must_use]
+    pub fn solve_problem(&self, stipulation: &str) -> Option<ProblemSolution> {
+        let stipulation = Stipulation::parse(stipulation)?;
+        let directed = |goal| {
+            Solver::new(goal)
+                .solve(self, stipulation.moves())
+                .map_or_else(Vec::new, |solution| solution.tree)
+        };
+        let tree = match stipulation {
+            Stipulation::Mate(_) => directed(Goal::Mate),
+            Stipulation::Stalemate(_) => directed(Goal::Stalemate),
+            Stipulation::SelfMate(_) => directed(Goal::SelfMate),
+            Stipulation::HelpMate(n) => HelpSolver::default().solve(self, 2 * n),
+        };
+        Some(ProblemSolution { stipulation, tree })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(fen: &str, stipulation: &str) -> ProblemSolution {
+        Board::from_fen(fen).unwrap().solve_problem(stipulation).unwrap()
+    }
+
+    fn uci_lines(solution: &ProblemSolution) -> Vec<Vec<String>> {
+        solution.lines().iter().map(|line| line.iter().map(Move::to_uci).collect()).collect()
+    }
+
+    #[test]
+    fn parses_stipulations() {
+        for (text, stipulation) in [
+            ("#2", Stipulation::Mate(2)),
+            ("=3", Stipulation::Stalemate(3)),
+            ("s#1", Stipulation::SelfMate(1)),
+            ("h#4", Stipulation::HelpMate(4)),
+        ] {
+            assert_eq!(Stipulation::parse(text), Some(stipulation));
+            assert_eq!(stipulation.to_string(), text);
+        }
+        assert_eq!(Stipulation::parse(" H#2 "), Some(Stipulation::HelpMate(2)));
+        for bad in ["", "#", "#0", "h#x", "x#2", "h=2", "#-1"] {
+            assert_eq!(Stipulation::parse(bad), None, "{bad}");
+        }
+        assert!(Board::new().solve_problem("mate in 2").is_none());
+    }
+
+    #[test]
+    fn direct_mate_matches_mate_solver() {
+        let fen = "k7/8/1K6/8/8/8/8/6RR w - - 0 1";
+        let solution = solve(fen, "#1");
+        let mate = Board::from_fen(fen).unwrap().solve_mate(1).unwrap();
+        assert_eq!(solution.key_moves(), mate.key_moves);
+        assert_eq!(solution.solution_count(), 2);
+        assert!(solution.is_solved() && !solution.is_sound());
+    }
+
+    #[test]
+    fn helpmate_in_one() {
+        // 1. Kb8 Rh8#
+        let solution = solve("k7/8/1K6/8/8/8/8/7R b - - 0 1", "h#1");
+        assert_eq!(uci_lines(&solution), [["a8b8", "h1h8"]]);
+        assert!(solution.is_sound());
+        assert!(!solve("k7/8/1K6/8/8/8/8/7R b - - 0 1", "#1").is_solved());
+    }
+
+    #[test]
+    fn helpmate_lines_end_in_mate_of_the_first_mover() {
+        let board = Board::new();
+        // Fool's mate with colours reversed: 1. f3 e5 2. g4 Qh4#.
+        let solution = board.solve_problem("h#2").unwrap();
+        assert!(solution.is_solved());
+        assert!(uci_lines(&solution).contains(&["f2f3", "e7e5", "g2g4", "d8h4"].map(String::from).to_vec()));
+        for line in solution.lines() {
+            assert_eq!(line.len(), 4);
+            let mut b = board.clone();
+            for mv in &line {
+                assert!(b.make_move(*mv));
+            }
+            assert!(is_checkmate(&b));
+            assert_eq!(b.side_to_move(), board.side_to_move());
+        }
+    }
+
+    #[test]
+    fn stalemate_in_one() {
+        // Qf7 stalemates; Qf8 would mate instead.
+        let solution = solve("7k/8/6K1/8/8/8/8/5Q2 w - - 0 1", "=1");
+        let keys: Vec<String> = solution.key_moves().iter().map(Move::to_uci).collect();
+        assert!(keys.contains(&"f1f7".to_string()));
+        assert!(!keys.contains(&"f1f8".to_string()));
+        for key in solution.key_moves() {
+            let mut b = Board::from_fen("7k/8/6K1/8/8/8/8/5Q2 w - - 0 1").unwrap();
+            b.make_move(key);
+            assert!(b.generate_legal_moves().is_empty() && !b.is_in_check(b.side_to_move()));
+        }
+    }
+
+    #[test]
+    fn selfmate_in_one() {
+        // 1. Qg7+ Qxg7# (the king cannot take: Kf8 guards g7).
+        let fen = "4BK2/7k/8/4Q3/8/2q5/8/8 w - - 0 1";
+        let solution = solve(fen, "s#1");
+        assert_eq!(uci_lines(&solution), [["e5g7", "c3g7"]]);
+        assert!(solution.is_sound());
+        // After each key every defence mates White, and there is at least one.
+        for node in &solution.tree {
+            let mut b = Board::from_fen(fen).unwrap();
+            b.make_move(node.mv);
+            let defences = b.generate_legal_moves();
+            assert!(!defences.is_empty());
+            assert_eq!(node.replies.len(), defences.len());
+            for reply in &node.replies {
+                let mut r = b.clone();
+                r.make_move(reply.mv);
+                assert!(is_checkmate(&r));
+            }
+        }
+    }
+
+    #[test]
+    fn unsolvable_problems() {
+        let solution = solve("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "=2");
+        assert!(!solution.is_solved());
+        assert_eq!(solution.solution_count(), 0);
+        assert!(!solution.is_sound());
+    }
+}
@@ -0,0 +1,69 @@
+//! A small deterministic pseudo-random generator.
+//!
+//! The engine has no dependencies (the WASM build must stay lean), so
+//! randomized features — book move selection, weakened play, self-play
+//! openings — share this xorshift64* generator. It is *not* suitable for
+//! anything security-related.
+
+/// Xorshift64* pseudo-random number generator.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Creates a generator from a seed. Every seed (including 0) is valid.
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        // Xorshift has a fixed point at zero; scramble the seed so that
+        // nearby seeds also start far apart.
+        let s = (seed ^ 0x9E37_79B9_7F4A_7C15).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        Self(if s == 0 { 0x2545_F491_4F6C_DD1D } else { s })
+    }
+
+    /// Returns the next 64 random bits.
+    pub const fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a value in `0..n`. Returns 0 when `n == 0`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        // Multiply-shift reduction: unbiased enough for n far below 2^64.
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            ((u128::from(self.next_u64()) * u128::from(n)) >> 64) as u64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic_per_seed() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = Rng::new(0);
+        let mut seen = [false; 7];
+        for _ in 0..1_000 {
+            let v = rng.below(7);
+            assert!(v < 7);
+            seen[usize::try_from(v).unwrap()] = true;
+        }
+        assert!(seen.iter().all(|&s| s), "every value should appear");
+        assert_eq!(rng.below(0), 0);
+    }
+}
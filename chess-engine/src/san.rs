@@ -0,0 +1,249 @@
+//! Standard Algebraic Notation (SAN) parsing and formatting.
+//!
+//! [`Board::parse_san`] accepts what real PGN files contain: check and
+//! annotation suffixes (`+`, `#`, `!`, `?`), castling with letter O or
+//! digit zero, and promotions with or without `=`. Ambiguous or illegal
+//! moves are rejected rather than guessed.
+
+use crate::board::Board;
+use crate::types::{GameState, Move, MoveKind, Piece, PieceType, Square};
+
+const fn piece_letter(pt: PieceType) -> Option<char> {
+    match pt {
+        PieceType::Pawn => None,
+        PieceType::Knight => Some('N'),
+        PieceType::Bishop => Some('B'),
+        PieceType::Rook => Some('R'),
+        PieceType::Queen => Some('Q'),
+        PieceType::King => Some('K'),
+    }
+}
+
+const fn piece_from_letter(c: u8) -> Option<PieceType> {
+    match c {
+        b'N' => Some(PieceType::Knight),
+        b'B' => Some(PieceType::Bishop),
+        b'R' => Some(PieceType::Rook),
+        b'Q' => Some(PieceType::Queen),
+        b'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+impl Board {
+    /// Parses a SAN move (e.g. `"Nf3"`, `"exd6"`, `"O-O"`, `"e8=Q+"`) into
+    /// the matching legal move.
+    ///
+    /// Returns `None` if the text is malformed, the move is illegal, or it
+    /// matches more than one legal move.
+    #[must_use]
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal = self.generate_legal_moves();
+
+        if matches!(san, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+            let file = if san.len() == 3 { 6 } else { 2 };
+            return legal
+                .into_iter()
+                .find(|m| m.kind() == MoveKind::Castle && m.to().file() == file);
+        }
+
+        let mut bytes = san.as_bytes();
+        let mut piece = PieceType::Pawn;
+        if let Some(pt) = bytes.first().copied().and_then(piece_from_letter) {
+            piece = pt;
+            bytes = &bytes[1..];
+        }
+
+        // Promotion suffix: "=Q" or a bare trailing piece letter.
+        let mut promotion = None;
+        if let Some(&last) = bytes.last() {
+            if let Some(pt) = piece_from_letter(last).filter(|&pt| pt != PieceType::King) {
+                promotion = Some(pt);
+                bytes = &bytes[..bytes.len() - 1];
+                if bytes.last() == Some(&b'=') {
+                    bytes = &bytes[..bytes.len() - 1];
+                }
+            }
+        }
+
+        if bytes.len() < 2 {
+            return None;
+        }
+        let to = Square::from_algebraic(std::str::from_utf8(&bytes[bytes.len() - 2..]).ok()?)?;
+        let mut disambig = &bytes[..bytes.len() - 2];
+        if disambig.last() == Some(&b'x') {
+            disambig = &disambig[..disambig.len() - 1];
+        }
+        let (mut from_file, mut from_rank) = (None, None);
+        for &c in disambig {
+            match c {
+                b'a'..=b'h' if from_file.is_none() => from_file = Some(c - b'a'),
+                b'1'..=b'8' if from_rank.is_none() => from_rank = Some(c - b'1'),
+                _ => return None,
+            }
+        }
+
+        let mut found = None;
+        for m in legal {
+            let moving = self.piece_at(m.from()).map(Piece::piece_type);
+            if m.kind() == MoveKind::Castle
+                || moving != Some(piece)
+                || m.to() != to
+                || m.promotion_piece() != promotion
+                || from_file.is_some_and(|f| m.from().file() != f)
+                || from_rank.is_some_and(|r| m.from().rank() != r)
+            {
+                continue;
+            }
+            if found.is_some() {
+                return None;
+            }
+            found = Some(m);
+        }
+        found
+    }
+
+    /// Formats a legal move in SAN, with `+` or `#` when it gives check or mate.
+    ///
+    /// The move must be legal in this position (e.g. taken from
+    /// [`generate_legal_moves`](Self::generate_legal_moves)).
+    #[must_use]
+    pub fn to_san(&self, mv: Move) -> String {
+        let mut san = String::with_capacity(8);
+        let moving = self.piece_at(mv.from()).map_or(PieceType::Pawn, Piece::piece_type);
+
+        if mv.kind() == MoveKind::Castle {
+            san.push_str(if mv.to().file() == 6 { "O-O" } else { "O-O-O" });
+        } else {
+            let is_capture = self.piece_at(mv.to()).is_some() || mv.kind() == MoveKind::EnPassant;
+            if let Some(letter) = piece_letter(moving) {
+                san.push(letter);
+                // Disambiguate against other pieces of the same type that can
+                // reach the same square: file first, then rank, then both.
+                let rivals: Vec<Square> = self
+                    .generate_legal_moves()
+                    .into_iter()
+                    .filter(|m| {
+                        m.to() == mv.to()
+                            && m.from() != mv.from()
+                            && self.piece_at(m.from()).map(Piece::piece_type) == Some(moving)
+                    })
+                    .map(Move::from)
+                    .collect();
+                if !rivals.is_empty() {
+                    let file_unique = rivals.iter().all(|s| s.file() != mv.from().file());
+                    let rank_unique = rivals.iter().all(|s| s.rank() != mv.from().rank());
+                    let from = mv.from().to_string();
+                    if file_unique {
+                        san.push_str(&from[..1]);
+                    } else if rank_unique {
+                        san.push_str(&from[1..]);
+                    } else {
+                        san.push_str(&from);
+                    }
+                }
+            } else if is_capture {
+                san.push((b'a' + mv.from().file()) as char);
+            }
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&mv.to().to_string());
+            if let Some(pt) = mv.promotion_piece() {
+                san.push('=');
+                san.push(piece_letter(pt).unwrap_or('Q'));
+            }
+        }
+
+        let mut after = self.clone();
+        after.apply_unchecked(mv);
+        if after.is_in_check(after.side_to_move()) {
+            san.push(if after.game_state() == GameState::Checkmate { '#' } else { '+' });
+        }
+        san
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::TEST_POSITIONS;
+
+    fn board(fen: &str) -> Board {
+        Board::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn parses_basic_moves() {
+        let b = Board::new();
+        assert_eq!(b.parse_san("e4").unwrap().to_uci(), "e2e4");
+        assert_eq!(b.parse_san("Nf3").unwrap().to_uci(), "g1f3");
+        assert_eq!(b.parse_san("Nf3!?").unwrap().to_uci(), "g1f3");
+        assert!(b.parse_san("e5").is_none());
+        assert!(b.parse_san("Ke2").is_none());
+        assert!(b.parse_san("").is_none());
+        assert!(b.parse_san("Zz9").is_none());
+    }
+
+    #[test]
+    fn parses_castling() {
+        let b = board("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let short = b.parse_san("O-O").unwrap();
+        assert_eq!((short.to_uci().as_str(), short.kind()), ("e1g1", MoveKind::Castle));
+        assert_eq!(b.parse_san("0-0-0").unwrap().to_uci(), "e1c1");
+        assert_eq!(b.to_san(short), "O-O");
+    }
+
+    #[test]
+    fn parses_captures_and_en_passant() {
+        let b = board("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 1");
+        let ep = b.parse_san("exd6").unwrap();
+        assert_eq!(ep.kind(), MoveKind::EnPassant);
+        assert_eq!(b.to_san(ep), "exd6");
+    }
+
+    #[test]
+    fn parses_promotions() {
+        let b = board("k2r4/4P3/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(b.parse_san("e8=Q+").unwrap().to_uci(), "e7e8q");
+        assert_eq!(b.parse_san("e8N").unwrap().to_uci(), "e7e8n");
+        assert_eq!(b.parse_san("exd8=R+").unwrap().to_uci(), "e7d8r");
+        assert!(b.parse_san("e8").is_none(), "promotion piece is required");
+        assert_eq!(b.to_san(b.parse_san("exd8=Q").unwrap()), "exd8=Q+");
+    }
+
+    #[test]
+    fn disambiguation() {
+        // Knights on b1 and f1 can both reach d2; rooks on a1 and a5 both reach a3.
+        let b = board("4k3/8/8/R7/8/8/8/RN2KN2 w - - 0 1");
+        assert!(b.parse_san("Nd2").is_none(), "ambiguous");
+        assert_eq!(b.parse_san("Nbd2").unwrap().to_uci(), "b1d2");
+        assert_eq!(b.parse_san("Nfd2").unwrap().to_uci(), "f1d2");
+        assert_eq!(b.parse_san("R1a3").unwrap().to_uci(), "a1a3");
+        assert_eq!(b.parse_san("R5a3").unwrap().to_uci(), "a5a3");
+        assert_eq!(b.to_san(Move::normal(Square::new(1, 0), Square::new(3, 1))), "Nbd2");
+        assert_eq!(b.to_san(Move::normal(Square::new(0, 4), Square::new(0, 2))), "R5a3");
+    }
+
+    #[test]
+    fn checkmate_suffix() {
+        let b = board("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2");
+        let mv = b.parse_san("Qh4#").unwrap();
+        assert_eq!(b.to_san(mv), "Qh4#");
+    }
+
+    #[test]
+    fn san_round_trips_every_legal_move() {
+        for fen in TEST_POSITIONS {
+            let b = board(fen);
+            if b.is_in_check(b.side_to_move().opposite()) {
+                continue;
+            }
+            for mv in b.generate_legal_moves() {
+                let san = b.to_san(mv);
+                assert_eq!(b.parse_san(&san), Some(mv), "{fen}: {san}");
+            }
+        }
+    }
+}
@@ -0,0 +1,1301 @@
+//! Alpha-beta search.
+//!
+//! [`Searcher::search`] runs iterative deepening over a principal-variation
+//! search (PVS) with a capture-only quiescence search at the leaves. Moves are
+//! tried hash move first (from the [transposition table](crate::tt)), then
+//! captures by MVV-LVA, then killer moves, then quiet moves by history score.
+//! The search stops at the first of the depth, node, and time limits in
+//! [`SearchLimits`], or when the flag from [`Searcher::stop_handle`] is set.
+//! Searches on a chess clock ([`SearchLimits::clock`]) also stop between
+//! iterations as the [time manager](crate::timeman) decides. A limited
+//! [`Skill`] caps the limits further and may play a weaker root move.
+//!
+//! Scores are centipawns from the side to move's view. Mate scores are
+//! `MATE - plies` for the side delivering mate and `-(MATE - plies)` for the
+//! side being mated; [`mate_in`] converts them to moves.
+//!
+//! Time limits need `std::time::Instant`, which does not exist on
+//! `wasm32-unknown-unknown`; there the time limit is ignored and only the
+//! depth and node limits apply. WASI targets have a clock and behave natively.
+//!
+//! Native builds can search with several threads ([`Searcher::set_threads`])
+//! using Lazy SMP: helper threads run their own iterative deepening on the
+//! same position, one ply ahead on every other helper, and share only the
+//! lock-free transposition table. Their entries cut off and reorder the main
+//! thread's search, which alone decides the result. With one thread no helper
+//! exists and the search is deterministic. Threads are compiled out on
+//! `wasm32`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::board::Board;
+use crate::eval::EvalParams;
+#[cfg(feature = "nnue")]
+use crate::nnue::{Accumulator, Network};
+use crate::rng::Rng;
+use crate::skill::{Skill, SKILL_LINES};
+#[cfg(feature = "syzygy")]
+use crate::syzygy::{Syzygy, WdlScore};
+use crate::timeman::{TimeBudget, TimeControl, TimeManager};
+use crate::tt::{Bound, TranspositionTable, TtEntry, TtStats};
+use crate::types::{Color, Move, MoveKind, PieceType};
+
+/// Maximum search depth in plies, including extensions.
+pub const MAX_PLY: usize = 128;
+
+/// Score of delivering mate on the current move.
+pub const MATE: i32 = 32_000;
+
+/// Bound larger than any score the search returns.
+pub const INFINITY: i32 = 32_001;
+
+/// Deepest iteration `search` starts, leaving room for extensions.
+const MAX_DEPTH: u8 = 100;
+
+/// Default transposition table size in mebibytes.
+pub const DEFAULT_HASH_MB: usize = 16;
+
+/// Scores beyond this are mate scores.
+pub(crate) const MATE_BOUND: i32 = MATE - 128;
+
+/// Score of a tablebase win at the root, minus the ply it is found at:
+/// below any mate score, above any evaluation.
+#[cfg(feature = "syzygy")]
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const TB_WIN: i32 = MATE_BOUND - 1 - MAX_PLY as i32;
+
+/// Nodes between clock and stop-flag checks.
+const CHECK_INTERVAL: u64 = 1024;
+
+/// Converts a mate score into moves to mate: positive when the side to move
+/// mates, negative when it gets mated, `None` for ordinary scores.
+#[must_use]
+pub const fn mate_in(score: i32) -> Option<i32> {
+    if score >= MATE_BOUND {
+        Some((MATE - score + 1) / 2)
+    } else if score <= -MATE_BOUND {
+        Some(-(MATE + score) / 2)
+    } else {
+        None
+    }
+}
+
+/// When to stop searching. Unset limits are unbounded; with no limits at
+/// all the search runs to [`MAX_PLY`] or until stopped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// Maximum iterative-deepening depth in plies.
+    pub depth: Option<u8>,
+    /// Maximum nodes (including quiescence nodes).
+    pub nodes: Option<u64>,
+    /// Maximum wall-clock time.
+    pub time: Option<Duration>,
+    /// Target wall-clock time on a chess clock: checked between iterations,
+    /// scaled by the [time manager](crate::timeman), and bounded by `time`.
+    pub soft_time: Option<Duration>,
+}
+
+impl SearchLimits {
+    /// Search to a fixed depth.
+    #[must_use]
+    pub const fn depth(depth: u8) -> Self {
+        Self { depth: Some(depth), nodes: None, time: None, soft_time: None }
+    }
+
+    /// Search a fixed number of nodes.
+    #[must_use]
+    pub const fn nodes(nodes: u64) -> Self {
+        Self { depth: None, nodes: Some(nodes), time: None, soft_time: None }
+    }
+
+    /// Search for a fixed time.
+    #[must_use]
+    pub const fn time(time: Duration) -> Self {
+        Self { depth: None, nodes: None, time: Some(time), soft_time: None }
+    }
+
+    /// Search on a chess clock, within the [budget](TimeControl::budget) for
+    /// the next move.
+    #[must_use]
+    pub fn clock(clock: TimeControl) -> Self {
+        let budget = clock.budget();
+        Self { depth: None, nodes: None, time: Some(budget.hard), soft_time: Some(budget.soft) }
+    }
+}
+
+/// Outcome of the deepest completed iteration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    /// Best move found, or `None` if the side to move has no legal moves.
+    pub best_move: Option<Move>,
+    /// Score of `best_move` in centipawns from the side to move's view.
+    pub score: i32,
+    /// Depth of the last completed iteration.
+    pub depth: u8,
+    /// Deepest ply reached, including quiescence.
+    pub seldepth: u8,
+    /// Nodes searched so far.
+    pub nodes: u64,
+    /// Time spent so far (zero where no clock is available).
+    pub time: Duration,
+    /// Principal variation, starting with `best_move`.
+    pub pv: Vec<Move>,
+    /// The best lines, best first: just the principal variation unless more
+    /// were requested with [`Searcher::search_multi_pv`].
+    pub lines: Vec<PvLine>,
+    /// Transposition table statistics for this search.
+    pub tt: TtStats,
+}
+
+/// One of the best lines of a multi-PV search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PvLine {
+    /// Score in centipawns from the side to move's view.
+    pub score: i32,
+    /// Depth of the iteration that produced the line.
+    pub depth: u8,
+    /// The line, starting with its root move.
+    pub pv: Vec<Move>,
+}
+
+// ---------------------------------------------------------------------------
+// Clock — `Instant` panics on wasm32-unknown-unknown, so it is compiled out.
+// ---------------------------------------------------------------------------
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[derive(Clone, Copy)]
+struct Clock(std::time::Instant);
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Clock {
+    fn start() -> Self {
+        Self(std::time::Instant::now())
+    }
+
+    #[allow(clippy::unnecessary_wraps)] // Mirrors the clockless variant.
+    fn elapsed(self) -> Option<Duration> {
+        Some(self.0.elapsed())
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+#[derive(Clone, Copy)]
+struct Clock;
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl Clock {
+    const fn start() -> Self {
+        Self
+    }
+
+    const fn elapsed(self) -> Option<Duration> {
+        None
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Evaluation and move ordering
+// ---------------------------------------------------------------------------
+
+/// Piece values for MVV-LVA capture ordering, indexed by `PieceType`.
+const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+const HASH_MOVE_SCORE: i32 = 1 << 30;
+const CAPTURE_SCORE: i32 = 1 << 24;
+const KILLER_SCORE: i32 = 1 << 20;
+const HISTORY_MAX: i32 = 1 << 16;
+
+/// The captured piece type, if any (en passant captures a pawn).
+fn victim(board: &Board, mv: Move) -> Option<PieceType> {
+    if mv.kind() == MoveKind::EnPassant {
+        return Some(PieceType::Pawn);
+    }
+    board.piece_at(mv.to()).map(crate::types::Piece::piece_type)
+}
+
+/// Captures and queen promotions: the moves quiescence searches.
+fn is_tactical(board: &Board, mv: Move) -> bool {
+    victim(board, mv).is_some() || mv.promotion_piece() == Some(PieceType::Queen)
+}
+
+/// Does the position lack the material for either side to mate?
+fn insufficient_material(board: &Board) -> bool {
+    let heavy = [PieceType::Pawn, PieceType::Rook, PieceType::Queen]
+        .iter()
+        .any(|&pt| board.pieces(Color::White, pt) | board.pieces(Color::Black, pt) != 0);
+    !heavy && board.occupied().count_ones() <= 3
+}
+
+/// The score to return without searching, if a table entry is deep enough
+/// and its bound decides the `alpha..beta` window. PV nodes (open windows)
+/// always search so that the principal variation stays complete.
+fn tt_cutoff(e: &TtEntry, depth: i32, alpha: i32, beta: i32) -> Option<i32> {
+    let is_pv = beta - alpha > 1;
+    if is_pv || i32::from(e.depth) < depth {
+        return None;
+    }
+    let cutoff = match e.bound {
+        Bound::Exact => true,
+        Bound::Lower => e.score >= beta,
+        Bound::Upper => e.score <= alpha,
+    };
+    cutoff.then_some(e.score)
+}
+
+// ---------------------------------------------------------------------------
+// Searcher
+// ---------------------------------------------------------------------------
+
+/// Reusable search state: killer and history tables persist between searches
+/// so that consecutive moves of a game benefit from earlier work.
+pub struct Searcher {
+    killers: Vec<[Option<Move>; 2]>,
+    history: Vec<[[i32; 64]; 64]>,
+    game_keys: Vec<u64>,
+    keys: Vec<u64>,
+    pv: Vec<Vec<Move>>,
+    tt: Arc<TranspositionTable>,
+    nodes: u64,
+    seldepth: usize,
+    limits: SearchLimits,
+    clock: Clock,
+    stop: Arc<AtomicBool>,
+    aborted: bool,
+    /// Root moves already reported in the current multi-PV iteration.
+    excluded_root_moves: Vec<Move>,
+    skill: Skill,
+    /// Randomness for the moves a limited skill picks.
+    rng: Rng,
+    /// Weights of the hand-written evaluation.
+    params: Arc<EvalParams>,
+    #[cfg(feature = "nnue")]
+    nnue: Option<NnueState>,
+    #[cfg(feature = "syzygy")]
+    syzygy: Option<Arc<Syzygy>>,
+    /// Root moves the tablebases rank below the best, never searched.
+    #[cfg(feature = "syzygy")]
+    tb_excluded_root_moves: Vec<Move>,
+    /// Lazy SMP helpers, one per thread beyond the first, sharing `tt`.
+    #[cfg(not(target_arch = "wasm32"))]
+    helpers: Vec<Self>,
+}
+
+/// The network and one accumulator per ply of the current line.
+#[cfg(feature = "nnue")]
+struct NnueState {
+    net: Arc<Network>,
+    stack: Vec<Accumulator>,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Searcher {
+    /// Creates a searcher with empty tables and a
+    /// [`DEFAULT_HASH_MB`] transposition table.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            killers: vec![[None; 2]; MAX_PLY],
+            history: vec![[[0; 64]; 64]; 2],
+            game_keys: Vec::new(),
+            keys: Vec::with_capacity(MAX_PLY),
+            pv: vec![Vec::new(); MAX_PLY + 1],
+            tt: Arc::new(TranspositionTable::new(DEFAULT_HASH_MB)),
+            nodes: 0,
+            seldepth: 0,
+            limits: SearchLimits::default(),
+            clock: Clock::start(),
+            stop: Arc::new(AtomicBool::new(false)),
+            aborted: false,
+            excluded_root_moves: Vec::new(),
+            skill: Skill::default(),
+            rng: Rng::new(0),
+            params: Arc::new(EvalParams::DEFAULT),
+            #[cfg(feature = "nnue")]
+            nnue: None,
+            #[cfg(feature = "syzygy")]
+            syzygy: None,
+            #[cfg(feature = "syzygy")]
+            tb_excluded_root_moves: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            helpers: Vec::new(),
+        }
+    }
+
+    /// A helper for Lazy SMP sharing this searcher's table, evaluation
+    /// weights, network, and endgame tables.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn helper(&self) -> Self {
+        #[cfg_attr(not(any(feature = "nnue", feature = "syzygy")), allow(unused_mut))]
+        let mut helper = Self { tt: Arc::clone(&self.tt), params: Arc::clone(&self.params), ..Self::new() };
+        #[cfg(feature = "nnue")]
+        helper.set_network(self.nnue.as_ref().map(|nnue| Arc::clone(&nnue.net)));
+        #[cfg(feature = "syzygy")]
+        helper.set_syzygy(self.syzygy.clone());
+        helper
+    }
+
+    /// Searches with `threads` threads (at least 1) from now on.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_threads(&mut self, threads: usize) {
+        let helpers = threads.max(1) - 1;
+        self.helpers.truncate(helpers);
+        while self.helpers.len() < helpers {
+            let helper = self.helper();
+            self.helpers.push(helper);
+        }
+    }
+
+    /// Number of search threads.
+    #[must_use]
+    pub const fn threads(&self) -> usize {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.helpers.len() + 1
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            1
+        }
+    }
+
+    /// A flag that, once set, makes the running search return its best result
+    /// so far. It is cleared when a new search starts.
+    #[must_use]
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Sets the [`polyglot_key`](Board::polyglot_key)s of the positions played
+    /// before the one being searched, oldest first, so that the search can
+    /// recognize repetitions of them.
+    pub fn set_game_history(&mut self, keys: Vec<u64>) {
+        self.game_keys = keys;
+    }
+
+    /// Resizes the transposition table to about `mb` mebibytes (0 disables
+    /// it), discarding its contents.
+    pub fn set_hash_size(&mut self, mb: usize) {
+        self.tt = Arc::new(TranspositionTable::new(mb));
+        #[cfg(not(target_arch = "wasm32"))]
+        for helper in &mut self.helpers {
+            helper.tt = Arc::clone(&self.tt);
+        }
+    }
+
+    /// The transposition table, e.g. for its size or statistics.
+    #[must_use]
+    pub fn tt(&self) -> &TranspositionTable {
+        &self.tt
+    }
+
+    /// Forgets the transposition table, killer, and history statistics
+    /// (e.g. for a new game).
+    pub fn clear(&mut self) {
+        self.tt.clear();
+        self.killers.fill([None; 2]);
+        self.history.fill([[0; 64]; 64]);
+        #[cfg(not(target_arch = "wasm32"))]
+        for helper in &mut self.helpers {
+            helper.clear();
+        }
+    }
+
+    /// Evaluates with `params` instead of [`EvalParams::DEFAULT`] from now on.
+    pub fn set_eval_params(&mut self, params: Arc<EvalParams>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        for helper in &mut self.helpers {
+            helper.set_eval_params(Arc::clone(&params));
+        }
+        self.params = params;
+    }
+
+    /// The weights the hand-written evaluation uses.
+    #[must_use]
+    pub fn eval_params(&self) -> &EvalParams {
+        &self.params
+    }
+
+    /// Evaluates leaves with `net` instead of [`Board::evaluate`], or goes
+    /// back to the hand-written evaluation with `None`.
+    #[cfg(feature = "nnue")]
+    pub fn set_network(&mut self, net: Option<Arc<Network>>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        for helper in &mut self.helpers {
+            helper.set_network(net.clone());
+        }
+        self.nnue = net.map(|net| {
+            let root = net.refresh(&Board::new());
+            NnueState { net, stack: vec![root; MAX_PLY + 1] }
+        });
+    }
+
+    /// Probes `syzygy` from now on, or stops probing with `None`: the root
+    /// is restricted to the moves the tables rank best (see
+    /// [`Syzygy::rank_root_moves`]) and the search stops at positions the
+    /// tables cover right after a capture or pawn move, scoring wins and
+    /// losses just inside the mate scores and anything the fifty-move rule
+    /// saves as a draw.
+    #[cfg(feature = "syzygy")]
+    pub fn set_syzygy(&mut self, syzygy: Option<Arc<Syzygy>>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        for helper in &mut self.helpers {
+            helper.set_syzygy(syzygy.clone());
+        }
+        self.syzygy = syzygy;
+    }
+
+    /// Plays at `skill` from now on: below full strength the search limits
+    /// are capped and the move played may not be the best one found.
+    pub const fn set_skill(&mut self, skill: Skill) {
+        self.skill = skill;
+    }
+
+    #[must_use]
+    pub const fn skill(&self) -> Skill {
+        self.skill
+    }
+
+    /// Reseeds the randomness of a limited skill's move choice.
+    pub const fn seed_skill(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Searches `board` within `limits` and returns the best move found.
+    pub fn search(&mut self, board: &Board, limits: SearchLimits) -> SearchResult {
+        self.search_with(board, limits, |_| {})
+    }
+
+    /// Quiescence search alone from `board`: its score and the captures and
+    /// promotions of its principal variation, after which the position is
+    /// quiet. Tuning tools evaluate that final position.
+    pub fn quiescence(&mut self, board: &Board) -> (i32, Vec<Move>) {
+        self.prepare(board, SearchLimits::default());
+        let score = self.quiesce(board, 0, -INFINITY, INFINITY);
+        (score, self.pv[0].clone())
+    }
+
+    /// Like [`search`](Self::search), calling `on_iteration` after each
+    /// completed depth (e.g. to print UCI `info` lines).
+    pub fn search_with(
+        &mut self,
+        board: &Board,
+        limits: SearchLimits,
+        on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        self.search_multi_pv(board, limits, 1, on_iteration)
+    }
+
+    /// Like [`search_with`](Self::search_with), but finds the best `lines`
+    /// root moves (at most the number of legal moves), each with its own
+    /// score and principal variation, in [`SearchResult::lines`].
+    ///
+    /// Every iteration searches the root once per line, excluding the root
+    /// moves of the lines already found, so `n` lines cost roughly `n` times
+    /// a single-line search.
+    ///
+    /// With several threads, the node limit and the node counts passed to
+    /// `on_iteration` cover the main thread only; the returned result counts
+    /// the helpers' nodes too.
+    ///
+    /// With a limited [`Skill`] at least [`SKILL_LINES`] lines are searched
+    /// (only `lines` are reported), and the returned best move, score, and
+    /// principal variation are those of the line the skill picks.
+    pub fn search_multi_pv(
+        &mut self,
+        board: &Board,
+        limits: SearchLimits,
+        lines: usize,
+        mut on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let skill = self.skill;
+        if !skill.is_limited() {
+            return self.run(board, limits, lines, on_iteration);
+        }
+        let shown = lines.max(1);
+        let mut result = self.run(board, skill.limits(limits), shown.max(SKILL_LINES), |r| {
+            let mut r = r.clone();
+            r.lines.truncate(shown);
+            on_iteration(&r);
+        });
+        let chosen = result.lines[skill.pick(&result.lines, &mut self.rng)].clone();
+        result.best_move = chosen.pv.first().copied();
+        result.score = chosen.score;
+        result.pv = chosen.pv;
+        result.lines.truncate(shown);
+        result
+    }
+
+    /// The search behind [`search_multi_pv`](Self::search_multi_pv), at full
+    /// strength.
+    fn run(
+        &mut self,
+        board: &Board,
+        limits: SearchLimits,
+        lines: usize,
+        on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        self.stop.store(false, Ordering::Relaxed);
+        self.tt.new_search();
+        self.prepare(board, limits);
+
+        #[cfg_attr(not(feature = "syzygy"), allow(unused_mut))]
+        let mut root_moves = board.generate_legal_moves();
+        #[cfg(feature = "syzygy")]
+        self.restrict_root_moves(board, &mut root_moves);
+        let score = if root_moves.is_empty() && board.is_in_check(board.side_to_move()) {
+            -MATE
+        } else if root_moves.is_empty() {
+            0
+        } else {
+            self.static_eval(board, 0)
+        };
+        let pv: Vec<Move> = root_moves.first().copied().into_iter().collect();
+        let best = SearchResult {
+            best_move: root_moves.first().copied(),
+            score,
+            depth: 0,
+            seldepth: 0,
+            nodes: 0,
+            time: Duration::ZERO,
+            lines: vec![PvLine { score, depth: 0, pv: pv.clone() }],
+            pv,
+            tt: TtStats::default(),
+        };
+        if root_moves.is_empty() {
+            return best;
+        }
+
+        let wanted = lines.clamp(1, root_moves.len());
+        let max_depth = limits.depth.unwrap_or(u8::MAX).clamp(1, MAX_DEPTH);
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.helpers.is_empty() {
+            return self.search_parallel(board, &root_moves, wanted, max_depth, best, on_iteration);
+        }
+        self.iterate(board, &root_moves, wanted, max_depth, best, on_iteration)
+    }
+
+    /// Drops the root moves the tablebases rank below the best, remembering
+    /// them so that the search skips them too.
+    #[cfg(feature = "syzygy")]
+    fn restrict_root_moves(&mut self, board: &Board, root_moves: &mut Vec<Move>) {
+        self.tb_excluded_root_moves.clear();
+        let Some(ranked) = self.syzygy.as_ref().and_then(|tb| tb.rank_root_moves(board)) else {
+            return;
+        };
+        let Some(best) = ranked.first().map(|m| m.rank) else {
+            return;
+        };
+        self.tb_excluded_root_moves.extend(ranked.iter().filter(|m| m.rank < best).map(|m| m.mv));
+        root_moves.retain(|mv| !self.tb_excluded_root_moves.contains(mv));
+    }
+
+    /// The score of a position the tablebases cover, probed only right
+    /// after a zeroing move so the fifty-move counter is known to be fresh.
+    #[cfg(feature = "syzygy")]
+    fn probe_tablebases(&self, board: &Board, ply: i32) -> Option<i32> {
+        let tb = self.syzygy.as_ref()?;
+        if board.halfmove_clock() != 0 || board.occupied().count_ones() as usize > tb.max_pieces() {
+            return None;
+        }
+        Some(match board.probe_wdl(tb)? {
+            WdlScore::Win => TB_WIN - ply,
+            WdlScore::Loss => -TB_WIN + ply,
+            WdlScore::CursedWin | WdlScore::Draw | WdlScore::BlessedLoss => 0,
+        })
+    }
+
+    /// Resets the per-search state of this thread.
+    #[cfg_attr(not(feature = "nnue"), allow(unused_variables))]
+    fn prepare(&mut self, board: &Board, limits: SearchLimits) {
+        self.limits = limits;
+        self.clock = Clock::start();
+        self.nodes = 0;
+        self.seldepth = 0;
+        self.aborted = false;
+        self.killers.fill([None; 2]);
+        for row in self.history.iter_mut().flatten().flatten() {
+            *row /= 8;
+        }
+        #[cfg(feature = "nnue")]
+        if let Some(nnue) = &mut self.nnue {
+            nnue.stack[0] = nnue.net.refresh(board);
+        }
+    }
+
+    /// Runs the helpers alongside the main thread's [`iterate`](Self::iterate)
+    /// and stops them once it returns.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn search_parallel(
+        &mut self,
+        board: &Board,
+        root_moves: &[Move],
+        wanted: usize,
+        max_depth: u8,
+        best: SearchResult,
+        on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let mut helpers = std::mem::take(&mut self.helpers);
+        let helper_stop = Arc::new(AtomicBool::new(false));
+        let mut best = std::thread::scope(|scope| {
+            for (i, helper) in helpers.iter_mut().enumerate() {
+                helper.stop = Arc::clone(&helper_stop);
+                helper.game_keys.clone_from(&self.game_keys);
+                #[cfg(feature = "syzygy")]
+                helper.tb_excluded_root_moves.clone_from(&self.tb_excluded_root_moves);
+                let skip = u8::from(i % 2 == 0);
+                scope.spawn(move || helper.help(board, max_depth, skip));
+            }
+            let best = self.iterate(board, root_moves, wanted, max_depth, best, on_iteration);
+            helper_stop.store(true, Ordering::Relaxed);
+            best
+        });
+        best.nodes += helpers.iter().map(|helper| helper.nodes).sum::<u64>();
+        self.helpers = helpers;
+        best
+    }
+
+    /// A helper thread's search: iterative deepening starting `skip` plies
+    /// deeper than the main thread, until `max_depth` or the stop flag.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn help(&mut self, board: &Board, max_depth: u8, skip: u8) {
+        self.prepare(board, SearchLimits::default());
+        for depth in (1 + skip)..=max_depth {
+            if self.search_root_lines(board, depth, 1).is_none() {
+                break;
+            }
+        }
+    }
+
+    /// Iterative deepening from `best`, the result before the first iteration.
+    fn iterate(
+        &mut self,
+        board: &Board,
+        root_moves: &[Move],
+        wanted: usize,
+        max_depth: u8,
+        mut best: SearchResult,
+        mut on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let mut time_manager = self.limits.soft_time.map(|soft| {
+            TimeManager::new(TimeBudget { soft, hard: self.limits.time.unwrap_or(Duration::MAX) })
+        });
+        for depth in 1..=max_depth {
+            let Some(lines) = self.search_root_lines(board, depth, wanted) else {
+                break;
+            };
+            let score = lines[0].score;
+            best = SearchResult {
+                best_move: lines[0].pv.first().copied(),
+                score,
+                depth,
+                #[allow(clippy::cast_possible_truncation)]
+                seldepth: self.seldepth as u8,
+                nodes: self.nodes,
+                time: self.clock.elapsed().unwrap_or_default(),
+                pv: lines[0].pv.clone(),
+                lines,
+                tt: self.tt.stats(),
+            };
+            on_iteration(&best);
+
+            if let Some(tm) = &mut time_manager {
+                if tm.iteration_done(&best, root_moves.len(), self.clock.elapsed()) {
+                    break;
+                }
+            }
+
+            // A mate found within the full-width depth will not change.
+            if wanted == 1 && mate_in(score).is_some_and(|m| 2 * m.unsigned_abs() <= u32::from(depth) + 1) {
+                break;
+            }
+        }
+        best.nodes = self.nodes;
+        best.time = self.clock.elapsed().unwrap_or_default();
+        best.tt = self.tt.stats();
+        best
+    }
+
+    /// One iteration: the best `wanted` lines at `depth`, best first, or
+    /// `None` if the search was aborted before all of them were found.
+    fn search_root_lines(&mut self, board: &Board, depth: u8, wanted: usize) -> Option<Vec<PvLine>> {
+        let mut lines = Vec::with_capacity(wanted);
+        self.excluded_root_moves.clear();
+        #[cfg(feature = "syzygy")]
+        self.excluded_root_moves.extend_from_slice(&self.tb_excluded_root_moves);
+        while lines.len() < wanted {
+            self.keys.clone_from(&self.game_keys);
+            let score = self.negamax(board, i32::from(depth), 0, -INFINITY, INFINITY);
+            if self.aborted {
+                break;
+            }
+            let pv = self.pv[0].clone();
+            self.excluded_root_moves.extend(pv.first());
+            lines.push(PvLine { score, depth, pv });
+        }
+        self.excluded_root_moves.clear();
+        if self.aborted {
+            return None;
+        }
+        // Each search saw only the moves left over, but search instability
+        // can still hand a later line a higher score.
+        lines.sort_by_key(|line| std::cmp::Reverse(line.score));
+        Some(lines)
+    }
+
+    /// Sets `aborted` when a limit has been reached or a stop was requested.
+    fn check_limits(&mut self) -> bool {
+        if self.limits.nodes.is_some_and(|n| self.nodes >= n) {
+            self.aborted = true;
+        } else if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            let out_of_time = self
+                .limits
+                .time
+                .zip(self.clock.elapsed())
+                .is_some_and(|(limit, elapsed)| elapsed >= limit);
+            if out_of_time || self.stop.load(Ordering::Relaxed) {
+                self.aborted = true;
+            }
+        }
+        self.aborted
+    }
+
+    /// Has this position occurred before with the same side to move since the
+    /// last irreversible move?
+    fn is_repetition(&self, key: u64, halfmove_clock: u16) -> bool {
+        self.keys
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .take(usize::from(halfmove_clock / 2))
+            .any(|&k| k == key)
+    }
+
+    /// Orders `moves` by descending priority score.
+    fn score_moves(
+        &self,
+        board: &Board,
+        moves: Vec<Move>,
+        ply: usize,
+        hash_move: Option<Move>,
+    ) -> Vec<(Move, i32)> {
+        let side = board.side_to_move().index();
+        moves
+            .into_iter()
+            .map(|mv| {
+                let score = if Some(mv) == hash_move {
+                    HASH_MOVE_SCORE
+                } else if let Some(v) = victim(board, mv) {
+                    let attacker = board.piece_at(mv.from()).map_or(0, |p| p.piece_type() as i32);
+                    CAPTURE_SCORE + 8 * PIECE_VALUES[v as usize] - attacker
+                } else if mv.promotion_piece() == Some(PieceType::Queen) {
+                    CAPTURE_SCORE
+                } else if self.killers[ply][0] == Some(mv) {
+                    KILLER_SCORE + 1
+                } else if self.killers[ply][1] == Some(mv) {
+                    KILLER_SCORE
+                } else {
+                    self.history[side][mv.from().index()][mv.to().index()]
+                };
+                (mv, score)
+            })
+            .collect()
+    }
+
+    /// Moves the highest-scored remaining move to position `i` and returns it.
+    fn pick(moves: &mut [(Move, i32)], i: usize) -> Move {
+        let best = (i..moves.len()).max_by_key(|&j| moves[j].1).unwrap_or(i);
+        moves.swap(i, best);
+        moves[i].0
+    }
+
+    fn record_cutoff(&mut self, board: &Board, mv: Move, ply: usize, depth: i32) {
+        if self.killers[ply][0] != Some(mv) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(mv);
+        }
+        let side = board.side_to_move().index();
+        let entry = &mut self.history[side][mv.from().index()][mv.to().index()];
+        *entry += depth * depth;
+        if *entry > HISTORY_MAX {
+            for v in self.history[side].iter_mut().flatten() {
+                *v /= 2;
+            }
+        }
+    }
+
+    fn update_pv(&mut self, ply: usize, mv: Move) {
+        let (head, tail) = self.pv.split_at_mut(ply + 1);
+        head[ply].clear();
+        head[ply].push(mv);
+        head[ply].extend_from_slice(&tail[0]);
+    }
+
+    /// Static evaluation of `board`, the position at `ply` of the current line.
+    fn static_eval(&self, board: &Board, ply: usize) -> i32 {
+        // Endgame knowledge is exact, or closer to it, where the network
+        // only estimates.
+        #[cfg(feature = "nnue")]
+        if let Some(nnue) =
+            self.nnue.as_ref().filter(|_| board.kpk_probe().is_none() && board.endgame_eval().is_none())
+        {
+            return nnue.net.evaluate(&nnue.stack[ply], board.side_to_move());
+        }
+        #[cfg(not(feature = "nnue"))]
+        let _ = ply;
+        board.evaluate_with(&self.params)
+    }
+
+    /// Derives the accumulator for `ply + 1` from the one at `ply` and `mv`.
+    #[cfg(feature = "nnue")]
+    fn nnue_push(&mut self, board: &Board, mv: Move, ply: usize) {
+        if let Some(nnue) = &mut self.nnue {
+            let (head, tail) = nnue.stack.split_at_mut(ply + 1);
+            tail[0].clone_from(&head[ply]);
+            nnue.net.update(&mut tail[0], board, mv);
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn negamax(&mut self, board: &Board, mut depth: i32, ply: usize, mut alpha: i32, mut beta: i32) -> i32 {
+        self.pv[ply].clear();
+        let key = board.polyglot_key();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let ply_score = ply as i32; // ply < MAX_PLY
+
+        if ply > 0 {
+            if board.halfmove_clock() >= 100
+                || insufficient_material(board)
+                || self.is_repetition(key, board.halfmove_clock())
+            {
+                return 0;
+            }
+            // Mate distance pruning: no line from here beats a shorter mate.
+            alpha = alpha.max(-MATE + ply_score);
+            beta = beta.min(MATE - ply_score - 1);
+            if alpha >= beta {
+                return alpha;
+            }
+            #[cfg(feature = "syzygy")]
+            if let Some(score) = self.probe_tablebases(board, ply_score) {
+                return score;
+            }
+        }
+
+        let us = board.side_to_move();
+        let in_check = board.is_in_check(us);
+        if in_check && ply < MAX_PLY / 2 {
+            depth += 1;
+        }
+        if depth <= 0 {
+            return self.quiesce(board, ply, alpha, beta);
+        }
+        if ply >= MAX_PLY - 1 {
+            return self.static_eval(board, ply);
+        }
+
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        if self.check_limits() {
+            return 0;
+        }
+
+        let entry = self.tt.probe(key, ply);
+        if let Some(score) = entry.and_then(|e| tt_cutoff(&e, depth, alpha, beta)) {
+            return score;
+        }
+
+        let hash_move = entry.and_then(|e| e.best_move);
+        let mut moves = self.score_moves(board, board.generate_pseudo_legal(), ply, hash_move);
+        self.keys.push(key);
+        let original_alpha = alpha;
+        let mut legal = 0;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for i in 0..moves.len() {
+            let mv = Self::pick(&mut moves, i);
+            if ply == 0 && self.excluded_root_moves.contains(&mv) {
+                continue;
+            }
+            let mut child = board.clone();
+            child.apply_unchecked(mv);
+            if child.is_in_check(us) {
+                continue;
+            }
+            #[cfg(feature = "nnue")]
+            self.nnue_push(board, mv, ply);
+            legal += 1;
+
+            let score = if legal == 1 {
+                -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha)
+            } else {
+                let s = -self.negamax(&child, depth - 1, ply + 1, -alpha - 1, -alpha);
+                if s > alpha && s < beta {
+                    -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha)
+                } else {
+                    s
+                }
+            };
+            if self.aborted {
+                self.keys.pop();
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = Some(mv);
+                    self.update_pv(ply, mv);
+                    if score >= beta {
+                        if !is_tactical(board, mv) {
+                            self.record_cutoff(board, mv, ply, depth);
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        self.keys.pop();
+
+        if legal == 0 {
+            return if in_check { -MATE + ply_score } else { 0 };
+        }
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        self.tt.store(key, ply, depth as u8, best_score, bound, best_move);
+        best_score
+    }
+
+    fn quiesce(&mut self, board: &Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv[ply].clear();
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        if self.check_limits() {
+            return 0;
+        }
+
+        let stand_pat = self.static_eval(board, ply);
+        if stand_pat >= beta || ply >= MAX_PLY - 1 {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let tactical: Vec<Move> = board
+            .generate_pseudo_legal()
+            .into_iter()
+            .filter(|&mv| is_tactical(board, mv))
+            .collect();
+        let mut moves = self.score_moves(board, tactical, ply, None);
+        let us = board.side_to_move();
+        let mut best_score = stand_pat;
+        for i in 0..moves.len() {
+            let mv = Self::pick(&mut moves, i);
+            let mut child = board.clone();
+            child.apply_unchecked(mv);
+            if child.is_in_check(us) {
+                continue;
+            }
+            #[cfg(feature = "nnue")]
+            self.nnue_push(board, mv, ply);
+            let score = -self.quiesce(&child, ply + 1, -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(ply, mv);
+                    if score >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        best_score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::TEST_POSITIONS;
+
+    fn search(fen: &str, depth: u8) -> SearchResult {
+        Searcher::new().search(&Board::from_fen(fen).unwrap(), SearchLimits::depth(depth))
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        let r = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+        assert_eq!(r.best_move.unwrap().to_uci(), "a1a8");
+        assert_eq!(r.score, MATE - 1);
+        assert_eq!(mate_in(r.score), Some(1));
+    }
+
+    #[cfg(feature = "nnue")]
+    #[test]
+    fn searches_with_network() {
+        use crate::nnue::{QA, QB, SCALE};
+        // A bias-only network: every position evaluates to SCALE / 4 for the mover.
+        let mut bytes = b"CENN\x01\x00\x10\x00".to_vec();
+        bytes.resize(bytes.len() + (768 + 3) * 16 * 2, 0);
+        bytes.extend_from_slice(&(QA * QB / 4).to_le_bytes());
+        let net = crate::nnue::Network::from_bytes(&bytes).unwrap();
+
+        let mut searcher = Searcher::new();
+        searcher.set_network(Some(Arc::new(net)));
+        let r = searcher.search(&Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap(), SearchLimits::depth(3));
+        assert_eq!(r.best_move.unwrap().to_uci(), "a1a8");
+        // After any move the opponent is the one enjoying the bonus.
+        let r = searcher.search(&Board::new(), SearchLimits::depth(1));
+        assert_eq!(r.score, -SCALE / 4);
+    }
+
+    #[test]
+    fn multi_pv_lines() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let r = Searcher::new().search_multi_pv(&board, SearchLimits::depth(3), 4, |_| {});
+        assert_eq!(r.lines.len(), 4);
+        assert_eq!(r.lines[0].pv[0].to_uci(), "a1a8");
+        assert_eq!(r.lines[0].score, MATE - 1);
+        assert_eq!((r.best_move, r.score, &r.pv), (Some(r.lines[0].pv[0]), r.lines[0].score, &r.lines[0].pv));
+        let legal = board.generate_legal_moves();
+        for (i, line) in r.lines.iter().enumerate() {
+            assert_eq!(line.depth, 3);
+            assert!(legal.contains(&line.pv[0]));
+            assert!(r.lines[..i].iter().all(|earlier| earlier.pv[0] != line.pv[0]));
+            assert!(r.lines[..i].iter().all(|earlier| earlier.score >= line.score));
+        }
+
+        // No more lines than legal moves: only Kxg1 here.
+        let board = Board::from_fen("8/8/8/8/8/5k2/8/6qK w - - 0 1").unwrap();
+        let r = Searcher::new().search_multi_pv(&board, SearchLimits::depth(2), 5, |_| {});
+        assert_eq!(r.lines.len(), 1);
+        assert_eq!(search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3).lines.len(), 1);
+    }
+
+    #[test]
+    fn finds_mate_in_two() {
+        // 1. Nf6+ gxf6 2. Bxf7#
+        let r = search("r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1", 4);
+        assert_eq!(r.best_move.unwrap().to_uci(), "d5f6");
+        assert_eq!(mate_in(r.score), Some(2));
+        assert_eq!(r.pv.len(), 3);
+    }
+
+    #[test]
+    fn reports_being_mated() {
+        // 1... Kb8 2. Rh8#
+        let r = search("k7/8/1K6/8/8/8/8/7R b - - 0 1", 3);
+        assert_eq!(r.best_move.unwrap().to_uci(), "a8b8");
+        assert_eq!(mate_in(r.score), Some(-1));
+    }
+
+    #[test]
+    fn wins_hanging_queen() {
+        let r = search("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 2);
+        assert_eq!(r.best_move.unwrap().to_uci(), "d2d5");
+        assert!(r.score > 400);
+    }
+
+    #[test]
+    fn quiescence_sees_recapture() {
+        // Rxd5 exd5 trades rook for queen: good, but not a free queen. (The
+        // pawns keep the result out of the KPK bitbase.)
+        let r = search("4k3/7p/4p3/3q4/8/8/P2R4/4K3 w - - 0 1", 1);
+        assert_eq!(r.best_move.unwrap().to_uci(), "d2d5");
+        assert!(r.score < 0, "{r:?}");
+    }
+
+    #[test]
+    fn no_moves_at_root() {
+        let mated = search("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3", 3);
+        assert_eq!((mated.best_move, mated.score), (None, -MATE));
+        let stalemate = search("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3);
+        assert_eq!((stalemate.best_move, stalemate.score), (None, 0));
+    }
+
+    #[test]
+    fn avoids_stalemating_when_winning() {
+        // Qf7 stalemates; Qf8 mates.
+        let r = search("7k/8/6K1/8/8/8/8/5Q2 w - - 0 1", 3);
+        assert_eq!(r.best_move.unwrap().to_uci(), "f1f8");
+        assert_eq!(mate_in(r.score), Some(1));
+    }
+
+    #[test]
+    fn repetition_counts_as_draw() {
+        // A queen down, White steers into a position from the game history.
+        let board = Board::from_fen("6k1/8/8/8/8/8/q7/4K3 w - - 4 10").unwrap();
+        let mut s = Searcher::new();
+        assert!(s.search(&board, SearchLimits::depth(3)).score < -500);
+
+        let mut repeated = board.clone();
+        repeated.make_move(Move::from_uci("e1d1").unwrap());
+        s.set_game_history(vec![repeated.polyglot_key()]);
+        let r = s.search(&board, SearchLimits::depth(3));
+        assert_eq!((r.best_move.unwrap().to_uci().as_str(), r.score), ("e1d1", 0));
+    }
+
+    #[test]
+    fn node_limit_is_respected() {
+        let mut s = Searcher::new();
+        let r = s.search(&Board::new(), SearchLimits::nodes(5_000));
+        assert!(r.nodes <= 5_000 + 1, "{}", r.nodes);
+        assert!(r.best_move.is_some());
+    }
+
+    #[test]
+    fn time_limit_is_respected() {
+        let mut s = Searcher::new();
+        let r = s.search(&Board::new(), SearchLimits::time(Duration::from_millis(50)));
+        assert!(r.time < Duration::from_millis(500), "{:?}", r.time);
+        assert!(r.depth >= 1);
+    }
+
+    #[test]
+    fn clock_search_stops_on_forced_move() {
+        let clock = TimeControl::new(Duration::from_mins(1), Duration::ZERO, None);
+        // Only Kxg1 is legal.
+        let board = Board::from_fen("8/8/8/8/8/5k2/8/6qK w - - 0 1").unwrap();
+        let r = Searcher::new().search(&board, SearchLimits::clock(clock));
+        assert_eq!((r.best_move.unwrap().to_uci().as_str(), r.depth), ("h1g1", 1));
+
+        // A short clock ends the search between iterations, within the hard limit.
+        let clock = TimeControl::new(Duration::from_millis(600), Duration::ZERO, None);
+        let limits = SearchLimits::clock(clock);
+        let r = Searcher::new().search(&Board::new(), limits);
+        assert!(r.time < limits.time.unwrap() + Duration::from_millis(100), "{:?}", r.time);
+        assert!(r.depth >= 1);
+    }
+
+    #[test]
+    fn limited_skill_plays_a_searched_line() {
+        let board = Board::from_fen(crate::board::tests::KIWIPETE).unwrap();
+        let mut s = Searcher::new();
+        s.set_skill(Skill::new(0));
+        let mut reported = Vec::new();
+        let r = s.search_with(&board, SearchLimits::depth(6), |r| reported.push(r.lines.len()));
+        // Level 0 stops at depth 1 and reports only the requested line.
+        assert_eq!((r.depth, r.lines.len(), reported), (1, 1, vec![1]));
+        assert!(board.generate_legal_moves().contains(&r.best_move.unwrap()));
+        assert_eq!(r.pv.first().copied(), r.best_move);
+    }
+
+    #[test]
+    fn stop_flag_ends_search() {
+        let mut s = Searcher::new();
+        let stop = s.stop_handle();
+        let mut iterations = 0;
+        let r = s.search_with(&Board::new(), SearchLimits::default(), |_| {
+            iterations += 1;
+            if iterations == 3 {
+                stop.store(true, Ordering::Relaxed);
+            }
+        });
+        assert_eq!(r.depth, 3);
+    }
+
+    #[test]
+    fn pv_is_legal_and_starts_with_best_move() {
+        for fen in TEST_POSITIONS.iter().take(12) {
+            let board = Board::from_fen(fen).unwrap();
+            if board.is_in_check(board.side_to_move().opposite()) {
+                continue;
+            }
+            let r = Searcher::new().search(&board, SearchLimits::depth(3));
+            assert_eq!(r.pv.first().copied(), r.best_move, "{fen}");
+            let mut b = board.clone();
+            for mv in &r.pv {
+                assert!(b.make_move(*mv), "{fen}: illegal PV move {mv}");
+            }
+        }
+    }
+
+    #[test]
+    fn deterministic() {
+        let a = search(crate::board::tests::KIWIPETE, 4);
+        let b = search(crate::board::tests::KIWIPETE, 4);
+        assert_eq!(a, SearchResult { time: a.time, ..b });
+    }
+
+    #[test]
+    fn lazy_smp_search() {
+        let mut s = Searcher::new();
+        s.set_threads(4);
+        assert_eq!(s.threads(), 4);
+        let board = Board::from_fen("r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1").unwrap();
+        let r = s.search(&board, SearchLimits::depth(4));
+        assert_eq!(r.best_move.unwrap().to_uci(), "d5f6");
+        assert_eq!(mate_in(r.score), Some(2));
+
+        // The helpers stop with the main thread and count towards the nodes.
+        let board = Board::from_fen(crate::board::tests::KIWIPETE).unwrap();
+        let stop = s.stop_handle();
+        let mut main_nodes = 0;
+        let r = s.search_with(&board, SearchLimits::default(), |r| {
+            main_nodes = r.nodes;
+            if r.depth == 4 {
+                stop.store(true, Ordering::Relaxed);
+            }
+        });
+        assert_eq!(r.depth, 4);
+        assert!(r.nodes >= main_nodes);
+        let mut b = board.clone();
+        for mv in &r.pv {
+            assert!(b.make_move(*mv), "illegal PV move {mv}");
+        }
+
+        s.set_threads(0);
+        assert_eq!(s.threads(), 1);
+    }
+
+    #[test]
+    fn transposition_table_saves_nodes() {
+        let board = Board::from_fen(crate::board::tests::KIWIPETE).unwrap();
+        let mut without = Searcher::new();
+        without.set_hash_size(0);
+        let plain = without.search(&board, SearchLimits::depth(5));
+        assert_eq!(plain.tt, TtStats::default());
+
+        let hashed = Searcher::new().search(&board, SearchLimits::depth(5));
+        assert!(hashed.nodes < plain.nodes, "{} vs {}", hashed.nodes, plain.nodes);
+        assert!(hashed.tt.hits > 0 && hashed.tt.hits <= hashed.tt.probes);
+        assert!(hashed.tt.hit_rate() > 0.0 && hashed.tt.hit_rate() < 1.0);
+    }
+
+    #[test]
+    fn mate_scores_survive_the_table() {
+        // Searching twice reuses mate entries stored at other plies.
+        let board = Board::from_fen("r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1").unwrap();
+        let mut s = Searcher::new();
+        for _ in 0..2 {
+            let r = s.search(&board, SearchLimits::depth(6));
+            assert_eq!(mate_in(r.score), Some(2), "{r:?}");
+        }
+    }
+
+    #[test]
+    fn mate_in_conversion() {
+        assert_eq!(mate_in(MATE - 1), Some(1));
+        assert_eq!(mate_in(MATE - 3), Some(2));
+        assert_eq!(mate_in(-MATE + 2), Some(-1));
+        assert_eq!(mate_in(-MATE + 4), Some(-2));
+        assert_eq!(mate_in(250), None);
+    }
+}
@@ -0,0 +1,278 @@
+//! Optional `serde` support (enabled with the `serde` cargo feature).
+//!
+//! Every type serializes to the same string form the rest of the ecosystem
+//! already uses, so JSON produced here is readable by the frontend and by
+//! hand-written tooling:
+//!
+//! | Type               | Format                          | Example        |
+//! |--------------------|---------------------------------|----------------|
+//! | [`Square`]         | algebraic                       | `"e4"`         |
+//! | [`Piece`]          | FEN character                   | `"N"`, `"q"`   |
+//! | [`Move`]           | UCI                             | `"e7e8q"`      |
+//! | [`CastlingRights`] | FEN castling field              | `"KQk"`, `"-"` |
+//! | [`GameState`]      | kebab-case name                 | `"checkmate"`  |
+//! | [`Board`]          | full FEN                        | `"rnbqkbnr/…"` |
+//!
+//! Deserialized moves carry [`MoveKind::Normal`](crate::types::MoveKind::Normal)
+//! unless they are promotions, exactly like [`Move::from_uci`]; the board
+//! resolves castling and en passant when the move is made.
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::board::Board;
+use crate::types::{CastlingRights, GameState, Move, Piece, Square};
+
+/// Deserializes a string and converts it with `parse`, reporting `expecting`
+/// when the conversion fails.
+fn deserialize_str<'de, D, T>(
+    deserializer: D,
+    expecting: &'static str,
+    parse: fn(&str) -> Option<T>,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct StrVisitor<T> {
+        expecting: &'static str,
+        parse: fn(&str) -> Option<T>,
+    }
+
+    impl<T> Visitor<'_> for StrVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.expecting)
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+            (self.parse)(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+
+    deserializer.deserialize_str(StrVisitor { expecting, parse })
+}
+
+// ---------------------------------------------------------------------------
+// String conversions that have no public counterpart elsewhere.
+// ---------------------------------------------------------------------------
+
+fn piece_from_str(s: &str) -> Option<Piece> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Piece::from_fen_char(c),
+        _ => None,
+    }
+}
+
+fn castling_to_str(rights: CastlingRights) -> String {
+    let mut s = String::with_capacity(4);
+    if rights.white_kingside() { s.push('K'); }
+    if rights.white_queenside() { s.push('Q'); }
+    if rights.black_kingside() { s.push('k'); }
+    if rights.black_queenside() { s.push('q'); }
+    if s.is_empty() { s.push('-'); }
+    s
+}
+
+fn castling_from_str(s: &str) -> Option<CastlingRights> {
+    if s == "-" {
+        return Some(CastlingRights::NONE);
+    }
+    if s.is_empty() {
+        return None;
+    }
+    let mut bits = 0u8;
+    for c in s.chars() {
+        let bit = match c {
+            'K' => CastlingRights::WK,
+            'Q' => CastlingRights::WQ,
+            'k' => CastlingRights::BK,
+            'q' => CastlingRights::BQ,
+            _ => return None,
+        };
+        if bits & bit != 0 {
+            return None;
+        }
+        bits |= bit;
+    }
+    Some(CastlingRights(bits))
+}
+
+const fn game_state_to_str(state: GameState) -> &'static str {
+    match state {
+        GameState::InProgress => "in-progress",
+        GameState::Checkmate => "checkmate",
+        GameState::Stalemate => "stalemate",
+        GameState::Draw => "draw",
+    }
+}
+
+fn game_state_from_str(s: &str) -> Option<GameState> {
+    match s {
+        "in-progress" => Some(GameState::InProgress),
+        "checkmate" => Some(GameState::Checkmate),
+        "stalemate" => Some(GameState::Stalemate),
+        "draw" => Some(GameState::Draw),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Impls
+// ---------------------------------------------------------------------------
+
+impl Serialize for Square {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Square {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, "an algebraic square like \"e4\"", Self::from_algebraic)
+    }
+}
+
+impl Serialize for Piece {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut buf = [0u8; 4];
+        serializer.serialize_str(self.to_fen_char().encode_utf8(&mut buf))
+    }
+}
+
+impl<'de> Deserialize<'de> for Piece {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, "a FEN piece character like \"N\" or \"q\"", piece_from_str)
+    }
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, "a UCI move like \"e2e4\" or \"e7e8q\"", Self::from_uci)
+    }
+}
+
+impl Serialize for CastlingRights {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&castling_to_str(*self))
+    }
+}
+
+impl<'de> Deserialize<'de> for CastlingRights {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, "a FEN castling field like \"KQkq\" or \"-\"", castling_from_str)
+    }
+}
+
+impl Serialize for GameState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(game_state_to_str(*self))
+    }
+}
+
+impl<'de> Deserialize<'de> for GameState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(
+            deserializer,
+            "one of \"in-progress\", \"checkmate\", \"stalemate\", \"draw\"",
+            game_state_from_str,
+        )
+    }
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_fen())
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, "a FEN position", Self::from_fen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Color, PieceType};
+
+    fn round_trip<T>(value: &T, json: &str)
+    where
+        T: Serialize + for<'de> Deserialize<'de> + PartialEq + fmt::Debug,
+    {
+        assert_eq!(serde_json::to_string(value).unwrap(), json);
+        assert_eq!(&serde_json::from_str::<T>(json).unwrap(), value);
+    }
+
+    #[test]
+    fn square_round_trip() {
+        round_trip(&Square::from_algebraic("e4").unwrap(), "\"e4\"");
+        for i in 0..64 {
+            let sq = Square::from_index(i);
+            let json = serde_json::to_string(&sq).unwrap();
+            assert_eq!(serde_json::from_str::<Square>(&json).unwrap(), sq);
+        }
+        assert!(serde_json::from_str::<Square>("\"i9\"").is_err());
+        assert!(serde_json::from_str::<Square>("12").is_err());
+    }
+
+    #[test]
+    fn piece_round_trip() {
+        round_trip(&Piece::new(PieceType::Knight, Color::White), "\"N\"");
+        round_trip(&Piece::new(PieceType::Queen, Color::Black), "\"q\"");
+        assert!(serde_json::from_str::<Piece>("\"x\"").is_err());
+        assert!(serde_json::from_str::<Piece>("\"NN\"").is_err());
+    }
+
+    #[test]
+    fn move_round_trip() {
+        round_trip(&Move::from_uci("e2e4").unwrap(), "\"e2e4\"");
+        round_trip(&Move::from_uci("e7e8q").unwrap(), "\"e7e8q\"");
+        assert!(serde_json::from_str::<Move>("\"e2\"").is_err());
+    }
+
+    #[test]
+    fn castling_round_trip() {
+        round_trip(&CastlingRights::ALL, "\"KQkq\"");
+        round_trip(&CastlingRights::NONE, "\"-\"");
+        round_trip(&CastlingRights(CastlingRights::WK | CastlingRights::BQ), "\"Kq\"");
+        assert!(serde_json::from_str::<CastlingRights>("\"\"").is_err());
+        assert!(serde_json::from_str::<CastlingRights>("\"KK\"").is_err());
+        assert!(serde_json::from_str::<CastlingRights>("\"X\"").is_err());
+    }
+
+    #[test]
+    fn game_state_round_trip() {
+        round_trip(&GameState::InProgress, "\"in-progress\"");
+        round_trip(&GameState::Checkmate, "\"checkmate\"");
+        round_trip(&GameState::Stalemate, "\"stalemate\"");
+        round_trip(&GameState::Draw, "\"draw\"");
+        assert!(serde_json::from_str::<GameState>("\"won\"").is_err());
+    }
+
+    #[test]
+    fn board_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let json = serde_json::to_string(&board).unwrap();
+            assert_eq!(json, format!("\"{fen}\""));
+            let back: Board = serde_json::from_str(&json).unwrap();
+            assert_eq!(back.to_fen(), fen);
+        }
+        assert!(serde_json::from_str::<Board>("\"not a fen\"").is_err());
+    }
+}
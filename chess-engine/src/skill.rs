@@ -0,0 +1,272 @@
+//! Playing strength limits.
+//!
+//! A [`Skill`] level from 0 to [`MAX_LEVEL`] weakens the engine in two ways:
+//! the search is capped in depth and nodes, and the move is picked from the
+//! best [`SKILL_LINES`] root moves with a controlled random bias towards the
+//! worse ones (the scheme Stockfish uses). [`MAX_LEVEL`] is full strength.
+//!
+//! [`Skill::from_elo`] maps a nominal Elo between [`MIN_ELO`] and
+//! [`MAX_ELO`] onto the levels, for the UCI `UCI_LimitStrength`/`UCI_Elo`
+//! options. The mapping is a straight line, not a measurement; what the
+//! levels do guarantee is that they get stronger in order, which
+//! [`play_match`] checks by playing them against each other.
+
+use crate::board::Board;
+use crate::rng::Rng;
+use crate::search::{PvLine, SearchLimits, Searcher};
+use crate::types::{Color, GameState};
+
+/// Full strength.
+pub const MAX_LEVEL: u8 = 20;
+
+/// Elo of level 0.
+pub const MIN_ELO: u32 = 500;
+
+/// Elo of [`MAX_LEVEL`].
+pub const MAX_ELO: u32 = 2500;
+
+/// Root moves searched to choose from when the strength is limited.
+pub const SKILL_LINES: usize = 4;
+
+/// Nodes allowed at level 0, enough for its one-ply search of
+/// [`SKILL_LINES`] lines in busy positions; every two levels double it.
+const BASE_NODES: u64 = 50_000;
+
+/// Largest score gap, in centipawns, that the random bias scales with.
+const MAX_DELTA: i32 = 100;
+
+/// A playing strength.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Skill {
+    level: u8,
+}
+
+impl Default for Skill {
+    /// Full strength.
+    fn default() -> Self {
+        Self::new(MAX_LEVEL)
+    }
+}
+
+impl Skill {
+    /// Level `level`, clamped to `0..=MAX_LEVEL`.
+    #[must_use]
+    pub const fn new(level: u8) -> Self {
+        Self { level: if level > MAX_LEVEL { MAX_LEVEL } else { level } }
+    }
+
+    /// The level nearest below a nominal `elo`, clamped to the level range.
+    #[must_use]
+    pub fn from_elo(elo: u32) -> Self {
+        let span = MAX_ELO - MIN_ELO;
+        let level = elo.clamp(MIN_ELO, MAX_ELO).saturating_sub(MIN_ELO) * u32::from(MAX_LEVEL) / span;
+        Self::new(u8::try_from(level).unwrap_or(MAX_LEVEL))
+    }
+
+    #[must_use]
+    pub const fn level(self) -> u8 {
+        self.level
+    }
+
+    /// The nominal Elo of this level.
+    #[must_use]
+    pub const fn elo(self) -> u32 {
+        MIN_ELO + (MAX_ELO - MIN_ELO) * self.level as u32 / MAX_LEVEL as u32
+    }
+
+    /// Below full strength?
+    #[must_use]
+    pub const fn is_limited(self) -> bool {
+        self.level < MAX_LEVEL
+    }
+
+    /// `limits` tightened to this level's depth and node caps.
+    #[must_use]
+    pub fn limits(self, limits: SearchLimits) -> SearchLimits {
+        if !self.is_limited() {
+            return limits;
+        }
+        let depth = 1 + self.level / 2;
+        let nodes = BASE_NODES << (self.level / 2);
+        SearchLimits {
+            depth: Some(limits.depth.map_or(depth, |d| d.min(depth))),
+            nodes: Some(limits.nodes.map_or(nodes, |n| n.min(nodes))),
+            ..limits
+        }
+    }
+
+    /// Index of the line to play among `lines` (best first, not empty).
+    ///
+    /// Each line's score gets a random push that grows with its deficit to
+    /// the best line and with the level's weakness; the highest pushed score
+    /// wins, so weak levels often play the second or third best move while
+    /// strong ones rarely leave the first.
+    pub(crate) fn pick(self, lines: &[PvLine], rng: &mut Rng) -> usize {
+        if !self.is_limited() || lines.len() < 2 {
+            return 0;
+        }
+        let top = lines[0].score;
+        let delta = (top - lines[lines.len() - 1].score).min(MAX_DELTA);
+        let weakness = 120 - 2 * i32::from(self.level);
+        let mut best = (i32::MIN, 0);
+        for (i, line) in lines.iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let noise = rng.below(weakness.unsigned_abs().into()) as i32;
+            let push = (weakness * (top - line.score) + delta * noise) / 128;
+            if line.score + push >= best.0 {
+                best = (line.score + push, i);
+            }
+        }
+        best.1
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Calibration
+// ---------------------------------------------------------------------------
+
+/// Plies after which a calibration game is adjudicated.
+const MAX_GAME_PLIES: usize = 200;
+
+/// Static evaluation that adjudicates an unfinished game as a win.
+const ADJUDICATION_MARGIN: i32 = 400;
+
+/// Outcome of a match from the first player's point of view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    /// Points per game in `0.0..=1.0` (draws count half), or 0.5 for no games.
+    #[must_use]
+    pub fn score(&self) -> f64 {
+        let games = self.wins + self.draws + self.losses;
+        if games == 0 {
+            return 0.5;
+        }
+        (f64::from(self.wins) + f64::from(self.draws) / 2.0) / f64::from(games)
+    }
+}
+
+/// Plays `games` games between `a` and `b` from the starting position,
+/// alternating colours.
+///
+/// Each side searches within `limits` as tightened by its skill. Games are
+/// drawn by stalemate, the fifty-move rule, insufficient material, or
+/// threefold repetition, and adjudicated by static evaluation after
+/// [`MAX_GAME_PLIES`]. `seed` makes the match reproducible.
+#[must_use]
+pub fn play_match(a: Skill, b: Skill, games: u32, limits: SearchLimits, seed: u64) -> MatchScore {
+    let mut score = MatchScore::default();
+    for game in 0..games {
+        let mut players = [Searcher::new(), Searcher::new()];
+        players[0].set_skill(a);
+        players[1].set_skill(b);
+        for (i, player) in players.iter_mut().enumerate() {
+            player.seed_skill(seed ^ (u64::from(game) << 8) ^ i as u64);
+        }
+        // Player `a` is White in even games.
+        let a_is_white = game % 2 == 0;
+        let white_points = play_game(&mut players, a_is_white, limits);
+        let a_points = if a_is_white { white_points } else { 2 - white_points };
+        match a_points {
+            2 => score.wins += 1,
+            1 => score.draws += 1,
+            _ => score.losses += 1,
+        }
+    }
+    score
+}
+
+/// One game; returns White's result in half points (0, 1, or 2).
+fn play_game(players: &mut [Searcher; 2], first_is_white: bool, limits: SearchLimits) -> u8 {
+    let mut board = Board::new();
+    let mut keys = vec![board.polyglot_key()];
+    for ply in 0..MAX_GAME_PLIES {
+        match board.game_state() {
+            GameState::Checkmate => return if board.side_to_move() == Color::White { 0 } else { 2 },
+            GameState::Stalemate | GameState::Draw => return 1,
+            GameState::InProgress => {}
+        }
+        if keys.iter().filter(|&&k| k == board.polyglot_key()).count() >= 3 {
+            return 1;
+        }
+        let player = &mut players[usize::from((ply % 2 == 0) != first_is_white)];
+        player.set_game_history(keys[..keys.len() - 1].to_vec());
+        let Some(mv) = player.search(&board, limits).best_move else {
+            return 1;
+        };
+        board.make_move(mv);
+        keys.push(board.polyglot_key());
+    }
+    let eval = board.evaluate();
+    let white_eval = if board.side_to_move() == Color::White { eval } else { -eval };
+    match white_eval {
+        e if e >= ADJUDICATION_MARGIN => 2,
+        e if e <= -ADJUDICATION_MARGIN => 0,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(score: i32) -> PvLine {
+        PvLine { score, depth: 1, pv: Vec::new() }
+    }
+
+    #[test]
+    fn levels_and_elo() {
+        assert_eq!(Skill::default().level(), MAX_LEVEL);
+        assert!(!Skill::default().is_limited());
+        assert_eq!(Skill::new(99).level(), MAX_LEVEL);
+        assert_eq!(Skill::from_elo(0).level(), 0);
+        assert_eq!(Skill::from_elo(1500).level(), 10);
+        assert_eq!(Skill::from_elo(1549).level(), 10);
+        assert_eq!(Skill::from_elo(9999), Skill::default());
+        for level in 0..=MAX_LEVEL {
+            assert_eq!(Skill::from_elo(Skill::new(level).elo()).level(), level);
+        }
+    }
+
+    #[test]
+    fn limits_are_capped() {
+        let l = Skill::new(0).limits(SearchLimits::default());
+        assert_eq!((l.depth, l.nodes), (Some(1), Some(BASE_NODES)));
+        let l = Skill::new(9).limits(SearchLimits::depth(3));
+        assert_eq!((l.depth, l.nodes), (Some(3), Some(BASE_NODES << 4)));
+        assert_eq!(Skill::default().limits(SearchLimits::depth(30)), SearchLimits::depth(30));
+    }
+
+    #[test]
+    fn weak_levels_pick_worse_moves_more_often() {
+        let lines = [line(50), line(30), line(0), line(-40)];
+        let mut rng = Rng::new(7);
+        let worse = |level, rng: &mut Rng| (0..1000).filter(|_| Skill::new(level).pick(&lines, rng) != 0).count();
+        let (weak, strong) = (worse(0, &mut rng), worse(18, &mut rng));
+        assert!(weak > strong, "{weak} vs {strong}");
+        assert_eq!(worse(MAX_LEVEL, &mut rng), 0);
+        assert_eq!(Skill::new(0).pick(&lines[..1], &mut rng), 0);
+    }
+
+    #[test]
+    fn stronger_level_wins_match() {
+        let score = play_match(Skill::new(8), Skill::new(0), 2, SearchLimits::depth(2), 1);
+        assert!(score.score() > 0.5, "{score:?}");
+    }
+
+    /// The calibration ladder: every level beats the one a few steps below.
+    #[test]
+    #[ignore = "plays dozens of games; run with --ignored --release"]
+    fn levels_are_monotonic() {
+        let ladder = [0, 4, 8, 12, 16, MAX_LEVEL];
+        for pair in ladder.windows(2) {
+            let score = play_match(Skill::new(pair[1]), Skill::new(pair[0]), 10, SearchLimits::nodes(50_000), 42);
+            assert!(score.score() > 0.5, "level {} vs {}: {score:?}", pair[1], pair[0]);
+        }
+    }
+}
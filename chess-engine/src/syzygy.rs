@@ -0,0 +1,1233 @@
+//! Syzygy endgame tablebase probing (optional `syzygy` feature).
+//!
+//! [`Syzygy::open`] indexes the `.rtbw` (win/draw/loss) and `.rtbz`
+//! (distance to zeroing) files found in one or more directories; a table is
+//! read into memory the first time a position needs it. [`Board::probe_wdl`]
+//! and [`Board::probe_dtz`] map a position into Syzygy's encoding and look it
+//! up, and [`Syzygy::rank_root_moves`] orders the moves of a position by
+//! their DTZ, counting the moves already played towards the fifty-move rule.
+//! The search uses both: see [`Searcher::set_syzygy`](crate::search::Searcher::set_syzygy).
+//!
+//! # Format
+//!
+//! The decoder follows Ronald de Man's format as read by the reference
+//! probing code (Stockfish, Fathom):
+//!
+//! - Tables are named by material, White's pieces first (`KQvKR`), and one
+//!   file serves both colour orientations. Without pawns the board is
+//!   reduced to the a1–d1–d4 triangle; with pawns the table is split into
+//!   four by the file (a–d, mirrored) of the leading pawn.
+//! - Pieces are encoded in per-table groups: the kings (with a third unique
+//!   piece if there is one) or the leading pawns first, then each group of
+//!   identical pieces as a combination of the remaining squares.
+//! - Values are compressed by recursive pairing with canonical Huffman codes
+//!   in fixed-size blocks, found through a sparse index.
+//! - WDL values are `0..=4` for loss, blessed loss (a loss saved by the
+//!   fifty-move rule), draw, cursed win, and win. DTZ tables store one side
+//!   to move only, in moves or plies, optionally through a value map.
+//!
+//! Positions with castling rights are never covered. Tables do not know en
+//! passant, so probes search captures (and for DTZ pawn moves) one ply
+//! before trusting the stored value, as the reference code does.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::board::Board;
+use crate::types::{CastlingRights, Color, Move, MoveKind, Piece, PieceType};
+
+/// Most pieces the format supports.
+const TB_PIECES: usize = 7;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+const WDL_SUFFIX: &str = "rtbw";
+const DTZ_SUFFIX: &str = "rtbz";
+
+/// Header bits: separate tables per side to move, and pawns.
+const HEADER_SPLIT: u8 = 1;
+const HEADER_PAWNS: u8 = 2;
+
+/// Flags of one (side, file) subtable.
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+/// Root move rank of a win certain before the fifty-move rule can intervene.
+const MAX_DTZ: i32 = 1 << 18;
+
+/// Piece letters in the order of table names.
+const NAME_ORDER: [(char, PieceType); 6] = [
+    ('K', PieceType::King),
+    ('Q', PieceType::Queen),
+    ('R', PieceType::Rook),
+    ('B', PieceType::Bishop),
+    ('N', PieceType::Knight),
+    ('P', PieceType::Pawn),
+];
+
+/// Win, draw, or loss for the side to move, with the fifty-move rule:
+/// a cursed win cannot be forced before it and a blessed loss is saved by it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WdlScore {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl WdlScore {
+    const fn from_value(value: i32) -> Self {
+        match value {
+            i32::MIN..=-2 => Self::Loss,
+            -1 => Self::BlessedLoss,
+            0 => Self::Draw,
+            1 => Self::CursedWin,
+            _ => Self::Win,
+        }
+    }
+}
+
+/// A root move ranked by [`Syzygy::rank_root_moves`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RootMove {
+    pub mv: Move,
+    /// Plies to the next zeroing move (capture or pawn move) with best play,
+    /// counted from before `mv`: positive when the mover wins, negative when
+    /// it loses, 0 for a draw. Wins and losses beyond the fifty-move rule
+    /// count 100 plies extra.
+    pub dtz: i32,
+    /// Higher is better: quick certain wins first, then wins the fifty-move
+    /// rule may spoil, draws, and losses, the slowest first.
+    pub rank: i32,
+}
+
+// ---------------------------------------------------------------------------
+// Encoding tables
+// ---------------------------------------------------------------------------
+
+/// Lookup tables shared by every table's index computation.
+struct Encoding {
+    binomial: [[u64; 64]; TB_PIECES],
+    /// Squares below the a1–h8 diagonal to `0..28`.
+    map_b1h1h7: [u64; 64],
+    /// The a1–d1–d4 triangle to `0..10`, diagonal squares last.
+    map_a1d1d4: [u64; 64],
+    /// The 462 legal placements of two kings, the first in the triangle.
+    map_kk: [[u64; 64]; 10],
+    /// Pawn squares to `0..48`, highest for the leading pawn.
+    map_pawns: [u64; 64],
+    lead_pawn_idx: [[u64; 64]; TB_PIECES],
+    lead_pawns_size: [[u64; 4]; TB_PIECES],
+}
+
+/// Rank minus file: negative below the a1–h8 diagonal.
+#[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+const fn off_diagonal(sq: usize) -> i32 {
+    (sq >> 3) as i32 - (sq & 7) as i32
+}
+
+const fn rank_of(sq: usize) -> u64 {
+    (sq >> 3) as u64
+}
+
+fn encoding() -> &'static Encoding {
+    static ENCODING: OnceLock<Encoding> = OnceLock::new();
+    ENCODING.get_or_init(Encoding::new)
+}
+
+impl Encoding {
+    fn new() -> Self {
+        let mut e = Self {
+            binomial: [[0; 64]; TB_PIECES],
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; TB_PIECES],
+            lead_pawns_size: [[0; 4]; TB_PIECES],
+        };
+
+        let mut code = 0;
+        for sq in 0..64 {
+            if off_diagonal(sq) < 0 {
+                e.map_b1h1h7[sq] = code;
+                code += 1;
+            }
+        }
+
+        let triangle: Vec<usize> = (0..64).filter(|&sq| sq & 7 <= 3 && sq >> 3 <= 3 && off_diagonal(sq) <= 0).collect();
+        let below = triangle.iter().filter(|&&sq| off_diagonal(sq) < 0);
+        let diagonal = triangle.iter().filter(|&&sq| off_diagonal(sq) == 0);
+        for (code, &sq) in (0..).zip(below.chain(diagonal)) {
+            e.map_a1d1d4[sq] = code;
+        }
+
+        let adjacent = |a: usize, b: usize| (a & 7).abs_diff(b & 7) <= 1 && (a >> 3).abs_diff(b >> 3) <= 1;
+        let mut both_on_diagonal = Vec::new();
+        let mut code = 0;
+        for idx in 0..10 {
+            for &s1 in triangle.iter().filter(|&&sq| e.map_a1d1d4[sq] == idx as u64) {
+                for s2 in 0..64 {
+                    if adjacent(s1, s2) || (off_diagonal(s1) == 0 && off_diagonal(s2) > 0) {
+                        continue;
+                    }
+                    if off_diagonal(s1) == 0 && off_diagonal(s2) == 0 {
+                        both_on_diagonal.push((idx, s2));
+                    } else {
+                        e.map_kk[idx][s2] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, s2) in both_on_diagonal {
+            e.map_kk[idx][s2] = code;
+            code += 1;
+        }
+
+        e.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..TB_PIECES.min(n + 1) {
+                e.binomial[k][n] =
+                    if k > 0 { e.binomial[k - 1][n - 1] } else { 0 } + if k < n { e.binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        let mut available = 47u64;
+        for lead in 1..TB_PIECES - 1 {
+            for file in 0..4 {
+                let mut idx = 0;
+                for rank in 1..7 {
+                    let sq = rank * 8 + file;
+                    if lead == 1 {
+                        e.map_pawns[sq] = available;
+                        e.map_pawns[sq ^ 7] = available.saturating_sub(1);
+                        available = available.saturating_sub(2);
+                    }
+                    e.lead_pawn_idx[lead][sq] = idx;
+                    idx += e.binomial[lead - 1][usize::try_from(e.map_pawns[sq]).unwrap_or(0)];
+                }
+                e.lead_pawns_size[lead][file] = idx;
+            }
+        }
+        e
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Material
+// ---------------------------------------------------------------------------
+
+/// Piece counts by colour and type, the key tables are named by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Material {
+    counts: [[u8; 6]; 2],
+}
+
+impl Material {
+    fn of(board: &Board) -> Self {
+        let mut counts = [[0; 6]; 2];
+        for color in [Color::White, Color::Black] {
+            for pt in PieceType::ALL {
+                counts[color.index()][pt as usize] =
+                    u8::try_from(board.pieces(color, pt).count_ones()).unwrap_or(u8::MAX);
+            }
+        }
+        Self { counts }
+    }
+
+    /// Parses a table name such as `KQvKR`.
+    fn parse(name: &str) -> Option<Self> {
+        let (white, black) = name.split_once('v')?;
+        let mut counts = [[0u8; 6]; 2];
+        for (side, pieces) in counts.iter_mut().zip(<[&str; 2]>::from((white, black))) {
+            for c in pieces.chars() {
+                let &(_, pt) = NAME_ORDER.iter().find(|(letter, _)| *letter == c)?;
+                side[pt as usize] += 1;
+            }
+        }
+        let material = Self { counts };
+        (counts[0][PieceType::King as usize] == 1
+            && counts[1][PieceType::King as usize] == 1
+            && material.piece_count() <= TB_PIECES)
+            .then_some(material)
+    }
+
+    fn name(&self) -> String {
+        let side = |counts: &[u8; 6]| -> String {
+            NAME_ORDER
+                .iter()
+                .flat_map(|&(letter, pt)| std::iter::repeat_n(letter, usize::from(counts[pt as usize])))
+                .collect()
+        };
+        format!("{}v{}", side(&self.counts[0]), side(&self.counts[1]))
+    }
+
+    const fn flipped(self) -> Self {
+        Self { counts: [self.counts[1], self.counts[0]] }
+    }
+
+    fn piece_count(&self) -> usize {
+        self.counts.iter().flatten().map(|&n| usize::from(n)).sum()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tables
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Wdl,
+    Dtz,
+}
+
+/// One compressed subtable: a side to move and, with pawns, a leading file.
+/// Offsets point into the table's bytes.
+#[derive(Clone, Default)]
+struct PairsData {
+    flags: u8,
+    /// Piece codes in encoding order (1..=6 White pawn..king, 9..=14 Black).
+    pieces: [u8; TB_PIECES],
+    /// Sizes of the encoding groups, zero-terminated.
+    group_len: [usize; TB_PIECES + 1],
+    /// Multiplier of each group in the index; the entry after the last
+    /// group is the subtable size.
+    group_idx: [u64; TB_PIECES + 1],
+    block_size: usize,
+    span: u64,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    block_lengths: usize,
+    block_lengths_size: usize,
+    blocks: usize,
+    data: usize,
+    /// Shortest code length, or the value of a single-valued subtable.
+    min_sym_len: u8,
+    lowest_sym: usize,
+    /// Smallest left-aligned code of each length, longest codes last.
+    base64: Vec<u64>,
+    /// Values each symbol expands to, minus one.
+    symlen: Vec<u8>,
+    btree: usize,
+    /// Offsets of the four DTZ value maps, in map units plus one.
+    map_idx: [usize; 4],
+}
+
+struct Table {
+    bytes: Vec<u8>,
+    symmetric: bool,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    /// Pawns of the leading colour, then of the other.
+    pawn_count: [usize; 2],
+    piece_count: usize,
+    /// Subtables by side to move (WDL tables of asymmetric material have
+    /// two) and leading pawn file.
+    items: Vec<Vec<PairsData>>,
+    /// Start of the DTZ value maps.
+    map: usize,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(*bytes.get(at..)?.first_chunk()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(*bytes.get(at..)?.first_chunk()?))
+}
+
+/// Left and right child of a symbol in the pairing tree (12 bits each).
+fn children(bytes: &[u8], btree: usize, sym: usize) -> Option<(usize, usize)> {
+    let lr = bytes.get(btree + 3 * sym..btree + 3 * sym + 3)?;
+    let left = usize::from(lr[1] & 0xF) << 8 | usize::from(lr[0]);
+    let right = usize::from(lr[2]) << 4 | usize::from(lr[1] >> 4);
+    Some((left, right))
+}
+
+impl Table {
+    /// Parses a table file of `material` (in the file's colour orientation).
+    fn new(bytes: Vec<u8>, kind: Kind, material: &Material) -> Option<Self> {
+        let magic = match kind {
+            Kind::Wdl => WDL_MAGIC,
+            Kind::Dtz => DTZ_MAGIC,
+        };
+        if bytes.get(..4)? != magic {
+            return None;
+        }
+        let pawns = |side: usize| usize::from(material.counts[side][PieceType::Pawn as usize]);
+        let (white_pawns, black_pawns) = (pawns(0), pawns(1));
+        // The side with fewer pawns (but some) leads: it compresses better.
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        let mut table = Self {
+            bytes,
+            symmetric: material.counts[0] == material.counts[1],
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces: material.counts.iter().any(|side| side[..PieceType::King as usize].contains(&1)),
+            pawn_count: if white_leads { [white_pawns, black_pawns] } else { [black_pawns, white_pawns] },
+            piece_count: material.piece_count(),
+            items: Vec::new(),
+            map: 0,
+        };
+        table.parse(kind)?;
+        Some(table)
+    }
+
+    fn parse(&mut self, kind: Kind) -> Option<()> {
+        let bytes = &self.bytes;
+        let header = *bytes.get(4)?;
+        if (header & HEADER_PAWNS != 0) != self.has_pawns || (header & HEADER_SPLIT != 0) == self.symmetric {
+            return None;
+        }
+        let sides = if kind == Kind::Wdl && !self.symmetric { 2 } else { 1 };
+        let files = if self.has_pawns { 4 } else { 1 };
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut items = vec![vec![PairsData::default(); files]; sides];
+        let mut p = 5;
+
+        for file in 0..files {
+            let first = *bytes.get(p)?;
+            let second = if both_pawns { *bytes.get(p + 1)? } else { 0xFF };
+            let order = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+            p += 1 + usize::from(both_pawns);
+            for k in 0..self.piece_count {
+                let b = *bytes.get(p)?;
+                for (side, item) in items.iter_mut().enumerate() {
+                    item[file].pieces[k] = if side == 1 { b >> 4 } else { b & 0xF };
+                }
+                p += 1;
+            }
+            for (side, item) in items.iter_mut().enumerate() {
+                self.set_groups(&mut item[file], order[side], file)?;
+            }
+        }
+        p += p & 1;
+
+        for file in 0..files {
+            for item in &mut items {
+                p = set_sizes(bytes, &mut item[file], p)?;
+            }
+        }
+
+        if kind == Kind::Dtz {
+            self.map = p;
+            for item in &mut items[0] {
+                if item.flags & FLAG_MAPPED == 0 {
+                    continue;
+                }
+                if item.flags & FLAG_WIDE != 0 {
+                    p += p & 1;
+                    for i in 0..4 {
+                        item.map_idx[i] = (p - self.map) / 2 + 1;
+                        p += 2 * usize::from(u16_at(bytes, p)?) + 2;
+                    }
+                } else {
+                    for i in 0..4 {
+                        item.map_idx[i] = p - self.map + 1;
+                        p += usize::from(*bytes.get(p)?) + 1;
+                    }
+                }
+            }
+            p += p & 1;
+        }
+
+        for file in 0..files {
+            for item in &mut items {
+                item[file].sparse_index = p;
+                p += item[file].sparse_index_size * 6;
+            }
+        }
+        for file in 0..files {
+            for item in &mut items {
+                item[file].block_lengths = p;
+                p += item[file].block_lengths_size * 2;
+            }
+        }
+        for file in 0..files {
+            for item in &mut items {
+                p = (p + 0x3F) & !0x3F;
+                item[file].data = p;
+                p += item[file].blocks * item[file].block_size;
+            }
+        }
+        if p > bytes.len() {
+            return None;
+        }
+        self.items = items;
+        Some(())
+    }
+
+    /// Splits the pieces into encoding groups and computes each group's
+    /// multiplier; `order` gives the position of the leading group and of
+    /// the other side's pawns among them.
+    fn set_groups(&self, d: &mut PairsData, order: [u8; 2], file: usize) -> Option<()> {
+        let e = encoding();
+        let mut n = 0;
+        let mut first_len: i32 = if self.has_pawns {
+            0
+        } else if self.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+        d.group_len[0] = 1;
+        for i in 1..self.piece_count {
+            first_len -= 1;
+            if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+                d.group_len[n] += 1;
+            } else {
+                n += 1;
+                d.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        d.group_len[n] = 0;
+
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free = 64 - d.group_len[0] - if both_pawns { d.group_len[1] } else { 0 };
+        let mut idx = 1u64;
+        let mut k = 0u8;
+        while next < n || k == order[0] || k == order[1] {
+            if k == order[0] {
+                d.group_idx[0] = idx;
+                idx *= if self.has_pawns {
+                    e.lead_pawns_size[d.group_len[0]][file]
+                } else if self.has_unique_pieces {
+                    31_332
+                } else {
+                    462
+                };
+            } else if k == order[1] {
+                d.group_idx[1] = idx;
+                idx *= e.binomial.get(d.group_len[1])?[48 - d.group_len[0]];
+            } else {
+                d.group_idx[next] = idx;
+                idx *= e.binomial.get(d.group_len[next])?[free];
+                free = free.checked_sub(d.group_len[next])?;
+                next += 1;
+            }
+            k = k.checked_add(1)?;
+        }
+        d.group_idx[n] = idx;
+        Some(())
+    }
+
+    /// The subtable and index of `board`, with colours and ranks swapped if
+    /// `flip`, and the side to move in the table's orientation (0 or 1).
+    fn encode(&self, board: &Board, flip: bool) -> Option<(&PairsData, u64, usize)> {
+        let e = encoding();
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let stm = usize::from(flip) ^ board.side_to_move().index();
+        let mut squares = [0usize; TB_PIECES];
+        let mut pieces = [0u8; TB_PIECES];
+        let mut size = 0;
+
+        let (lead_pawns, file) = if self.has_pawns {
+            let lead = self.items[0][0].pieces[0] ^ flip_color;
+            let color = if lead & 8 == 0 { Color::White } else { Color::Black };
+            let pawns = board.pieces(color, PieceType::Pawn);
+            for sq in bits(pawns) {
+                squares[size] = sq ^ flip_squares;
+                size += 1;
+            }
+            let leading = (0..size).max_by_key(|&i| e.map_pawns[squares[i]])?;
+            squares.swap(0, leading);
+            (pawns, (squares[0] & 7).min(7 - (squares[0] & 7)))
+        } else {
+            (0, 0)
+        };
+        let lead_count = size;
+
+        for sq in bits(board.occupied() ^ lead_pawns) {
+            let piece = board.piece_at(crate::types::Square::from_index(u8::try_from(sq).ok()?))?;
+            squares[size] = sq ^ flip_squares;
+            pieces[size] = piece_code(piece) ^ flip_color;
+            size += 1;
+        }
+
+        let d = &self.items[stm % self.items.len()][file];
+
+        // Put the pieces in the table's order.
+        for i in lead_count..size - 1 {
+            if let Some(j) = (i + 1..size).find(|&j| d.pieces[i] == pieces[j]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+
+        if squares[0] & 7 > 3 {
+            for sq in &mut squares[..size] {
+                *sq ^= 7;
+            }
+        }
+
+        let mut idx;
+        if self.has_pawns {
+            idx = e.lead_pawn_idx[lead_count][squares[0]];
+            squares[1..lead_count].sort_by_key(|&sq| e.map_pawns[sq]);
+            for (i, &sq) in squares.iter().enumerate().take(lead_count).skip(1) {
+                idx += e.binomial[i][usize::try_from(e.map_pawns[sq]).ok()?];
+            }
+        } else {
+            if squares[0] >> 3 > 3 {
+                for sq in &mut squares[..size] {
+                    *sq ^= 0b11_1000;
+                }
+            }
+            // Reflect along the a1–h8 diagonal so that the first piece of the
+            // leading group off the diagonal is below it.
+            if let Some(i) = (0..d.group_len[0]).find(|&i| off_diagonal(squares[i]) != 0) {
+                if off_diagonal(squares[i]) > 0 {
+                    for sq in &mut squares[i..size] {
+                        *sq = ((*sq >> 3) | (*sq << 3)) & 63;
+                    }
+                }
+            }
+            let [s0, s1, s2] = [squares[0], squares[1], squares[2]];
+            idx = if self.has_unique_pieces {
+                let adjust1 = u64::from(s1 > s0);
+                let adjust2 = u64::from(s2 > s0) + u64::from(s2 > s1);
+                if off_diagonal(s0) != 0 {
+                    (e.map_a1d1d4[s0] * 63 + (s1 as u64 - adjust1)) * 62 + s2 as u64 - adjust2
+                } else if off_diagonal(s1) != 0 {
+                    (6 * 63 + rank_of(s0) * 28 + e.map_b1h1h7[s1]) * 62 + s2 as u64 - adjust2
+                } else if off_diagonal(s2) != 0 {
+                    6 * 63 * 62 + 4 * 28 * 62 + rank_of(s0) * 7 * 28 + (rank_of(s1) - adjust1) * 28 + e.map_b1h1h7[s2]
+                } else {
+                    6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank_of(s0) * 7 * 6 + (rank_of(s1) - adjust1) * 6 + rank_of(s2)
+                        - adjust2
+                }
+            } else {
+                e.map_kk[usize::try_from(e.map_a1d1d4[s0]).ok()?][s1]
+            };
+        }
+
+        idx *= d.group_idx[0];
+        let mut start = d.group_len[0];
+        let mut remaining_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = 1;
+        while d.group_len[next] != 0 {
+            let len = d.group_len[next];
+            squares[start..start + len].sort_unstable();
+            let mut n = 0;
+            for i in 0..len {
+                let sq = squares[start + i];
+                let adjust = squares[..start].iter().filter(|&&s| sq > s).count();
+                let shift = if remaining_pawns { 8 } else { 0 };
+                n += e.binomial[i + 1][sq.checked_sub(adjust + shift)?];
+            }
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            start += len;
+            next += 1;
+        }
+        Some((d, idx, stm))
+    }
+
+    /// The value stored at `idx` of subtable `d`.
+    fn decompress(&self, d: &PairsData, idx: u64) -> Option<u16> {
+        if d.flags & FLAG_SINGLE_VALUE != 0 {
+            return Some(u16::from(d.min_sym_len));
+        }
+        let bytes = &self.bytes;
+        let k = usize::try_from(idx / d.span).ok()?;
+        if k >= d.sparse_index_size {
+            return None;
+        }
+        let entry = d.sparse_index + 6 * k;
+        let mut block = usize::try_from(u32_at(bytes, entry)?).ok()?;
+        let half_span = i64::try_from(d.span / 2).ok()?;
+        let mut offset = i64::from(u16_at(bytes, entry + 4)?) + i64::try_from(idx % d.span).ok()? - half_span;
+        let block_length = |block: usize| -> Option<i64> {
+            if block >= d.block_lengths_size {
+                return None;
+            }
+            Some(i64::from(u16_at(bytes, d.block_lengths + 2 * block)?))
+        };
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)? + 1;
+        }
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        let mut ptr = d.data + block * d.block_size;
+        let read_be = |at: usize, n: usize| -> Option<u64> {
+            Some(bytes.get(at..at + n)?.iter().fold(0, |acc, &b| acc << 8 | u64::from(b)))
+        };
+        let mut buf = read_be(ptr, 8)?;
+        ptr += 8;
+        let mut buf_size = 64;
+        let min_len = u32::from(d.min_sym_len);
+        let mut sym;
+        loop {
+            let mut len = 0;
+            while buf < *d.base64.get(len)? {
+                len += 1;
+            }
+            let len32 = u32::try_from(len).ok()?;
+            sym = usize::try_from((buf - d.base64[len]) >> (64 - len32 - min_len)).ok()?;
+            sym += usize::from(u16_at(bytes, d.lowest_sym + 2 * len)?);
+            let expands = i64::from(*d.symlen.get(sym)?) + 1;
+            if offset < expands {
+                break;
+            }
+            offset -= expands;
+            let consumed = len32 + min_len;
+            buf = buf.checked_shl(consumed).unwrap_or(0);
+            buf_size -= i64::from(consumed);
+            if buf_size <= 32 {
+                buf_size += 32;
+                buf |= read_be(ptr, 4)? << (64 - buf_size);
+                ptr += 4;
+            }
+        }
+
+        while d.symlen[sym] != 0 {
+            let (left, right) = children(bytes, d.btree, sym)?;
+            let left_len = i64::from(*d.symlen.get(left)?) + 1;
+            if offset < left_len {
+                sym = left;
+            } else {
+                offset -= left_len;
+                sym = right;
+            }
+        }
+        children(bytes, d.btree, sym).and_then(|(left, _)| u16::try_from(left).ok())
+    }
+
+    /// Converts a stored DTZ value to plies for a position of result `wdl`.
+    fn map_dtz(&self, d: &PairsData, value: u16, wdl: i32) -> Option<i32> {
+        const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+        let mut value = usize::from(value);
+        if d.flags & FLAG_MAPPED != 0 {
+            let map = d.map_idx[WDL_MAP[usize::try_from(wdl + 2).ok()?]];
+            value = if d.flags & FLAG_WIDE != 0 {
+                usize::from(u16_at(&self.bytes, self.map + 2 * (map + value))?)
+            } else {
+                usize::from(*self.bytes.get(self.map + map + value)?)
+            };
+        }
+        let mut plies = i32::try_from(value).ok()?;
+        if (wdl == 2 && d.flags & FLAG_WIN_PLIES == 0) || (wdl == -2 && d.flags & FLAG_LOSS_PLIES == 0) || wdl.abs() == 1 {
+            plies *= 2;
+        }
+        Some(plies + 1)
+    }
+}
+
+/// Reads the compression parameters of a subtable at `p` and returns the
+/// offset after them.
+fn set_sizes(bytes: &[u8], d: &mut PairsData, mut p: usize) -> Option<usize> {
+    d.flags = *bytes.get(p)?;
+    p += 1;
+    if d.flags & FLAG_SINGLE_VALUE != 0 {
+        d.min_sym_len = *bytes.get(p)?;
+        return Some(p + 1);
+    }
+    let size = d.group_idx[d.group_len.iter().position(|&len| len == 0)?];
+    d.block_size = 1usize.checked_shl(u32::from(*bytes.get(p)?))?;
+    d.span = 1u64.checked_shl(u32::from(*bytes.get(p + 1)?))?;
+    d.sparse_index_size = usize::try_from(size.div_ceil(d.span)).ok()?;
+    let padding = usize::from(*bytes.get(p + 2)?);
+    d.blocks = usize::try_from(u32_at(bytes, p + 3)?).ok()?;
+    d.block_lengths_size = d.blocks + padding;
+    let max_sym_len = *bytes.get(p + 7)?;
+    d.min_sym_len = *bytes.get(p + 8)?;
+    p += 9;
+    d.lowest_sym = p;
+    let lengths = usize::from(max_sym_len.checked_sub(d.min_sym_len)?) + 1;
+    d.base64 = vec![0; lengths];
+    for i in (0..lengths - 1).rev() {
+        let lowest = u64::from(u16_at(bytes, p + 2 * i)?);
+        let next_lowest = u64::from(u16_at(bytes, p + 2 * i + 2)?);
+        d.base64[i] = (d.base64[i + 1] + lowest).checked_sub(next_lowest)? / 2;
+    }
+    for (i, base) in d.base64.iter_mut().enumerate() {
+        let shift = 64 - u32::try_from(i).ok()? - u32::from(d.min_sym_len);
+        *base = base.checked_shl(shift).unwrap_or(0);
+    }
+    p += 2 * lengths;
+    let symbols = usize::from(u16_at(bytes, p)?);
+    p += 2;
+    d.btree = p;
+    d.symlen = vec![0; symbols];
+    let mut visited = vec![false; symbols];
+    for sym in 0..symbols {
+        if !visited[sym] {
+            d.symlen[sym] = symlen(bytes, d.btree, sym, &mut d.symlen, &mut visited)?;
+        }
+    }
+    Some(p + 3 * symbols + (symbols & 1))
+}
+
+/// Number of values `sym` expands to, minus one.
+fn symlen(bytes: &[u8], btree: usize, sym: usize, lens: &mut [u8], visited: &mut [bool]) -> Option<u8> {
+    visited[sym] = true;
+    let (left, right) = children(bytes, btree, sym)?;
+    if right == 0xFFF {
+        return Some(0);
+    }
+    for child in [left, right] {
+        if !*visited.get(child)? {
+            lens[child] = symlen(bytes, btree, child, lens, visited)?;
+        }
+    }
+    Some(lens[left].wrapping_add(lens[right]).wrapping_add(1))
+}
+
+/// Syzygy's piece code: 1..=6 for White's pawn..king, 9..=14 for Black's.
+const fn piece_code(piece: Piece) -> u8 {
+    piece.piece_type() as u8 + 1 + if matches!(piece.color(), Color::Black) { 8 } else { 0 }
+}
+
+/// Square indices of the set bits of `bb`, ascending.
+fn bits(mut bb: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        let sq = (bb != 0).then(|| bb.trailing_zeros() as usize)?;
+        bb &= bb - 1;
+        Some(sq)
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Probing
+// ---------------------------------------------------------------------------
+
+/// The files of one material, loaded on first use.
+struct Entry {
+    material: Material,
+    wdl_path: Option<PathBuf>,
+    dtz_path: Option<PathBuf>,
+    wdl: OnceLock<Option<Table>>,
+    dtz: OnceLock<Option<Table>>,
+}
+
+impl Entry {
+    fn table(&self, kind: Kind) -> Option<&Table> {
+        let (path, cell) = match kind {
+            Kind::Wdl => (&self.wdl_path, &self.wdl),
+            Kind::Dtz => (&self.dtz_path, &self.dtz),
+        };
+        cell.get_or_init(|| {
+            let bytes = std::fs::read(path.as_ref()?).ok()?;
+            Table::new(bytes, kind, &self.material)
+        })
+        .as_ref()
+    }
+}
+
+/// A set of Syzygy tables on disk.
+pub struct Syzygy {
+    entries: HashMap<String, Entry>,
+    max_pieces: usize,
+}
+
+/// Did a probe's capture search already find the value (so the stored one
+/// must not be used)?
+type Searched = bool;
+
+/// A DTZ table holds the side to move of `board` only through a search.
+struct OtherSide;
+
+const fn dtz_before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        2 => 1,
+        1 => 101,
+        -1 => -101,
+        -2 => -1,
+        _ => 0,
+    }
+}
+
+fn is_zeroing(board: &Board, mv: Move) -> bool {
+    mv.kind() == MoveKind::EnPassant
+        || board.piece_at(mv.to()).is_some()
+        || board.piece_at(mv.from()).is_some_and(|p| p.piece_type() == PieceType::Pawn)
+}
+
+fn is_capture(board: &Board, mv: Move) -> bool {
+    mv.kind() == MoveKind::EnPassant || board.piece_at(mv.to()).is_some()
+}
+
+fn is_checkmate(board: &Board) -> bool {
+    board.is_in_check(board.side_to_move()) && board.generate_legal_moves().is_empty()
+}
+
+impl Syzygy {
+    /// Indexes the tables in `paths`: one directory, or several separated
+    /// as in the `PATH` environment variable (`:` on Unix, `;` on Windows).
+    /// Files are named like `KQvKR.rtbw`; others are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Any I/O error from listing a directory.
+    pub fn open(paths: impl AsRef<OsStr>) -> io::Result<Self> {
+        let mut entries: HashMap<String, Entry> = HashMap::new();
+        for dir in std::env::split_paths(&paths) {
+            for file in std::fs::read_dir(dir)? {
+                let path = file?.path();
+                let (Some(stem), Some(ext)) = (path.file_stem().and_then(OsStr::to_str), path.extension()) else {
+                    continue;
+                };
+                let Some(material) = Material::parse(stem) else {
+                    continue;
+                };
+                let entry = entries.entry(stem.to_string()).or_insert_with(|| Entry {
+                    material,
+                    wdl_path: None,
+                    dtz_path: None,
+                    wdl: OnceLock::new(),
+                    dtz: OnceLock::new(),
+                });
+                if ext == WDL_SUFFIX {
+                    entry.wdl_path = Some(path);
+                } else if ext == DTZ_SUFFIX {
+                    entry.dtz_path = Some(path);
+                }
+            }
+        }
+        let max_pieces = entries.values().filter(|e| e.wdl_path.is_some()).map(|e| e.material.piece_count()).max();
+        Ok(Self { entries, max_pieces: max_pieces.unwrap_or(0) })
+    }
+
+    /// Number of materials with at least one file.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Pieces (kings included) of the largest WDL table found.
+    #[must_use]
+    pub const fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// Could `board` be in the tables?
+    fn covers(&self, board: &Board) -> bool {
+        board.castling() == CastlingRights::NONE && board.occupied().count_ones() as usize <= self.max_pieces
+    }
+
+    /// The table for `board`'s material and whether to probe it with the
+    /// colours swapped.
+    fn table(&self, board: &Board, kind: Kind) -> Option<(&Table, bool)> {
+        let material = Material::of(board);
+        if let Some(entry) = self.entries.get(&material.name()) {
+            let table = entry.table(kind)?;
+            return Some((table, table.symmetric && board.side_to_move() == Color::Black));
+        }
+        Some((self.entries.get(&material.flipped().name())?.table(kind)?, true))
+    }
+
+    fn probe_wdl_table(&self, board: &Board) -> Option<i32> {
+        if board.occupied().count_ones() == 2 {
+            return Some(0);
+        }
+        let (table, flip) = self.table(board, Kind::Wdl)?;
+        let (d, idx, _) = table.encode(board, flip)?;
+        Some(i32::from(table.decompress(d, idx)?) - 2)
+    }
+
+    /// The stored DTZ of `board`, whose result is `wdl`: `Err` if the
+    /// table only stores the other side to move, `Ok(None)` if it is missing.
+    fn probe_dtz_table(&self, board: &Board, wdl: i32) -> Result<Option<i32>, OtherSide> {
+        let Some((table, flip)) = self.table(board, Kind::Dtz) else {
+            return Ok(None);
+        };
+        let Some((d, idx, stm)) = table.encode(board, flip) else {
+            return Ok(None);
+        };
+        if (table.has_pawns || !table.symmetric) && usize::from(d.flags & FLAG_STM) != stm {
+            return Err(OtherSide);
+        }
+        Ok(table.decompress(d, idx).and_then(|value| table.map_dtz(d, value, wdl)))
+    }
+
+    /// WDL of `board` (-2..=2) after trying its captures, and for DTZ
+    /// (`zeroing`) its pawn moves, first.
+    fn search_wdl(&self, board: &Board, zeroing: bool) -> Option<(i32, Searched)> {
+        let moves = board.generate_legal_moves();
+        let mut best = -2;
+        let mut searched = 0;
+        for &mv in &moves {
+            if !(is_capture(board, mv) || (zeroing && is_zeroing(board, mv))) {
+                continue;
+            }
+            searched += 1;
+            let mut child = board.clone();
+            child.apply_unchecked(mv);
+            let value = -self.search_wdl(&child, false)?.0;
+            if value > best {
+                best = value;
+                if value >= 2 {
+                    return Some((value, true));
+                }
+            }
+        }
+        let all_searched = searched > 0 && searched == moves.len();
+        let value = if all_searched { best } else { self.probe_wdl_table(board)? };
+        if best >= value {
+            return Some((best, best > 0 || all_searched));
+        }
+        Some((value, false))
+    }
+
+    fn dtz(&self, board: &Board) -> Option<i32> {
+        let (wdl, searched) = self.search_wdl(board, true)?;
+        if wdl == 0 {
+            return Some(0);
+        }
+        if searched {
+            return Some(dtz_before_zeroing(wdl));
+        }
+        if let Ok(dtz) = self.probe_dtz_table(board, wdl) {
+            let cursed = if wdl.abs() == 1 { 100 } else { 0 };
+            return Some((dtz? + cursed) * wdl.signum());
+        }
+        // The table stores the other side to move: search one ply.
+        let mut best = i32::MAX;
+        for mv in board.generate_legal_moves() {
+            let zeroing = is_zeroing(board, mv);
+            let mut child = board.clone();
+            child.apply_unchecked(mv);
+            let mut dtz = if zeroing {
+                -dtz_before_zeroing(self.search_wdl(&child, false)?.0)
+            } else {
+                -self.dtz(&child)?
+            };
+            if dtz == 1 && is_checkmate(&child) {
+                best = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < best && dtz.signum() == wdl.signum() {
+                best = dtz;
+            }
+        }
+        Some(if best == i32::MAX { -1 } else { best })
+    }
+
+    /// Ranks the legal moves of `board` by DTZ, best first, or `None` if the
+    /// position is not covered. Certain wins are ordered by DTZ; wins too
+    /// slow for the fifty-move rule (given `board`'s halfmove clock) come
+    /// after them, losses are ordered slowest first. Repetitions are not
+    /// considered.
+    #[must_use]
+    pub fn rank_root_moves(&self, board: &Board) -> Option<Vec<RootMove>> {
+        if !self.covers(board) {
+            return None;
+        }
+        let clock = i32::from(board.halfmove_clock());
+        let mut ranked = Vec::new();
+        for mv in board.generate_legal_moves() {
+            let mut child = board.clone();
+            child.apply_unchecked(mv);
+            let mut dtz = if child.halfmove_clock() == 0 {
+                dtz_before_zeroing(-self.search_wdl(&child, false)?.0)
+            } else if child.halfmove_clock() >= 100 {
+                0
+            } else {
+                let dtz = -self.dtz(&child)?;
+                dtz + dtz.signum()
+            };
+            if dtz == 2 && is_checkmate(&child) {
+                dtz = 1;
+            }
+            let rank = match dtz {
+                d if d > 0 && d + clock <= 99 => MAX_DTZ - d,
+                d if d > 0 => MAX_DTZ - (d + clock),
+                0 => 0,
+                d => -MAX_DTZ - d + clock,
+            };
+            ranked.push(RootMove { mv, dtz, rank });
+        }
+        ranked.sort_by_key(|m| std::cmp::Reverse(m.rank));
+        Some(ranked)
+    }
+}
+
+impl Board {
+    /// The Syzygy win/draw/loss result for the side to move, or `None` if
+    /// the position has castling rights or a table it needs is missing.
+    #[must_use]
+    pub fn probe_wdl(&self, syzygy: &Syzygy) -> Option<WdlScore> {
+        if !syzygy.covers(self) {
+            return None;
+        }
+        syzygy.search_wdl(self, false).map(|(wdl, _)| WdlScore::from_value(wdl))
+    }
+
+    /// Plies to the next zeroing move with best play: positive when the
+    /// side to move wins, negative when it loses (-1 if mated), 0 for a
+    /// draw, and 100 plies further for cursed wins and blessed losses.
+    /// `None` if the position is not covered.
+    #[must_use]
+    pub fn probe_dtz(&self, syzygy: &Syzygy) -> Option<i32> {
+        if !syzygy.covers(self) {
+            return None;
+        }
+        syzygy.dtz(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tablebase::{Tablebases, Wdl};
+
+    /// Where the tests look for tables: `$SYZYGY_PATH`, or `tests/syzygy`.
+    fn syzygy() -> Syzygy {
+        let path = std::env::var("SYZYGY_PATH").unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy").into());
+        Syzygy::open(path).unwrap()
+    }
+
+    fn board(fen: &str) -> Board {
+        Board::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn encoding_tables() {
+        let e = encoding();
+        assert_eq!(e.map_kk.iter().flatten().max(), Some(&461));
+        assert_eq!(e.map_a1d1d4[1], 0);
+        assert_eq!(e.map_a1d1d4[0], 6);
+        assert_eq!(e.map_a1d1d4[27], 9);
+        assert_eq!(e.map_b1h1h7.iter().max(), Some(&27));
+        assert_eq!(e.binomial[2][62], 1891);
+        assert_eq!(e.binomial[3][5], 10);
+        assert_eq!((e.map_pawns[8], e.map_pawns[15], e.map_pawns[9]), (47, 46, 35));
+        assert_eq!(e.lead_pawns_size[1], [6; 4]);
+        assert_eq!(e.lead_pawns_size[2][0], 47 + 45 + 43 + 41 + 39 + 37);
+    }
+
+    #[test]
+    fn material_names() {
+        let m = Material::of(&board("8/8/8/8/8/2k5/8/KQ2r3 w - - 0 1"));
+        assert_eq!(m.name(), "KQvKR");
+        assert_eq!(m.flipped().name(), "KRvKQ");
+        assert_eq!(Material::parse("KQvKR"), Some(m));
+        assert_eq!(Material::parse("KBNvK").unwrap().name(), "KBNvK");
+        for bad in ["KQK", "QvK", "KXvK", "KKvK", "KQQQQQQvK"] {
+            assert_eq!(Material::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn missing_and_broken_tables() {
+        let dir = std::env::temp_dir().join(format!("syzygy-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("KQvK.rtbw"), b"not a table").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"").unwrap();
+        let tb = Syzygy::open(&dir).unwrap();
+        assert_eq!((tb.len(), tb.max_pieces()), (1, 3));
+        assert_eq!(board("8/8/8/8/8/2k5/8/KQ6 w - - 0 1").probe_wdl(&tb), None);
+        assert_eq!(board("8/8/8/8/8/2k5/8/KR6 w - - 0 1").probe_wdl(&tb), None);
+        // Bare kings need no table; four pieces exceed what was found.
+        assert_eq!(board("8/8/8/8/8/2k5/8/K7 w - - 0 1").probe_wdl(&tb), Some(WdlScore::Draw));
+        assert_eq!(board("8/8/8/8/8/2k5/8/KQ2r3 w - - 0 1").probe_wdl(&tb), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(Syzygy::open(&dir).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs the Syzygy files listed in tests/syzygy/README.md"]
+    fn wdl_and_dtz() {
+        let tb = syzygy();
+        assert!(tb.max_pieces() >= 4);
+        assert_eq!(board("8/8/8/8/8/2k5/8/KQ6 w - - 0 1").probe_wdl(&tb), Some(WdlScore::Win));
+        assert_eq!(board("8/8/8/8/8/2k5/8/KQ6 b - - 0 1").probe_wdl(&tb), Some(WdlScore::Loss));
+        assert_eq!(board("k6Q/8/1K6/8/8/8/8/8 b - - 0 1").probe_dtz(&tb), Some(-1));
+        assert_eq!(board("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1").probe_dtz(&tb), Some(1));
+        // Black to move is stalemated; White to move wins.
+        assert_eq!(board("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1").probe_wdl(&tb), Some(WdlScore::Draw));
+        assert_eq!(board("4k3/4P3/4K3/8/8/8/8/8 w - - 0 1").probe_wdl(&tb), Some(WdlScore::Win));
+        // Pawn pushes zero the counter at once.
+        assert_eq!(board("8/8/8/8/4k3/8/4P3/4K3 w - - 0 1").probe_dtz(&tb).map(i32::signum), Some(0));
+        assert_eq!(board("8/8/8/4k3/8/8/4P3/4K3 w - - 0 1").probe_wdl(&tb), Some(WdlScore::Draw));
+        assert_eq!(board("8/4P3/8/8/8/8/k7/4K3 w - - 0 1").probe_dtz(&tb), Some(1));
+        // Here the king blocks the pawn: it steps aside first (Kd6, then e6).
+        assert_eq!(board("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1").probe_dtz(&tb), Some(3));
+        // Castling rights are never covered.
+        assert_eq!(board("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").probe_wdl(&tb), None);
+    }
+
+    /// Every position of our own tables gets the same result from Syzygy,
+    /// and for KQK and KRK the winner's DTZ is the distance to mate.
+    #[test]
+    #[ignore = "needs the Syzygy files listed in tests/syzygy/README.md"]
+    fn agrees_with_generated_tables() {
+        let tb = syzygy();
+        let mut ours = Tablebases::new();
+        for signature in ["KQK", "KRK", "KPK", "KBNK", "KQKR"] {
+            ours.generate(signature);
+        }
+        let mut rng = crate::rng::Rng::new(3);
+        let pieces = ["Q", "R", "P", "BN", "Qr"];
+        let mut checked = 0;
+        while checked < 20_000 {
+            let set = pieces[usize::try_from(rng.below(pieces.len() as u64)).unwrap()];
+            let mut b = Board::empty();
+            for c in ("Kk".to_string() + set).chars() {
+                let piece = Piece::from_fen_char(c).unwrap();
+                let sq = crate::types::Square::from_index(u8::try_from(rng.below(64)).unwrap());
+                if b.piece_at(sq).is_some() || (piece.piece_type() == PieceType::Pawn && (sq.rank() == 0 || sq.rank() == 7)) {
+                    continue;
+                }
+                b.put_piece(sq, piece);
+            }
+            let stm = if rng.below(2) == 0 { Color::White } else { Color::Black };
+            b.set_state(stm, CastlingRights::NONE, None, 0, 1);
+            if Material::of(&b).piece_count() != 2 + set.len() || !b.has_valid_kings() {
+                continue;
+            }
+            let Some(expected) = b.probe_tablebase(&ours) else {
+                continue;
+            };
+            let wdl = b.probe_wdl(&tb).unwrap();
+            let simple = match wdl {
+                WdlScore::Win | WdlScore::CursedWin => Wdl::Win,
+                WdlScore::Draw => Wdl::Draw,
+                WdlScore::Loss | WdlScore::BlessedLoss => Wdl::Loss,
+            };
+            assert_eq!(simple, expected.wdl, "{}", b.to_fen());
+            if matches!(set, "Q" | "R") && expected.wdl == Wdl::Win {
+                let dtm = i32::try_from(expected.dtm.unwrap()).unwrap();
+                assert_eq!(b.probe_dtz(&tb), Some(dtm), "{}", b.to_fen());
+            }
+            checked += 1;
+        }
+    }
+
+    #[test]
+    #[ignore = "needs the Syzygy files listed in tests/syzygy/README.md"]
+    fn root_moves_respect_the_fifty_move_counter() {
+        let tb = syzygy();
+        let fresh = board("8/8/8/3k4/8/8/8/KR6 w - - 0 1");
+        let ranked = tb.rank_root_moves(&fresh).unwrap();
+        assert!(ranked[0].dtz > 0 && ranked[0].rank > MAX_DTZ - 100);
+        assert!(ranked.windows(2).all(|w| w[0].rank >= w[1].rank));
+        // Far into the counter the same win can no longer be forced in time.
+        let late = board("8/8/8/3k4/8/8/8/KR6 w - - 90 80");
+        let ranked = tb.rank_root_moves(&late).unwrap();
+        assert!(ranked[0].rank < MAX_DTZ - 100, "{:?}", ranked[0]);
+        assert_eq!(tb.rank_root_moves(&board("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1")), None);
+    }
+}
@@ -0,0 +1,930 @@
+//! Endgame tablebases for up to four pieces, built by retrograde analysis.
+//!
+//! [`Tablebases::generate`] solves every position of a material signature
+//! such as `KQK`, `KRK`, `KPK`, `KBNK`, or `KQKR` exactly, using the engine's
+//! own move generator, and [`Board::probe_tablebase`] looks positions up:
+//! win, draw, or loss for the side to move ([`Wdl`]) and the distance to mate
+//! in plies. Tables a signature depends on (after captures and promotions)
+//! are generated first.
+//!
+//! # Generation
+//!
+//! Every index is decoded once to find checkmates, stalemates, and the
+//! results of moves that leave the table (captures and promotions, looked up
+//! in the smaller tables). Results then spread backwards ply by ply: the
+//! predecessors of a position lost in `n` are won in `n + 1`, and a position
+//! whose every move reaches a won position is lost once the last of them is
+//! known. Predecessors come from un-moving the pieces of the side that just
+//! moved. Whatever is left unresolved is a draw.
+//!
+//! Castling and en passant are not part of the tables, and the fifty-move
+//! rule is ignored: distances are pure distances to mate.
+//!
+//! # Index
+//!
+//! A position's pieces are listed as White's king, Black's king, White's
+//! other pieces, then Black's, each side ordered queen, rook, bishop,
+//! knight, pawn. The index is
+//!
+//! ```text
+//! stm + 2 * (king + K * (s[1] + 64 * (s[2] + 64 * s[3] ...)))
+//! ```
+//!
+//! where `stm` is 0 for White to move, `s[i]` are the square indices (a1 = 0,
+//! h8 = 63) of the pieces after White's king, and `king` is the class of
+//! White's king square. Symmetry reduces the king to `K` classes: without
+//! pawns the board is rotated and reflected so the king stands in the
+//! a1–d1–d4 triangle (`K = 10`, numbered a1, b1, c1, d1, b2, c2, d2, c3, d3,
+//! d4); with pawns it is only mirrored onto files a–d (`K = 32`, numbered
+//! `rank * 4 + file`). When several symmetric images qualify, the smallest
+//! index is used, and identical pieces are listed in ascending square order.
+//! Indices that are illegal (overlapping pieces, pawns on the first or last
+//! rank, the side not to move in check) or not canonical are unused.
+//!
+//! # File format
+//!
+//! One table per file, conventionally named `<signature>.cetb`:
+//!
+//! ```text
+//! offset  size  field
+//! 0       4     magic "CETB"
+//! 4       2     format version, little-endian (1)
+//! 6       1     signature length L
+//! 7       L     signature in ASCII, White's pieces first (e.g. "KQKR")
+//! 7 + L   4     number of entries N, little-endian
+//! 11 + L  N     one byte per index: 0 = draw (or unused index), otherwise
+//!               the distance to mate in plies plus one. An odd distance is
+//!               a win for the side to move, an even one a loss.
+//! ```
+//!
+//! A table for a signature also answers the colour-reversed signature
+//! (`KQKR` covers `KRKQ`), and material that cannot mate at all (`KK`,
+//! `KBK`, `KNK`) is a draw without any table.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::board::Board;
+use crate::types::{CastlingRights, Color, MoveKind, Piece, PieceType, Square};
+
+/// Most pieces, kings included, a table can have.
+pub const MAX_PIECES: usize = 4;
+
+/// Conventional file extension of a table.
+pub const EXTENSION: &str = "cetb";
+
+const MAGIC: &[u8; 4] = b"CETB";
+const VERSION: u16 = 1;
+
+/// King squares of the a1–d1–d4 triangle, in class order.
+const TRIANGLE: [u8; 10] = [0, 1, 2, 3, 9, 10, 11, 18, 19, 27];
+
+/// Non-king piece types in signature order.
+const SIGNATURE_ORDER: [PieceType; 5] =
+    [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight, PieceType::Pawn];
+
+/// Win, draw, or loss for the side to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wdl {
+    Loss,
+    Draw,
+    Win,
+}
+
+/// A tablebase answer for a position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TbProbe {
+    pub wdl: Wdl,
+    /// Plies to mate with best play (`None` for draws): odd for a win,
+    /// even for a loss, 0 if the side to move is already mated.
+    pub dtm: Option<u32>,
+}
+
+impl TbProbe {
+    const DRAW: Self = Self { wdl: Wdl::Draw, dtm: None };
+
+    /// Decodes a table entry.
+    fn from_entry(entry: u8) -> Self {
+        if entry == 0 {
+            return Self::DRAW;
+        }
+        let plies = u32::from(entry - 1);
+        Self { wdl: if plies % 2 == 1 { Wdl::Win } else { Wdl::Loss }, dtm: Some(plies) }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Material signatures
+// ---------------------------------------------------------------------------
+
+/// The material of a position: the non-king pieces of each side, in
+/// signature order. Written like `KQKR` (White's king and pieces first).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Signature {
+    pieces: [Vec<PieceType>; 2],
+}
+
+impl Signature {
+    fn new(mut white: Vec<PieceType>, mut black: Vec<PieceType>) -> Self {
+        let order = |pt: &PieceType| SIGNATURE_ORDER.iter().position(|p| p == pt);
+        white.sort_by_key(order);
+        black.sort_by_key(order);
+        Self { pieces: [white, black] }
+    }
+
+    /// Parses a signature such as `KQKR` (case-insensitive). `None` if it is
+    /// malformed or has more than [`MAX_PIECES`] pieces.
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_ascii_uppercase();
+        let (white, black) = s.strip_prefix('K')?.split_once('K')?;
+        let side = |pieces: &str| {
+            pieces
+                .chars()
+                .map(|c| {
+                    let piece = Piece::from_fen_char(c)?;
+                    (piece.piece_type() != PieceType::King).then(|| piece.piece_type())
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        let signature = Self::new(side(white)?, side(black)?);
+        (signature.piece_count() <= MAX_PIECES).then_some(signature)
+    }
+
+    /// The material on `board`.
+    #[must_use]
+    pub fn of(board: &Board) -> Self {
+        let side = |color| {
+            SIGNATURE_ORDER
+                .iter()
+                .flat_map(|&pt| std::iter::repeat_n(pt, board.pieces(color, pt).count_ones() as usize))
+                .collect()
+        };
+        Self { pieces: [side(Color::White), side(Color::Black)] }
+    }
+
+    /// Pieces including both kings.
+    #[must_use]
+    pub const fn piece_count(&self) -> usize {
+        2 + self.pieces[0].len() + self.pieces[1].len()
+    }
+
+    #[must_use]
+    pub fn has_pawns(&self) -> bool {
+        self.pieces.iter().flatten().any(|&pt| pt == PieceType::Pawn)
+    }
+
+    /// The same material with the colours exchanged.
+    #[must_use]
+    pub fn flipped(&self) -> Self {
+        Self { pieces: [self.pieces[1].clone(), self.pieces[0].clone()] }
+    }
+
+    /// The orientation tables are stored under: the stronger side as White.
+    #[must_use]
+    pub fn canonical(&self) -> Self {
+        let strength = |side: &[PieceType]| -> Vec<usize> {
+            side.iter().map(|pt| SIGNATURE_ORDER.len() - SIGNATURE_ORDER.iter().position(|p| p == pt).unwrap_or(0)).collect()
+        };
+        if strength(&self.pieces[1]) > strength(&self.pieces[0]) {
+            self.flipped()
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Neither side can ever mate: at most one minor piece and nothing else.
+    #[must_use]
+    pub fn is_trivial_draw(&self) -> bool {
+        let all: Vec<PieceType> = self.pieces.iter().flatten().copied().collect();
+        all.len() <= 1 && all.iter().all(|&pt| matches!(pt, PieceType::Bishop | PieceType::Knight))
+    }
+
+    /// Canonical signatures reachable by one capture or promotion that need
+    /// a table of their own.
+    fn dependencies(&self) -> Vec<Self> {
+        let mut deps = Vec::new();
+        for color in 0..2 {
+            for i in 0..self.pieces[color].len() {
+                let mut fewer = self.pieces.clone();
+                let removed = fewer[color].remove(i);
+                let [white, black] = fewer.clone();
+                deps.push(Self::new(white, black));
+                if removed == PieceType::Pawn {
+                    for promoted in PieceType::PROMOTABLE {
+                        let mut more = fewer.clone();
+                        more[color].push(promoted);
+                        let [white, black] = more;
+                        deps.push(Self::new(white, black));
+                    }
+                }
+            }
+        }
+        let mut deps: Vec<Self> = deps.iter().map(Self::canonical).filter(|s| !s.is_trivial_draw()).collect();
+        deps.dedup();
+        deps
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (color, side) in [Color::White, Color::Black].into_iter().zip(&self.pieces) {
+            f.write_str("K")?;
+            for &pt in side {
+                write!(f, "{}", Piece::new(pt, color).to_fen_char().to_ascii_uppercase())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Indexing
+// ---------------------------------------------------------------------------
+
+/// Maps positions of one signature to table indices and back.
+struct Indexer {
+    /// Pieces in index order: both kings, then the signature's pieces.
+    pieces: Vec<Piece>,
+    pawns: bool,
+}
+
+/// Applies symmetry `t`: bit 0 mirrors files, bit 1 flips ranks, bit 2
+/// transposes along the a1–h8 diagonal.
+const fn transform(sq: u8, t: u8) -> u8 {
+    let mut sq = sq;
+    if t & 1 != 0 {
+        sq ^= 0b111;
+    }
+    if t & 2 != 0 {
+        sq ^= 0b11_1000;
+    }
+    if t & 4 != 0 {
+        sq = (sq & 7) << 3 | sq >> 3;
+    }
+    sq
+}
+
+impl Indexer {
+    fn new(signature: &Signature) -> Self {
+        let mut pieces = vec![Piece::new(PieceType::King, Color::White), Piece::new(PieceType::King, Color::Black)];
+        for (color, side) in [Color::White, Color::Black].into_iter().zip(&signature.pieces) {
+            pieces.extend(side.iter().map(|&pt| Piece::new(pt, color)));
+        }
+        Self { pieces, pawns: signature.has_pawns() }
+    }
+
+    const fn king_classes(&self) -> usize {
+        if self.pawns {
+            32
+        } else {
+            TRIANGLE.len()
+        }
+    }
+
+    fn len(&self) -> usize {
+        2 * self.king_classes() * 64usize.pow(u32::try_from(self.pieces.len() - 1).unwrap_or(0))
+    }
+
+    /// The class of White's king on `sq`, if the symmetry allows it there.
+    fn king_class(&self, sq: u8) -> Option<usize> {
+        if self.pawns {
+            (sq & 7 < 4).then_some(usize::from(sq >> 3) * 4 + usize::from(sq & 7))
+        } else {
+            TRIANGLE.iter().position(|&t| t == sq)
+        }
+    }
+
+    /// The canonical index of a placement (squares in index order).
+    fn index(&self, squares: &[u8], stm: Color) -> usize {
+        let symmetries: &[u8] = if self.pawns { &[0, 1] } else { &[0, 1, 2, 3, 4, 5, 6, 7] };
+        let mut best = usize::MAX;
+        let mut image = squares.to_vec();
+        for &t in symmetries {
+            let Some(class) = self.king_class(transform(squares[0], t)) else {
+                continue;
+            };
+            for (dst, &sq) in image.iter_mut().zip(squares) {
+                *dst = transform(sq, t);
+            }
+            // Identical pieces are adjacent: list them in ascending order.
+            for i in 2..image.len() {
+                let mut j = i;
+                while j > 2 && self.pieces[j - 1] == self.pieces[j] && image[j - 1] > image[j] {
+                    image.swap(j - 1, j);
+                    j -= 1;
+                }
+            }
+            let mut index = 0;
+            for &sq in image[1..].iter().rev() {
+                index = index * 64 + usize::from(sq);
+            }
+            best = best.min(stm.index() + 2 * (class + self.king_classes() * index));
+        }
+        best
+    }
+
+    /// The placement and side to move of an index.
+    fn decode(&self, mut index: usize) -> (Vec<u8>, Color) {
+        let stm = if index.is_multiple_of(2) { Color::White } else { Color::Black };
+        index /= 2;
+        let class = index % self.king_classes();
+        index /= self.king_classes();
+        let king = if self.pawns {
+            u8::try_from(class / 4 * 8 + class % 4).unwrap_or(0)
+        } else {
+            TRIANGLE[class]
+        };
+        let mut squares = vec![king];
+        for _ in 1..self.pieces.len() {
+            squares.push(u8::try_from(index % 64).unwrap_or(0));
+            index /= 64;
+        }
+        (squares, stm)
+    }
+
+    /// The position at `index`, if the index is legal and canonical.
+    fn board(&self, index: usize) -> Option<Board> {
+        let (squares, stm) = self.decode(index);
+        let mut board = Board::empty();
+        for (&piece, &sq) in self.pieces.iter().zip(&squares) {
+            let square = Square::from_index(sq);
+            let back_rank = square.rank() == 0 || square.rank() == 7;
+            if board.occupied() & square.bitboard() != 0 || (piece.piece_type() == PieceType::Pawn && back_rank) {
+                return None;
+            }
+            board.put_piece(square, piece);
+        }
+        board.set_state(stm, CastlingRights::NONE, None, 0, 1);
+        (!board.is_in_check(stm.opposite()) && self.index(&squares, stm) == index).then_some(board)
+    }
+
+    /// The placement of `board`, which must have this indexer's material.
+    fn squares(&self, board: &Board) -> Vec<u8> {
+        let mut left = [[0u64; 6]; 2];
+        for color in [Color::White, Color::Black] {
+            for pt in PieceType::ALL {
+                left[color.index()][pt as usize] = board.pieces(color, pt);
+            }
+        }
+        self.pieces
+            .iter()
+            .map(|piece| {
+                let bb = &mut left[piece.color().index()][piece.piece_type() as usize];
+                let sq = bb.trailing_zeros();
+                *bb &= *bb - 1;
+                u8::try_from(sq).unwrap_or(0)
+            })
+            .collect()
+    }
+
+    fn board_index(&self, board: &Board) -> usize {
+        self.index(&self.squares(board), board.side_to_move())
+    }
+
+    /// Canonical indices of the positions that reach `board` (a legal
+    /// position at a canonical index) by a move that stays in the table,
+    /// whether or not they are legal themselves.
+    fn predecessors(&self, board: &Board) -> Vec<usize> {
+        let squares = self.squares(board);
+        let mover = board.side_to_move().opposite();
+        let empty = !board.occupied();
+        let mut preds = Vec::new();
+        for (i, &piece) in self.pieces.iter().enumerate() {
+            if piece.color() != mover {
+                continue;
+            }
+            let to = Square::from_index(squares[i]);
+            let origins = if piece.piece_type() == PieceType::Pawn {
+                pawn_origins(to, mover, empty)
+            } else {
+                board.attacks(piece, to) & empty
+            };
+            let mut bits = origins;
+            while bits != 0 {
+                let mut from = squares.clone();
+                from[i] = u8::try_from(bits.trailing_zeros()).unwrap_or(0);
+                bits &= bits - 1;
+                preds.push(self.index(&from, mover));
+            }
+        }
+        preds.sort_unstable();
+        preds.dedup();
+        preds
+    }
+}
+
+/// Squares a pawn of `color` on `to` can have pushed from.
+fn pawn_origins(to: Square, color: Color, empty: u64) -> u64 {
+    let (back, start_rank, double_rank) = match color {
+        Color::White => (-8i8, 1, 3),
+        Color::Black => (8, 6, 4),
+    };
+    let Some(single) = to.raw().checked_add_signed(back).filter(|&sq| sq < 64) else {
+        return 0;
+    };
+    let single = Square::from_index(single);
+    if empty & single.bitboard() == 0 || single.rank() == 0 || single.rank() == 7 {
+        return 0;
+    }
+    let mut origins = single.bitboard();
+    if to.rank() == double_rank {
+        let double = Square::new(to.file(), start_rank);
+        if empty & double.bitboard() != 0 {
+            origins |= double.bitboard();
+        }
+    }
+    origins
+}
+
+// ---------------------------------------------------------------------------
+// Tables
+// ---------------------------------------------------------------------------
+
+/// The solved positions of one material signature.
+pub struct Table {
+    signature: Signature,
+    indexer: Indexer,
+    data: Vec<u8>,
+}
+
+impl Table {
+    #[must_use]
+    pub const fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Number of indices, used or not.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The longest win in the table, in plies.
+    #[must_use]
+    pub fn longest_win(&self) -> Option<u32> {
+        self.data.iter().filter_map(|&e| TbProbe::from_entry(e).dtm).filter(|d| d % 2 == 1).max()
+    }
+
+    /// The entry for `board`, which must have this table's material.
+    fn probe(&self, board: &Board) -> TbProbe {
+        TbProbe::from_entry(self.data[self.indexer.board_index(board)])
+    }
+
+    /// Serializes the table in the documented file format.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let name = self.signature.to_string();
+        let mut out = Vec::with_capacity(11 + name.len() + self.data.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.push(u8::try_from(name.len()).unwrap_or(u8::MAX));
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&u32::try_from(self.data.len()).unwrap_or(u32::MAX).to_le_bytes());
+        out.extend_from_slice(&self.data);
+        out
+    }
+
+    /// Parses a table file. `None` if the header is wrong or the entry count
+    /// does not match the signature.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let rest = bytes.strip_prefix(MAGIC)?;
+        let (version, rest) = rest.split_first_chunk::<2>()?;
+        if u16::from_le_bytes(*version) != VERSION {
+            return None;
+        }
+        let (&name_len, rest) = rest.split_first()?;
+        let (name, rest) = rest.split_at_checked(usize::from(name_len))?;
+        let signature = Signature::parse(std::str::from_utf8(name).ok()?)?;
+        let (count, data) = rest.split_first_chunk::<4>()?;
+        let indexer = Indexer::new(&signature);
+        let count = usize::try_from(u32::from_le_bytes(*count)).ok()?;
+        (count == indexer.len() && data.len() == count && data.iter().all(|&e| e != u8::MAX))
+            .then(|| Self { signature, indexer, data: data.to_vec() })
+    }
+
+    /// Writes the table to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Any I/O error from writing the file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    /// Reads a table from `path`.
+    ///
+    /// # Errors
+    ///
+    /// Any I/O error, or [`io::ErrorKind::InvalidData`] if the file is not
+    /// a valid table.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a tablebase file"))
+    }
+}
+
+/// A set of tables, keyed by canonical signature.
+#[derive(Default)]
+pub struct Tablebases {
+    tables: HashMap<Signature, Table>,
+}
+
+impl Tablebases {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a table, replacing any with the same signature.
+    pub fn add(&mut self, table: Table) {
+        self.tables.insert(table.signature.canonical(), table);
+    }
+
+    /// The table covering `signature` in either colour orientation.
+    #[must_use]
+    pub fn table(&self, signature: &str) -> Option<&Table> {
+        self.tables.get(&Signature::parse(signature)?.canonical())
+    }
+
+    /// Every table, in no particular order.
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+
+    /// Loads every `.cetb` file in `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Any I/O error, or [`io::ErrorKind::InvalidData`] for a bad table.
+    pub fn load_dir(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let mut tb = Self::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                tb.add(Table::load(&path)?);
+            }
+        }
+        Ok(tb)
+    }
+
+    /// Generates the table for `signature` (e.g. `"KQKR"`) and those it
+    /// depends on, unless already present, and returns it. `None` for an
+    /// invalid signature or one that needs no table (`KK`, `KBK`, `KNK`).
+    pub fn generate(&mut self, signature: &str) -> Option<&Table> {
+        let signature = Signature::parse(signature)?.canonical();
+        self.generate_signature(&signature);
+        self.tables.get(&signature)
+    }
+
+    fn generate_signature(&mut self, signature: &Signature) {
+        if signature.is_trivial_draw() || self.tables.contains_key(signature) {
+            return;
+        }
+        for dependency in signature.dependencies() {
+            self.generate_signature(&dependency);
+        }
+        let table = Generator::new(signature, self).run();
+        self.tables.insert(signature.clone(), table);
+    }
+
+    /// The result for `board` ignoring castling and en passant, if its
+    /// material is covered.
+    fn lookup(&self, board: &Board) -> Option<TbProbe> {
+        let signature = Signature::of(board);
+        if signature.piece_count() > MAX_PIECES {
+            return None;
+        }
+        if signature.is_trivial_draw() {
+            return Some(TbProbe::DRAW);
+        }
+        if let Some(table) = self.tables.get(&signature) {
+            return Some(table.probe(board));
+        }
+        self.tables.get(&signature.flipped()).map(|table| table.probe(&board.flipped()))
+    }
+}
+
+impl Board {
+    /// Looks the position up in `tablebases`: the result for the side to
+    /// move and the distance to mate. `None` if the material is not covered,
+    /// castling rights remain, en passant is possible, or the side not to
+    /// move is in check.
+    #[must_use]
+    pub fn probe_tablebase(&self, tablebases: &Tablebases) -> Option<TbProbe> {
+        let en_passant = || self.generate_legal_moves().iter().any(|mv| mv.kind() == MoveKind::EnPassant);
+        if self.castling() != CastlingRights::NONE || self.is_in_check(self.side_to_move().opposite()) || en_passant() {
+            return None;
+        }
+        tablebases.lookup(self)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Retrograde generation
+// ---------------------------------------------------------------------------
+
+const UNKNOWN: u8 = 0;
+const BROKEN: u8 = 1;
+const WIN: u8 = 2;
+const LOSS: u8 = 3;
+const DRAW: u8 = 4;
+
+/// Longest distance an entry byte can hold.
+const MAX_DTM: usize = 253;
+
+struct Generator<'a> {
+    signature: Signature,
+    indexer: Indexer,
+    /// Smaller tables for moves that leave this one.
+    tablebases: &'a Tablebases,
+    state: Vec<u8>,
+    dtm: Vec<u8>,
+    /// Moves within the table not yet known to lose (distinct successors).
+    remaining: Vec<u8>,
+    /// Whether every move leaving the table reaches a win for the opponent.
+    can_lose: Vec<bool>,
+    /// Longest such exit, in plies from here.
+    exit_loss: Vec<u8>,
+    /// Shortest win by leaving the table, in plies (0 for none).
+    exit_win: Vec<u8>,
+    propagated: Vec<bool>,
+    /// Positions to propagate, by distance.
+    buckets: Vec<Vec<usize>>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(signature: &Signature, tablebases: &'a Tablebases) -> Self {
+        let indexer = Indexer::new(signature);
+        let len = indexer.len();
+        Self {
+            signature: signature.clone(),
+            indexer,
+            tablebases,
+            state: vec![UNKNOWN; len],
+            dtm: vec![0; len],
+            remaining: vec![0; len],
+            can_lose: vec![true; len],
+            exit_loss: vec![0; len],
+            exit_win: vec![0; len],
+            propagated: vec![false; len],
+            buckets: vec![Vec::new(); MAX_DTM + 2],
+        }
+    }
+
+    fn run(mut self) -> Table {
+        for index in 0..self.indexer.len() {
+            self.initialize(index);
+        }
+        for distance in 0..self.buckets.len() {
+            for index in std::mem::take(&mut self.buckets[distance]) {
+                if self.state[index] == UNKNOWN && usize::from(self.exit_win[index]) == distance {
+                    self.state[index] = WIN;
+                    self.dtm[index] = self.exit_win[index];
+                }
+                if !self.propagated[index] && matches!(self.state[index], WIN | LOSS) {
+                    self.propagated[index] = true;
+                    self.propagate(index, distance);
+                }
+            }
+        }
+        let data = self
+            .state
+            .iter()
+            .zip(&self.dtm)
+            .map(|(&state, &dtm)| if matches!(state, WIN | LOSS) { dtm + 1 } else { 0 })
+            .collect();
+        Table { signature: self.signature, indexer: self.indexer, data }
+    }
+
+    /// Classifies a position by the moves that end the game or leave the table.
+    fn initialize(&mut self, index: usize) {
+        let Some(board) = self.indexer.board(index) else {
+            self.state[index] = BROKEN;
+            return;
+        };
+        let moves = board.generate_legal_moves();
+        if moves.is_empty() {
+            if board.is_in_check(board.side_to_move()) {
+                self.set(index, LOSS, 0);
+            } else {
+                self.state[index] = DRAW;
+            }
+            return;
+        }
+        let mut successors = Vec::with_capacity(moves.len());
+        for mv in moves {
+            let mut child = board.clone();
+            child.apply_unchecked(mv);
+            if child.occupied().count_ones() == board.occupied().count_ones() && mv.promotion_piece().is_none() {
+                successors.push(self.indexer.board_index(&child));
+                continue;
+            }
+            let result = self.tablebases.lookup(&child).expect("dependencies are generated first");
+            let plies = result.dtm.map_or(0, |d| u8::try_from(d + 1).unwrap_or(u8::MAX));
+            match result.wdl {
+                Wdl::Win => self.exit_loss[index] = self.exit_loss[index].max(plies),
+                Wdl::Loss => {
+                    self.can_lose[index] = false;
+                    if self.exit_win[index] == 0 || plies < self.exit_win[index] {
+                        self.exit_win[index] = plies;
+                    }
+                }
+                Wdl::Draw => self.can_lose[index] = false,
+            }
+        }
+        successors.sort_unstable();
+        successors.dedup();
+        self.remaining[index] = u8::try_from(successors.len()).unwrap_or(u8::MAX);
+        if self.exit_win[index] > 0 {
+            self.buckets[usize::from(self.exit_win[index])].push(index);
+        }
+        if successors.is_empty() && self.can_lose[index] {
+            self.set(index, LOSS, self.exit_loss[index]);
+        }
+    }
+
+    fn set(&mut self, index: usize, state: u8, dtm: u8) {
+        assert!(usize::from(dtm) <= MAX_DTM, "distance to mate too long for {}", self.signature);
+        self.state[index] = state;
+        self.dtm[index] = dtm;
+        self.buckets[usize::from(dtm)].push(index);
+    }
+
+    /// Spreads the result of `index`, decided at `distance`, to its predecessors.
+    fn propagate(&mut self, index: usize, distance: usize) {
+        let board = self.indexer.board(index).expect("decided positions are legal");
+        let lost = self.state[index] == LOSS;
+        let next = u8::try_from(distance + 1).unwrap_or(u8::MAX);
+        for pred in self.indexer.predecessors(&board) {
+            if self.state[pred] != UNKNOWN {
+                continue;
+            }
+            if lost {
+                self.set(pred, WIN, next);
+            } else {
+                self.remaining[pred] -= 1;
+                if self.remaining[pred] == 0 && self.can_lose[pred] {
+                    self.set(pred, LOSS, next.max(self.exit_loss[pred]));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(tb: &Tablebases, fen: &str) -> Option<TbProbe> {
+        Board::from_fen(fen).unwrap().probe_tablebase(tb)
+    }
+
+    /// Checks every legal position of a table against its successors: a win
+    /// in `d` has a move to a loss in `d - 1` and none faster, a loss in `d`
+    /// has only moves to wins, the slowest in `d - 1`, and a draw has a move
+    /// to a draw but none to a loss.
+    fn assert_consistent(tb: &Tablebases, signature: &str) {
+        let table = tb.table(signature).unwrap();
+        for index in 0..table.len() {
+            let Some(board) = table.indexer.board(index) else {
+                continue;
+            };
+            let result = table.probe(&board);
+            let children: Vec<TbProbe> = board
+                .generate_legal_moves()
+                .into_iter()
+                .map(|mv| {
+                    let mut child = board.clone();
+                    child.apply_unchecked(mv);
+                    tb.lookup(&child).unwrap()
+                })
+                .collect();
+            let losses = children.iter().filter(|c| c.wdl == Wdl::Loss).filter_map(|c| c.dtm);
+            match result.wdl {
+                Wdl::Win => assert_eq!(losses.min().map(|d| d + 1), result.dtm, "{}", board.to_fen()),
+                Wdl::Loss => {
+                    assert!(children.iter().all(|c| c.wdl == Wdl::Win), "{}", board.to_fen());
+                    let slowest = children.iter().filter_map(|c| c.dtm).max().map_or(0, |d| d + 1);
+                    assert_eq!(Some(slowest), result.dtm, "{}", board.to_fen());
+                }
+                Wdl::Draw => {
+                    assert!(children.iter().all(|c| c.wdl != Wdl::Loss), "{}", board.to_fen());
+                    assert!(children.is_empty() || children.iter().any(|c| c.wdl == Wdl::Draw), "{}", board.to_fen());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn signatures() {
+        let s = Signature::parse("kqkr").unwrap();
+        assert_eq!(s.to_string(), "KQKR");
+        assert_eq!(Signature::parse("KRKQ").unwrap().canonical(), s);
+        assert_eq!(Signature::parse("KNBK").unwrap().to_string(), "KBNK");
+        assert_eq!(Signature::of(&Board::from_fen("8/8/8/8/8/2k5/8/KQ2r3 w - - 0 1").unwrap()), s);
+        for bad in ["", "QK", "KQ", "KQKRR", "KXK", "KKK"] {
+            assert_eq!(Signature::parse(bad), None, "{bad}");
+        }
+        assert!(Signature::parse("KNK").unwrap().is_trivial_draw());
+        assert!(!Signature::parse("KNKN").unwrap().is_trivial_draw());
+        let deps: Vec<String> = Signature::parse("KPK").unwrap().dependencies().iter().map(ToString::to_string).collect();
+        assert_eq!(deps, ["KQK", "KRK"]);
+    }
+
+    #[test]
+    fn symmetric_positions_share_an_index() {
+        let indexer = Indexer::new(&Signature::parse("KRRK").unwrap());
+        let board = Board::from_fen("8/8/8/3k4/8/8/1R6/K3R3 b - - 0 1").unwrap();
+        let index = indexer.board_index(&board);
+        assert_eq!(indexer.board_index(&board.mirrored()), index);
+        assert_eq!(indexer.board_index(&board.flipped().flipped()), index);
+        let board = indexer.board(index).unwrap();
+        assert_eq!(indexer.board_index(&board), index);
+    }
+
+    #[test]
+    fn kqk_and_krk() {
+        let mut tb = Tablebases::new();
+        tb.generate("KQK").unwrap();
+        tb.generate("KRK").unwrap();
+        // The longest mates: ten moves with the queen, sixteen with the rook.
+        assert_eq!(tb.table("KQK").unwrap().longest_win(), Some(19));
+        assert_eq!(tb.table("KRK").unwrap().longest_win(), Some(31));
+        assert_consistent(&tb, "KQK");
+
+        let mate_in_one = probe(&tb, "k7/8/1K6/8/8/8/8/6Q1 w - - 0 1").unwrap();
+        assert_eq!(mate_in_one, TbProbe { wdl: Wdl::Win, dtm: Some(1) });
+        let mated = probe(&tb, "k6Q/8/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(mated, TbProbe { wdl: Wdl::Loss, dtm: Some(0) });
+        // Colours reversed, and a stalemate.
+        let reversed = probe(&tb, "K7/8/1k6/8/8/8/8/6q1 b - - 0 1").unwrap();
+        assert_eq!(reversed, mate_in_one);
+        assert_eq!(probe(&tb, "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap().wdl, Wdl::Draw);
+        // Black to move takes the hanging rook.
+        assert_eq!(probe(&tb, "8/8/8/8/8/8/6kR/K7 b - - 0 1").unwrap().wdl, Wdl::Draw);
+    }
+
+    #[test]
+    fn kpk() {
+        let mut tb = Tablebases::new();
+        tb.generate("KPK").unwrap();
+        assert_consistent(&tb, "KPK");
+        // King in front of the pawn on the sixth rank wins either way.
+        assert_eq!(probe(&tb, "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1").unwrap().wdl, Wdl::Win);
+        assert_eq!(probe(&tb, "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1").unwrap().wdl, Wdl::Loss);
+        // Pawn on the seventh: White to move wins, Black to move is stalemated.
+        assert_eq!(probe(&tb, "4k3/4P3/4K3/8/8/8/8/8 w - - 0 1").unwrap().wdl, Wdl::Win);
+        assert_eq!(probe(&tb, "4k3/4P3/4K3/8/8/8/8/8 b - - 0 1").unwrap().wdl, Wdl::Draw);
+        // A rook pawn with the defender in the corner.
+        assert_eq!(probe(&tb, "k7/8/K7/P7/8/8/8/8 w - - 0 1").unwrap().wdl, Wdl::Draw);
+    }
+
+    #[test]
+    fn probe_needs_covered_positions() {
+        let mut tb = Tablebases::new();
+        tb.generate("KQK");
+        assert_eq!(probe(&tb, "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1"), None);
+        assert_eq!(probe(&tb, "4k3/8/8/8/8/8/8/3RK3 w - - 0 1"), None);
+        assert_eq!(probe(&tb, crate::board::tests::KIWIPETE), None);
+        assert_eq!(probe(&tb, "8/8/8/3k4/8/8/8/2BK4 w - - 0 1"), Some(TbProbe::DRAW));
+        assert!(tb.generate("KK").is_none() && tb.generate("KQQKQ").is_none());
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let mut tb = Tablebases::new();
+        let table = tb.generate("KRK").unwrap();
+        let bytes = table.to_bytes();
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(&bytes[7..10], b"KRK");
+        let copy = Table::from_bytes(&bytes).unwrap();
+        assert_eq!((copy.signature(), &copy.data), (table.signature(), &table.data));
+        assert!(Table::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        let mut renamed = bytes;
+        renamed[8] = b'Q';
+        assert!(Table::from_bytes(&renamed).is_some(), "same size, different material");
+        renamed[0] = b'X';
+        assert!(Table::from_bytes(&renamed).is_none());
+    }
+
+    #[test]
+    #[ignore = "generates four-piece tables; run with --ignored --release"]
+    fn four_piece_tables() {
+        let mut tb = Tablebases::new();
+        // KBNK: mate in 33 at most; KQKR: mate in 35.
+        assert_eq!(tb.generate("KBNK").unwrap().longest_win(), Some(65));
+        assert_eq!(tb.generate("KQKR").unwrap().longest_win(), Some(69));
+        assert_consistent(&tb, "KBNK");
+        // The pinned queen takes the pinning rook.
+        assert_eq!(probe(&tb, "8/8/8/8/8/2k5/8/KQ2r3 w - - 0 1").map(|p| p.wdl), Some(Wdl::Win));
+    }
+}
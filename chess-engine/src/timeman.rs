@@ -0,0 +1,209 @@
+//! Time management: turning a chess clock into search time limits.
+//!
+//! [`TimeControl::budget`] splits the time left on the clock, the increment,
+//! and the moves until the next time control into two limits:
+//!
+//! - the *soft* limit, how long a move should normally take. The search
+//!   checks it after every completed iteration, scaled by how settled the
+//!   result is: a best move that keeps changing or a falling score extends
+//!   it, a best move that has held for several iterations shrinks it;
+//! - the *hard* limit, which aborts the search mid-iteration no matter what.
+//!
+//! With a single legal move the search stops after its first iteration.
+//!
+//! [`SearchLimits::clock`](crate::search::SearchLimits::clock) applies a
+//! budget to a search; the UCI and `XBoard` front ends and the WASM
+//! component's `best-move-on-clock` use it. On `wasm32-unknown-unknown`,
+//! where the search has no clock, only the forced-move rule applies.
+
+use std::time::Duration;
+
+use crate::search::SearchResult;
+use crate::types::Move;
+
+/// Time kept in reserve for communication delays.
+pub const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+/// Moves to plan for when the time control does not say.
+pub const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// The hard limit is at most this many soft limits...
+const HARD_FACTOR: u64 = 4;
+
+/// ...and at most this fraction of the usable time, unless the soft limit
+/// is larger.
+const HARD_SHARE: u64 = 2;
+
+/// Soft-limit scale in percent by iterations the best move has held for:
+/// a change in the last iteration extends, long stability shrinks.
+const STABILITY_PERCENT: [u32; 5] = [140, 110, 100, 85, 70];
+
+/// Soft-limit scale in percent for a score drop since the last iteration
+/// of at least the given centipawns, largest drop first.
+const SCORE_DROP_PERCENT: [(i32, u32); 2] = [(60, 160), (25, 125)];
+
+/// The state of the engine's clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimeControl {
+    /// Time left on the clock.
+    pub remaining: Duration,
+    /// Time added after each move.
+    pub increment: Duration,
+    /// Moves until the next time control, if the time control has one.
+    pub moves_to_go: Option<u32>,
+}
+
+/// Search time limits for one move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeBudget {
+    /// Normal thinking time, checked between iterations.
+    pub soft: Duration,
+    /// Time the search never exceeds.
+    pub hard: Duration,
+}
+
+impl TimeControl {
+    /// A clock with `remaining` time, `increment` per move, and optionally
+    /// `moves_to_go` moves until the next time control.
+    #[must_use]
+    pub const fn new(remaining: Duration, increment: Duration, moves_to_go: Option<u32>) -> Self {
+        Self { remaining, increment, moves_to_go }
+    }
+
+    /// The time limits for the next move: an equal share of the remaining
+    /// time over the moves to go plus most of the increment, never more than
+    /// the clock allows after [`MOVE_OVERHEAD`], and at least a millisecond.
+    #[must_use]
+    pub fn budget(&self) -> TimeBudget {
+        let remaining = millis(self.remaining);
+        let usable = remaining.saturating_sub(millis(MOVE_OVERHEAD)).max(1);
+        let moves = u64::from(self.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1));
+        let share = remaining / moves + millis(self.increment).saturating_mul(3) / 4;
+        let soft = share.min(usable).max(1);
+        let hard = soft.saturating_mul(HARD_FACTOR).min((usable / HARD_SHARE).max(soft));
+        TimeBudget { soft: Duration::from_millis(soft), hard: Duration::from_millis(hard) }
+    }
+}
+
+/// Whole milliseconds, saturating.
+fn millis(d: Duration) -> u64 {
+    u64::try_from(d.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Decides between iterations whether a search on the clock should stop.
+pub(crate) struct TimeManager {
+    budget: TimeBudget,
+    best_move: Option<Move>,
+    /// Completed iterations since the best move last changed.
+    stable_iterations: usize,
+    previous_score: Option<i32>,
+}
+
+impl TimeManager {
+    pub(crate) const fn new(budget: TimeBudget) -> Self {
+        Self { budget, best_move: None, stable_iterations: 0, previous_score: None }
+    }
+
+    /// Records a completed iteration and returns whether to stop searching,
+    /// given the number of legal root moves and the time spent so far
+    /// (`None` without a clock).
+    pub(crate) fn iteration_done(&mut self, result: &SearchResult, legal_moves: usize, elapsed: Option<Duration>) -> bool {
+        if legal_moves == 1 {
+            return true;
+        }
+        if result.best_move == self.best_move {
+            self.stable_iterations += 1;
+        } else {
+            self.best_move = result.best_move;
+            self.stable_iterations = 0;
+        }
+        let drop = self.previous_score.map_or(0, |previous| previous - result.score);
+        self.previous_score = Some(result.score);
+        elapsed.is_some_and(|elapsed| elapsed >= self.soft_limit(drop))
+    }
+
+    /// The soft limit scaled for the current stability and score drop.
+    fn soft_limit(&self, drop: i32) -> Duration {
+        let stability = STABILITY_PERCENT[self.stable_iterations.min(STABILITY_PERCENT.len() - 1)];
+        let falling = SCORE_DROP_PERCENT.iter().find(|&&(cp, _)| drop >= cp).map_or(100, |&(_, percent)| percent);
+        (self.budget.soft * stability * falling / 10_000).min(self.budget.hard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn result(best_move: &str, score: i32) -> SearchResult {
+        let best_move = Move::from_uci(best_move);
+        SearchResult {
+            best_move,
+            score,
+            depth: 1,
+            seldepth: 1,
+            nodes: 0,
+            time: Duration::ZERO,
+            pv: best_move.into_iter().collect(),
+            lines: Vec::new(),
+            tt: crate::tt::TtStats::default(),
+        }
+    }
+
+    #[test]
+    fn budgets() {
+        let b = TimeControl::new(ms(60_000), ms(1000), None).budget();
+        assert_eq!(b, TimeBudget { soft: ms(2750), hard: ms(11_000) });
+        // Short on time: the hard limit keeps half the clock in reserve.
+        let b = TimeControl::new(ms(1000), ms(0), None).budget();
+        assert_eq!(b, TimeBudget { soft: ms(33), hard: ms(132) });
+        let b = TimeControl::new(ms(3000), ms(0), Some(2)).budget();
+        assert_eq!(b, TimeBudget { soft: ms(1500), hard: ms(1500) });
+        // Last move before the control: everything but the overhead.
+        let b = TimeControl::new(ms(100), ms(0), Some(1)).budget();
+        assert_eq!(b, TimeBudget { soft: ms(70), hard: ms(70) });
+        let b = TimeControl::new(ms(0), ms(0), Some(0)).budget();
+        assert_eq!(b, TimeBudget { soft: ms(1), hard: ms(1) });
+    }
+
+    #[test]
+    fn forced_move_stops_at_once() {
+        let mut tm = TimeManager::new(TimeBudget { soft: ms(1000), hard: ms(4000) });
+        assert!(tm.iteration_done(&result("e2e4", 0), 1, Some(ms(0))));
+        assert!(tm.iteration_done(&result("e2e4", 0), 1, None));
+    }
+
+    #[test]
+    fn stability_shrinks_and_changes_extend() {
+        let budget = TimeBudget { soft: ms(1000), hard: ms(4000) };
+        let mut tm = TimeManager::new(budget);
+        // A new best move: 140% of the soft limit.
+        assert!(!tm.iteration_done(&result("e2e4", 10), 20, Some(ms(1300))));
+        assert!(!tm.iteration_done(&result("d2d4", 10), 20, Some(ms(1300))));
+        // Held for four iterations: 70%.
+        for _ in 0..3 {
+            tm.iteration_done(&result("d2d4", 10), 20, Some(ms(0)));
+        }
+        assert!(!tm.iteration_done(&result("d2d4", 10), 20, Some(ms(650))));
+        assert!(tm.iteration_done(&result("d2d4", 10), 20, Some(ms(700))));
+        // Without a clock only a forced move stops the search.
+        assert!(!tm.iteration_done(&result("d2d4", 10), 20, None));
+    }
+
+    #[test]
+    fn score_drops_extend_up_to_the_hard_limit() {
+        let budget = TimeBudget { soft: ms(1000), hard: ms(2000) };
+        let mut tm = TimeManager::new(budget);
+        for _ in 0..3 {
+            tm.iteration_done(&result("e2e4", 50), 20, Some(ms(0)));
+        }
+        // Stable (85%) but 30 cp worse: 125% of that.
+        assert!(!tm.iteration_done(&result("e2e4", 20), 20, Some(ms(1000))));
+        assert!(tm.iteration_done(&result("e2e4", 20), 20, Some(ms(1000))));
+        // A new move (140%) after a collapse (160%) is capped by the hard limit.
+        assert!(tm.iteration_done(&result("d2d4", -500), 20, Some(ms(2000))));
+    }
+}
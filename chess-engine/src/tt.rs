@@ -0,0 +1,479 @@
+//! Transposition table: a fixed-size hash table of search results.
+//!
+//! Entries are keyed by [`Board::polyglot_key`](crate::board::Board::polyglot_key)
+//! and record the score bound, depth, best move, and the search generation
+//! ("age") that wrote them. Each entry is two `u64` words — the full key and
+//! a packed data word — grouped into two-entry buckets:
+//!
+//! - slot 0 is *depth-preferred*: it is only overwritten by the same position,
+//!   an equal or deeper search, or an entry left over from an earlier search;
+//! - slot 1 *always replaces*, so recent shallow results still get stored.
+//!
+//! Mate scores are stored relative to the node ("mate in N from here") rather
+//! than the root, so that they stay correct when the same position is reached
+//! at a different ply; [`probe`](TranspositionTable::probe) and
+//! [`store`](TranspositionTable::store) convert using the caller's ply.
+//!
+//! The table is lock-free so that the threads of a parallel search can share
+//! it: every word is an atomic, and the key word is stored XOR-ed with the
+//! data word. A probe that races with a store of another position reads a
+//! key and data word that do not belong together, fails the key check, and
+//! is treated as a miss.
+
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::search::MATE_BOUND;
+use crate::types::{Move, MoveKind, PieceType, Square};
+
+/// Bytes per bucket: two entries of a key word and a data word.
+const BUCKET_BYTES: usize = 32;
+
+/// Entries sampled by [`TranspositionTable::hashfull`].
+const HASHFULL_SAMPLE: usize = 1000;
+
+/// How a stored score relates to the true value of the position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact (a PV node).
+    Exact,
+    /// The true score is at least this (the search failed high).
+    Lower,
+    /// The true score is at most this (the search failed low).
+    Upper,
+}
+
+/// A decoded table entry, with its score already adjusted to the probing ply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TtEntry {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+}
+
+/// Probe counters since the last [`TranspositionTable::new_search`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TtStats {
+    pub probes: u64,
+    pub hits: u64,
+    pub stores: u64,
+    /// Table occupancy by the current search in per mille, as UCI reports it.
+    pub hashfull: u16,
+}
+
+impl TtStats {
+    /// Fraction of probes that found their position, in `0.0..=1.0`.
+    #[must_use]
+    pub fn hit_rate(&self) -> f64 {
+        if self.probes == 0 {
+            0.0
+        } else {
+            #[allow(clippy::cast_precision_loss)]
+            {
+                self.hits as f64 / self.probes as f64
+            }
+        }
+    }
+}
+
+/// One entry: the key XOR-ed with the data word, and the data word.
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    /// The data word, if the slot holds `key`.
+    fn load(&self, key: u64) -> Option<u64> {
+        let data = self.data.load(Ordering::Relaxed);
+        (data != 0 && self.key.load(Ordering::Relaxed) ^ data == key).then_some(data)
+    }
+
+    fn store(&self, key: u64, data: u64) {
+        self.key.store(key ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.store(0, 0);
+    }
+}
+
+/// Probe counters, shared by all threads using the table.
+#[derive(Default)]
+struct Counters {
+    probes: AtomicU64,
+    hits: AtomicU64,
+    stores: AtomicU64,
+}
+
+impl Counters {
+    fn reset(&self) {
+        for counter in [&self.probes, &self.hits, &self.stores] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Fixed-size transposition table. A size of 0 MB disables it.
+///
+/// Probing and storing take `&self`, so one table can be shared between
+/// threads (e.g. in an `Arc`).
+pub struct TranspositionTable {
+    buckets: Vec<[Slot; 2]>,
+    age: AtomicU8,
+    counters: Counters,
+}
+
+impl TranspositionTable {
+    /// Creates a table using about `mb` mebibytes.
+    #[must_use]
+    pub fn new(mb: usize) -> Self {
+        Self::with_buckets(mb * 1024 * 1024 / BUCKET_BYTES)
+    }
+
+    fn with_buckets(len: usize) -> Self {
+        Self {
+            buckets: std::iter::repeat_with(Default::default).take(len).collect(),
+            age: AtomicU8::new(0),
+            counters: Counters::default(),
+        }
+    }
+
+    /// Reallocates to about `mb` mebibytes, discarding all entries.
+    pub fn resize(&mut self, mb: usize) {
+        *self = Self::new(mb);
+    }
+
+    /// Size in mebibytes.
+    #[must_use]
+    pub const fn size_mb(&self) -> usize {
+        self.buckets.len() * BUCKET_BYTES / (1024 * 1024)
+    }
+
+    /// Discards all entries.
+    pub fn clear(&self) {
+        self.buckets.iter().flatten().for_each(Slot::clear);
+        self.age.store(0, Ordering::Relaxed);
+        self.counters.reset();
+    }
+
+    /// Starts a new search generation: older entries become preferred for
+    /// replacement, and the statistics restart.
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+        self.counters.reset();
+    }
+
+    /// Counters for the current search, including a fresh `hashfull` sample.
+    #[must_use]
+    pub fn stats(&self) -> TtStats {
+        TtStats {
+            probes: self.counters.probes.load(Ordering::Relaxed),
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            stores: self.counters.stores.load(Ordering::Relaxed),
+            hashfull: self.hashfull(),
+        }
+    }
+
+    /// Per mille of sampled entries written during the current search.
+    #[must_use]
+    pub fn hashfull(&self) -> u16 {
+        let age = self.age.load(Ordering::Relaxed);
+        let sample = self.buckets.iter().flatten().take(HASHFULL_SAMPLE);
+        let (mut total, mut used) = (0u32, 0u32);
+        for slot in sample {
+            total += 1;
+            let data = slot.data.load(Ordering::Relaxed);
+            if data != 0 && unpack_age(data) == age {
+                used += 1;
+            }
+        }
+        (used * 1000).checked_div(total).map_or(0, |v| u16::try_from(v).unwrap_or(1000))
+    }
+
+    fn bucket_index(&self, key: u64) -> usize {
+        // Multiply-shift maps the key onto any table length without a modulo.
+        let len = self.buckets.len() as u128;
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            ((u128::from(key) * len) >> 64) as usize
+        }
+    }
+
+    /// Looks up `key`, adjusting mate scores to be relative to `ply`.
+    pub fn probe(&self, key: u64, ply: usize) -> Option<TtEntry> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        self.counters.probes.fetch_add(1, Ordering::Relaxed);
+        let bucket = &self.buckets[self.bucket_index(key)];
+        let data = bucket.iter().find_map(|slot| slot.load(key))?;
+        self.counters.hits.fetch_add(1, Ordering::Relaxed);
+        let mut entry = unpack(data);
+        entry.score = score_from_tt(entry.score, ply);
+        Some(entry)
+    }
+
+    /// Stores a search result for `key` found at `ply` from the root.
+    ///
+    /// A `None` move keeps the move already stored for the same position.
+    pub fn store(
+        &self,
+        key: u64,
+        ply: usize,
+        depth: u8,
+        score: i32,
+        bound: Bound,
+        best_move: Option<Move>,
+    ) {
+        if self.buckets.is_empty() {
+            return;
+        }
+        self.counters.stores.fetch_add(1, Ordering::Relaxed);
+        let age = self.age.load(Ordering::Relaxed);
+        let bucket = &self.buckets[self.bucket_index(key)];
+
+        let first = bucket[0].data.load(Ordering::Relaxed);
+        let replace_first = first == 0
+            || bucket[0].load(key).is_some()
+            || unpack_age(first) != age
+            || depth >= unpack(first).depth;
+        let slot = &bucket[usize::from(!replace_first)];
+
+        let best_move = best_move.or_else(|| slot.load(key).and_then(|old| unpack(old).best_move));
+        slot.store(key, pack(best_move, score_to_tt(score, ply), depth, bound, age));
+    }
+}
+
+/// Converts a root-relative mate score into a node-relative one.
+const fn score_to_tt(score: i32, ply: usize) -> i32 {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let ply = ply as i32;
+    if score >= MATE_BOUND {
+        score + ply
+    } else if score <= -MATE_BOUND {
+        score - ply
+    } else {
+        score
+    }
+}
+
+/// Converts a node-relative mate score back to one relative to the root.
+const fn score_from_tt(score: i32, ply: usize) -> i32 {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let ply = ply as i32;
+    if score >= MATE_BOUND {
+        score - ply
+    } else if score <= -MATE_BOUND {
+        score + ply
+    } else {
+        score
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Data word layout (an all-zero word marks an empty slot):
+//
+//   bits  0..16  move (from | to << 6 | flag << 12; 0 = none), where flag is
+//                0 normal, 1 castle, 2 en passant, 0b1000 | piece promotion
+//   bits 16..32  score as i16
+//   bits 32..40  depth
+//   bits 40..42  bound (1 exact, 2 lower, 3 upper)
+//   bits 48..56  age
+// ---------------------------------------------------------------------------
+
+const fn pack_move(mv: Option<Move>) -> u64 {
+    let Some(mv) = mv else {
+        return 0;
+    };
+    let flag: u64 = match mv.kind() {
+        MoveKind::Normal => 0,
+        MoveKind::Castle => 1,
+        MoveKind::EnPassant => 2,
+        MoveKind::Promotion(pt) => 0b1000 | pt as u64,
+    };
+    mv.from().raw() as u64 | (mv.to().raw() as u64) << 6 | flag << 12
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn unpack_move(bits: u64) -> Option<Move> {
+    if bits == 0 {
+        return None;
+    }
+    let from = Square::from_index((bits & 0x3F) as u8);
+    let to = Square::from_index((bits >> 6 & 0x3F) as u8);
+    Some(match bits >> 12 & 0xF {
+        1 => Move::castle(from, to),
+        2 => Move::en_passant(from, to),
+        flag if flag & 0b1000 != 0 => {
+            Move::promotion(from, to, PieceType::ALL[(flag & 0b111) as usize])
+        }
+        _ => Move::normal(from, to),
+    })
+}
+
+fn pack(best_move: Option<Move>, score: i32, depth: u8, bound: Bound, age: u8) -> u64 {
+    let bound_bits: u64 = match bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    let score = i16::try_from(score).unwrap_or(if score > 0 { i16::MAX } else { -i16::MAX });
+    pack_move(best_move)
+        | u64::from(score.cast_unsigned()) << 16
+        | u64::from(depth) << 32
+        | bound_bits << 40
+        | u64::from(age) << 48
+}
+
+fn unpack(data: u64) -> TtEntry {
+    #[allow(clippy::cast_possible_truncation)]
+    TtEntry {
+        best_move: unpack_move(data & 0xFFFF),
+        score: i32::from(((data >> 16) as u16).cast_signed()),
+        depth: (data >> 32) as u8,
+        bound: match data >> 40 & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            _ => Bound::Upper,
+        },
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn unpack_age(data: u64) -> u8 {
+    (data >> 48) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::TEST_POSITIONS;
+    use crate::board::Board;
+    use crate::search::MATE;
+
+    #[test]
+    fn size_in_mb() {
+        assert_eq!(TranspositionTable::new(1).buckets.len(), 32_768);
+        assert_eq!(TranspositionTable::new(16).size_mb(), 16);
+        assert!(TranspositionTable::new(0).buckets.is_empty());
+    }
+
+    #[test]
+    fn moves_pack_round_trip() {
+        for fen in TEST_POSITIONS {
+            for mv in Board::from_fen(fen).unwrap().generate_legal_moves() {
+                assert_eq!(unpack_move(pack_move(Some(mv))), Some(mv), "{fen}: {mv}");
+            }
+        }
+        assert_eq!(unpack_move(pack_move(None)), None);
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let tt = TranspositionTable::new(1);
+        let mv = Move::from_uci("e2e4");
+        tt.store(42, 0, 7, -123, Bound::Lower, mv);
+        let e = tt.probe(42, 0).unwrap();
+        assert_eq!(e, TtEntry { best_move: mv, score: -123, depth: 7, bound: Bound::Lower });
+        assert_eq!(tt.probe(43, 0), None);
+        assert_eq!((tt.stats().probes, tt.stats().hits), (2, 1));
+    }
+
+    #[test]
+    fn mate_scores_are_ply_relative() {
+        let tt = TranspositionTable::new(1);
+        // Mate in 5 plies from a node at ply 3 is mate in 8 from the root...
+        tt.store(1, 3, 4, MATE - 8, Bound::Exact, None);
+        // ...and mate in 5 from the same node reached at ply 7.
+        assert_eq!(tt.probe(1, 7).unwrap().score, MATE - 12);
+        tt.store(2, 2, 4, -MATE + 6, Bound::Exact, None);
+        assert_eq!(tt.probe(2, 0).unwrap().score, -MATE + 4);
+        tt.store(3, 9, 4, 250, Bound::Exact, None);
+        assert_eq!(tt.probe(3, 0).unwrap().score, 250);
+    }
+
+    #[test]
+    fn depth_preferred_replacement() {
+        let tt = TranspositionTable::with_buckets(1);
+        tt.store(1, 0, 8, 10, Bound::Exact, None);
+        tt.store(2, 0, 3, 20, Bound::Exact, None);
+        tt.store(3, 0, 2, 30, Bound::Exact, None);
+        // The deep entry survives; the always-replace slot holds the newest.
+        assert_eq!(tt.probe(1, 0).unwrap().score, 10);
+        assert_eq!(tt.probe(2, 0), None);
+        assert_eq!(tt.probe(3, 0).unwrap().score, 30);
+
+        // In a new search the stale deep entry gives way.
+        tt.new_search();
+        tt.store(4, 0, 1, 40, Bound::Exact, None);
+        assert_eq!(tt.probe(1, 0), None);
+        assert_eq!(tt.probe(4, 0).unwrap().score, 40);
+    }
+
+    #[test]
+    fn store_keeps_move_when_none_given() {
+        let tt = TranspositionTable::new(1);
+        let mv = Move::from_uci("g1f3");
+        tt.store(5, 0, 2, 0, Bound::Lower, mv);
+        tt.store(5, 0, 3, -50, Bound::Upper, None);
+        assert_eq!(tt.probe(5, 0).unwrap().best_move, mv);
+    }
+
+    #[test]
+    fn hashfull_counts_current_generation() {
+        let tt = TranspositionTable::new(1);
+        assert_eq!(tt.hashfull(), 0);
+        for (i, key) in (0..200_000u64).enumerate() {
+            let depth = u8::try_from(i % 8).unwrap();
+            tt.store(key.wrapping_mul(0x9E37_79B9_7F4A_7C15), 0, depth, 0, Bound::Exact, None);
+        }
+        assert!(tt.hashfull() > 900);
+        tt.new_search();
+        assert_eq!(tt.hashfull(), 0);
+    }
+
+    #[test]
+    fn torn_entries_are_misses() {
+        let tt = TranspositionTable::with_buckets(1);
+        tt.store(7, 0, 3, 15, Bound::Exact, None);
+        // Another thread's data word lands without its key word.
+        let other = pack(None, 99, 9, Bound::Lower, 0);
+        tt.buckets[0][0].data.store(other, Ordering::Relaxed);
+        assert_eq!(tt.probe(7, 0), None);
+    }
+
+    #[test]
+    fn concurrent_stores_stay_consistent() {
+        // Every thread writes entries whose score is derived from the key, all
+        // into one bucket; a hit must never pair a key with foreign data.
+        let tt = TranspositionTable::with_buckets(1);
+        let score = |key: u64| i32::try_from(key % 1000).unwrap();
+        std::thread::scope(|scope| {
+            for t in 0..4u64 {
+                let tt = &tt;
+                scope.spawn(move || {
+                    for i in 0..20_000u64 {
+                        let key = (i * 4 + t).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                        tt.store(key, 0, 1, score(key), Bound::Exact, None);
+                        let probe = key ^ 0x5555;
+                        if let Some(e) = tt.probe(probe, 0) {
+                            assert_eq!(e.score, score(probe));
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(tt.stats().stores, 80_000);
+    }
+
+    #[test]
+    fn disabled_table() {
+        let tt = TranspositionTable::new(0);
+        tt.store(1, 0, 1, 0, Bound::Exact, None);
+        assert_eq!(tt.probe(1, 0), None);
+        assert_eq!(tt.hashfull(), 0);
+    }
+}
@@ -0,0 +1,301 @@
+//! Texel-style tuning of the evaluation weights.
+//!
+//! [`Tuner::add_positions`] reads positions labelled with the result of the
+//! game they come from, one per line: a FEN or EPD followed by the result as
+//! `1-0`, `0-1`, `1/2-1/2`, or a number (1, 0.5, 0) from White's side, bare,
+//! quoted, or bracketed as the last field, or as an EPD `c9` opcode. Lines
+//! written by [`datagen`](crate::datagen) are read as they are:
+//!
+//! ```text
+//! rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1 [0.5]
+//! 8/5k2/8/8/8/2K5/3R4/8 w - - hmvc 0; fmvn 1; c9 "1-0";
+//! 8/5k2/8/8/8/2K5/3R4/8 w - - 0 1 | 1.0 | 812 | d2d7
+//! ```
+//!
+//! Each position is resolved once with a quiescence search and the quiet
+//! position at the end of its principal variation is kept. Positions in
+//! check and those the [`endgame`](crate::endgame) registry scores (which
+//! ignores the weights) are skipped.
+//!
+//! The error of a weight set is the mean squared difference between each
+//! result and `1 / (1 + 10^(-K * eval / 400))`, where `eval` is the quiet
+//! position's evaluation from White's side and `K` is first fitted to the
+//! data ([`Tuner::fit_k`]). [`Tuner::tune`] then minimises the error by local
+//! search: each pass tries moving every midgame and endgame weight up and
+//! down by the step and keeps any change that lowers the error. The result
+//! is written with [`EvalParams::to_text`].
+
+use crate::board::Board;
+use crate::eval::EvalParams;
+use crate::search::Searcher;
+use crate::types::Color;
+
+/// A quiet position and the game result from White's side (1, 0.5, or 0).
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub board: Board,
+    pub result: f64,
+}
+
+/// Parses one labelled line: the position (not yet resolved) and result.
+#[must_use]
+pub fn parse_labelled(line: &str) -> Option<(Board, f64)> {
+    // `fen | result | score | move`, as written by the datagen tool.
+    if let Some((fen, rest)) = line.split_once('|') {
+        let result = parse_result(rest.split('|').next()?.trim())?;
+        return Some((Board::from_fen(fen.trim())?, result));
+    }
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    // Four FEN fields, then the clocks only if both are there and a result follows.
+    let clocks = if fields.len() > 6 && fields[4..6].iter().all(|f| f.parse::<u32>().is_ok()) { 2 } else { 0 };
+    let board = Board::from_fen(&fields.get(..4 + clocks)?.join(" "))?;
+    let rest = &fields[4 + clocks..];
+    // The `c9` opcode if the line has opcodes, else the last field.
+    let result = match rest.iter().position(|&field| field == "c9") {
+        Some(i) => rest.get(i + 1)?,
+        None => rest.last()?,
+    };
+    Some((board, parse_result(result)?))
+}
+
+/// Reads a result from White's side, stripped of quotes, brackets, and `;`.
+fn parse_result(field: &str) -> Option<f64> {
+    match field.trim_matches(|c| matches!(c, '"' | '[' | ']' | ';' | '(' | ')')) {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" => Some(0.5),
+        number => number.parse::<f64>().ok().filter(|r| (0.0..=1.0).contains(r)),
+    }
+}
+
+/// Collects samples and tunes [`EvalParams`] on them.
+pub struct Tuner {
+    samples: Vec<Sample>,
+    searcher: Searcher,
+    step: i32,
+    threads: usize,
+}
+
+impl Default for Tuner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tuner {
+    /// A tuner with no samples, a step of 1 centipawn, and one thread per
+    /// available core.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            samples: Vec::new(),
+            searcher: Searcher::new(),
+            step: 1,
+            threads: std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get),
+        }
+    }
+
+    /// Tries weights `step` centipawns either side of the current ones.
+    #[must_use]
+    pub fn step(mut self, step: i32) -> Self {
+        self.step = step.max(1);
+        self
+    }
+
+    /// Computes errors on `threads` threads (at least 1).
+    #[must_use]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// Adds a position with its result, resolved to a quiet one. Returns
+    /// whether it was used.
+    pub fn add(&mut self, board: &Board, result: f64) -> bool {
+        if board.is_in_check(board.side_to_move()) {
+            return false;
+        }
+        let (_, pv) = self.searcher.quiescence(board);
+        let mut quiet = board.clone();
+        for mv in pv {
+            quiet.make_move(mv);
+        }
+        if quiet.endgame_eval().is_some() || quiet.kpk_probe().is_some() {
+            return false;
+        }
+        self.samples.push(Sample { board: quiet, result });
+        true
+    }
+
+    /// Adds every labelled line of `text` (see the module docs) and returns
+    /// how many were used; malformed lines are skipped.
+    pub fn add_positions(&mut self, text: &str) -> usize {
+        text.lines().filter_map(parse_labelled).filter(|(board, result)| self.add(board, *result)).count()
+    }
+
+    /// Mean squared error of `params` with scaling constant `k`.
+    #[must_use]
+    pub fn error(&self, params: &EvalParams, k: f64) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let chunk = self.samples.len().div_ceil(self.threads);
+        let total: f64 = std::thread::scope(|scope| {
+            // Spawn every worker before joining any of them.
+            #[allow(clippy::needless_collect)]
+            let workers: Vec<_> = self
+                .samples
+                .chunks(chunk)
+                .map(|samples| scope.spawn(move || samples.iter().map(|s| sample_error(s, params, k)).sum::<f64>()))
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap_or(f64::NAN)).sum()
+        });
+        #[allow(clippy::cast_precision_loss)]
+        let n = self.samples.len() as f64;
+        total / n
+    }
+
+    /// The `K` that minimises the error of `params`, by golden-section
+    /// search over `0.1..=3`.
+    #[must_use]
+    pub fn fit_k(&self, params: &EvalParams) -> f64 {
+        let ratio = (5f64.sqrt() - 1.0) / 2.0;
+        let (mut lo, mut hi) = (0.1, 3.0);
+        for _ in 0..40 {
+            let a = hi - ratio * (hi - lo);
+            let b = lo + ratio * (hi - lo);
+            if self.error(params, a) < self.error(params, b) {
+                hi = b;
+            } else {
+                lo = a;
+            }
+        }
+        f64::midpoint(lo, hi)
+    }
+
+    /// Runs up to `passes` local-search passes from `params` with scaling
+    /// constant `k`, stopping early once a pass changes nothing.
+    /// `on_pass(pass, error, params)` is called after each pass.
+    pub fn tune(
+        &self,
+        mut params: EvalParams,
+        k: f64,
+        passes: usize,
+        mut on_pass: impl FnMut(usize, f64, &EvalParams),
+    ) -> EvalParams {
+        let mut best = self.error(&params, k);
+        let weights = params.named_mut().len();
+        for pass in 1..=passes {
+            let mut improved = false;
+            for i in 0..weights {
+                for half in [Half::Mg, Half::Eg] {
+                    for delta in [self.step, -self.step] {
+                        let mut candidate = params.clone();
+                        *half.of(candidate.named_mut()[i].1) += delta;
+                        let error = self.error(&candidate, k);
+                        if error < best {
+                            (params, best, improved) = (candidate, error, true);
+                            break;
+                        }
+                    }
+                }
+            }
+            on_pass(pass, best, &params);
+            if !improved {
+                break;
+            }
+        }
+        params
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Half {
+    Mg,
+    Eg,
+}
+
+impl Half {
+    const fn of(self, score: &mut crate::eval::Score) -> &mut i32 {
+        match self {
+            Self::Mg => &mut score.mg,
+            Self::Eg => &mut score.eg,
+        }
+    }
+}
+
+fn sample_error(sample: &Sample, params: &EvalParams, k: f64) -> f64 {
+    let eval = sample.board.evaluate_with(params);
+    let white = if sample.board.side_to_move() == Color::White { eval } else { -eval };
+    let predicted = 1.0 / (1.0 + 10f64.powf(-k * f64::from(white) / 400.0));
+    (sample.result - predicted).powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labelled_lines() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+        for (line, result) in [
+            (format!("{start} 0 1 [1.0]"), 1.0),
+            (format!("{start} 0 1 \"0-1\""), 0.0),
+            (format!("{start} c9 \"1/2-1/2\";"), 0.5),
+            (format!("{start} 0.5"), 0.5),
+            (format!("{start} 3 [0]"), 0.0),
+            (format!("{start} 1"), 1.0),
+            (format!("{start} 0"), 0.0),
+            (format!("{start} hmvc 0; fmvn 1; c9 \"1-0\";"), 1.0),
+            (format!("{start} fmvn 1; c9 \"0-1\";"), 0.0),
+            (format!("{start} 0 1 | 1.0 | 25 | e2e4"), 1.0),
+        ] {
+            let (board, parsed) = parse_labelled(&line).unwrap();
+            assert!((parsed - result).abs() < 1e-9, "{line}");
+            assert_eq!(board.side_to_move(), Color::White);
+        }
+        assert_eq!(parse_labelled(&format!("{start} 0 1 *")).map(|(_, r)| r), None);
+        assert_eq!(parse_labelled(&format!("{start} [2]")).map(|(_, r)| r), None);
+        assert!(parse_labelled("not a fen 1-0").is_none());
+    }
+
+    #[test]
+    fn positions_are_resolved_and_filtered() {
+        let mut tuner = Tuner::new().threads(2);
+        let text = "\
+            4k3/p7/8/3q4/8/8/P2R4/4K3 w - - [1.0]\n\
+            4k3/8/8/8/8/8/4q3/4K3 w - - [0.0]\n\
+            4k3/8/8/8/8/8/8/R3K3 w - - [1.0]\n\
+            garbage\n\
+            r3k3/p7/8/8/8/8/P7/4K2R b - - [0.5]\n";
+        assert_eq!(tuner.add_positions(text), 2);
+        // The hanging queen is taken before the position is kept.
+        assert_eq!(tuner.samples()[0].board.to_fen(), "4k3/p7/8/3R4/8/8/P7/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn tuning_lowers_the_error() {
+        // Positions a pawn up that White still loses: the pawn is overvalued.
+        let mut tuner = Tuner::new().step(5);
+        for fen in [
+            "4k3/pp6/8/8/8/8/PPP5/4K3 w - -",
+            "4k3/6pp/8/8/8/8/5PPP/4K3 b - -",
+            "4k3/p1p5/8/8/8/8/PPP5/4K3 w - -",
+            "4k3/pp2r3/8/8/8/8/PPP2R2/4K3 b - -",
+        ] {
+            assert!(tuner.add(&Board::from_fen(fen).unwrap(), 0.0), "{fen}");
+        }
+        let k = tuner.fit_k(&EvalParams::DEFAULT);
+        assert!((0.1..=3.0).contains(&k));
+        let before = tuner.error(&EvalParams::DEFAULT, k);
+        let mut passes = 0;
+        let params = tuner.tune(EvalParams::DEFAULT, k, 2, |_, _, _| passes += 1);
+        assert!(tuner.error(&params, k) < before);
+        assert!((1..=2).contains(&passes));
+    }
+}
@@ -92,6 +92,28 @@ impl Square {
     pub const fn bitboard(self) -> u64 {
         1u64 << self.0
     }
+
+    /// Reflects the square across the horizontal midline (e2 ↔ e7).
+    #[inline]
+    #[must_use]
+    pub const fn flipped(self) -> Self {
+        Self(self.0 ^ 0b11_1000)
+    }
+
+    /// Reflects the square across the vertical midline (a1 ↔ h1).
+    #[inline]
+    #[must_use]
+    pub const fn mirrored(self) -> Self {
+        Self(self.0 ^ 0b111)
+    }
+
+    /// King moves between the two squares (Chebyshev distance).
+    #[must_use]
+    pub const fn distance(self, other: Self) -> u8 {
+        let files = self.file().abs_diff(other.file());
+        let ranks = self.rank().abs_diff(other.rank());
+        if files > ranks { files } else { ranks }
+    }
 }
 
 impl fmt::Display for Square {
@@ -110,7 +132,7 @@ impl fmt::Display for Square {
 // ---------------------------------------------------------------------------
 
 /// One of the six chess piece types.
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 #[repr(u8)]
 pub enum PieceType {
     Pawn = 0,
@@ -277,6 +299,12 @@ impl Piece {
         };
         Some(Self { piece_type, color })
     }
+
+    /// Returns the same piece type in the opposite color.
+    #[must_use]
+    pub const fn flipped(self) -> Self {
+        Self { piece_type: self.piece_type, color: self.color.opposite() }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -406,6 +434,22 @@ impl Move {
 
         Some(Self { from, to, kind })
     }
+
+    /// The same move on a color-flipped board (see [`Square::flipped`]).
+    #[must_use]
+    pub const fn flipped(self) -> Self {
+        Self { from: self.from.flipped(), to: self.to.flipped(), kind: self.kind }
+    }
+
+    /// The same move on a file-mirrored board (see [`Square::mirrored`]).
+    ///
+    /// Castling has no mirrored counterpart, so a mirrored castle is only
+    /// meaningful as a king step; callers that mirror positions drop castling
+    /// rights and never generate one.
+    #[must_use]
+    pub const fn mirrored(self) -> Self {
+        Self { from: self.from.mirrored(), to: self.to.mirrored(), kind: self.kind }
+    }
 }
 
 impl fmt::Display for Move {
@@ -457,6 +501,13 @@ impl CastlingRights {
     #[must_use]
     pub const fn black_queenside(self) -> bool { self.0 & Self::BQ != 0 }
 
+    /// Swaps White's rights with Black's (used when flipping a position).
+    #[inline]
+    #[must_use]
+    pub const fn flipped(self) -> Self {
+        Self(((self.0 & 0b0011) << 2) | ((self.0 & 0b1100) >> 2))
+    }
+
     /// Clears a right by its bit mask.
     #[inline]
     pub const fn clear(&mut self, mask: u8) { self.0 &= !mask; }
@@ -0,0 +1,594 @@
+//! Universal Chess Interface front end for the native `chess-engine` binary.
+//!
+//! [`Uci`] reads one command per line and writes responses to any
+//! [`Write`] sink. Searches run on a worker thread so that `stop`,
+//! `isready`, and `quit` are answered while the engine thinks; commands that
+//! change the position or options first stop a running search.
+//!
+//! Supported commands: `uci`, `isready`, `ucinewgame`,
+//! `position startpos|fen <fen> [moves ...]`, `go` (`depth`, `nodes`,
+//! `movetime`, `wtime`, `btime`, `winc`, `binc`, `movestogo`, `infinite`),
+//! `stop`, `setoption`, and `quit`. Unknown commands are reported with
+//! `info string` and otherwise ignored.
+//!
+//! Options: `Hash`, `Clear Hash`, `MultiPV`, `Threads` (Lazy SMP),
+//! `Skill Level`, `UCI_LimitStrength` with `UCI_Elo` (which overrides
+//! `Skill Level` while set; see [`crate::skill`]), `EvalParams` (a text or
+//! JSON weights file, see [`EvalParams::load`]), with the `nnue` feature
+//! `EvalFile`, and with the `syzygy` feature `SyzygyPath`.
+
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::board::Board;
+use crate::eval::EvalParams;
+use crate::search::{mate_in, SearchLimits, SearchResult, Searcher, DEFAULT_HASH_MB};
+use crate::skill::{Skill, MAX_ELO, MAX_LEVEL, MIN_ELO};
+use crate::timeman::TimeControl;
+use crate::types::{Color, Move};
+
+/// Largest `Hash` option value, in mebibytes.
+const MAX_HASH_MB: usize = 4096;
+
+/// Largest `Threads` option value.
+pub(crate) const MAX_THREADS: usize = 256;
+
+/// Default `UCI_Elo` option value.
+const DEFAULT_ELO: u32 = 1500;
+
+/// Largest `MultiPV` option value.
+const MAX_MULTI_PV: usize = 256;
+
+/// A UCI session writing its responses to `W`.
+pub struct Uci<W> {
+    board: Board,
+    /// Polyglot keys of the positions before `board`, oldest first.
+    history: Vec<u64>,
+    /// The searcher while idle; the worker owns it during a search.
+    searcher: Option<Searcher>,
+    worker: Option<JoinHandle<Searcher>>,
+    stop: Arc<AtomicBool>,
+    out: Arc<Mutex<W>>,
+    /// Lines to report per iteration (`MultiPV` option).
+    multi_pv: usize,
+    /// `Skill Level` option.
+    skill_level: u8,
+    /// `UCI_LimitStrength` option.
+    limit_strength: bool,
+    /// `UCI_Elo` option.
+    elo: u32,
+}
+
+/// Writes one line and flushes, ignoring closed pipes.
+pub(crate) fn send<W: Write>(out: &Mutex<W>, line: &str) {
+    let mut out = out.lock().unwrap_or_else(PoisonError::into_inner);
+    let _ = writeln!(out, "{line}");
+    let _ = out.flush();
+}
+
+/// Stops a search thread through its searcher's `stop` flag and joins it.
+pub(crate) fn stop_worker<T>(worker: JoinHandle<T>, stop: &AtomicBool) -> T {
+    // The searcher clears the flag when it starts, so keep raising it until
+    // the worker is done in case it had not started yet.
+    while !worker.is_finished() {
+        stop.store(true, Ordering::Relaxed);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    worker.join().expect("search thread panicked")
+}
+
+/// Formats line `index` of a completed iteration as an `info` line.
+fn info_line(r: &SearchResult, index: usize) -> String {
+    let line = &r.lines[index];
+    let score = mate_in(line.score).map_or_else(|| format!("cp {}", line.score), |m| format!("mate {m}"));
+    let ms = r.time.as_millis();
+    let nps = (u128::from(r.nodes) * 1000).checked_div(ms).unwrap_or(0);
+    let pv: Vec<String> = line.pv.iter().map(Move::to_uci).collect();
+    format!(
+        "info depth {} seldepth {} multipv {} score {score} nodes {} nps {nps} hashfull {} time {ms} pv {}",
+        line.depth,
+        r.seldepth,
+        index + 1,
+        r.nodes,
+        r.tt.hashfull,
+        pv.join(" ")
+    )
+}
+
+/// Formats the final `bestmove` line (`0000` when there is no legal move).
+fn bestmove_line(r: &SearchResult) -> String {
+    let best = r.best_move.map_or_else(|| "0000".to_string(), |m| m.to_uci());
+    r.pv.get(1).map_or_else(
+        || format!("bestmove {best}"),
+        |ponder| format!("bestmove {best} ponder {}", ponder.to_uci()),
+    )
+}
+
+/// The `go` parameters, in milliseconds where applicable.
+#[derive(Default)]
+pub(crate) struct GoParams {
+    pub(crate) depth: Option<u8>,
+    pub(crate) nodes: Option<u64>,
+    pub(crate) movetime: Option<u64>,
+    pub(crate) time: [Option<u64>; 2],
+    pub(crate) inc: [u64; 2],
+    pub(crate) movestogo: Option<u32>,
+    pub(crate) infinite: bool,
+}
+
+impl GoParams {
+    fn parse<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Self {
+        let mut p = Self::default();
+        while let Some(token) = tokens.next() {
+            let mut value = || tokens.next().and_then(|v| v.parse::<i64>().ok()).map(|v| v.max(0).cast_unsigned());
+            match token {
+                "depth" => p.depth = value().map(|d| u8::try_from(d).unwrap_or(u8::MAX)),
+                "nodes" => p.nodes = value(),
+                "movetime" => p.movetime = value(),
+                "wtime" => p.time[Color::White.index()] = value(),
+                "btime" => p.time[Color::Black.index()] = value(),
+                "winc" | "inc" => p.inc[Color::White.index()] = value().unwrap_or(0),
+                "binc" => p.inc[Color::Black.index()] = value().unwrap_or(0),
+                "movestogo" => p.movestogo = value().map(|m| u32::try_from(m).unwrap_or(u32::MAX)),
+                "infinite" => p.infinite = true,
+                _ => {}
+            }
+        }
+        p
+    }
+
+    /// Search limits for `us`: `movetime` if given, otherwise the
+    /// [time manager's](crate::timeman) budget for `us`'s clock. Without a
+    /// clock, depth, node, or movetime limit the search is infinite.
+    pub(crate) fn limits(&self, us: Color) -> SearchLimits {
+        let mut limits = match (self.movetime, self.time[us.index()]) {
+            (Some(ms), _) => SearchLimits::time(Duration::from_millis(ms)),
+            (None, Some(remaining)) => SearchLimits::clock(TimeControl::new(
+                Duration::from_millis(remaining),
+                Duration::from_millis(self.inc[us.index()]),
+                self.movestogo,
+            )),
+            (None, None) => SearchLimits::default(),
+        };
+        limits.depth = self.depth;
+        limits.nodes = self.nodes;
+        limits
+    }
+
+    const fn is_infinite(&self, limits: &SearchLimits) -> bool {
+        self.infinite || (limits.depth.is_none() && limits.nodes.is_none() && limits.time.is_none())
+    }
+}
+
+impl<W: Write + Send + 'static> Uci<W> {
+    /// A session at the starting position.
+    pub fn new(out: W) -> Self {
+        let searcher = Searcher::new();
+        Self {
+            board: Board::new(),
+            history: Vec::new(),
+            stop: searcher.stop_handle(),
+            searcher: Some(searcher),
+            worker: None,
+            out: Arc::new(Mutex::new(out)),
+            multi_pv: 1,
+            skill_level: MAX_LEVEL,
+            limit_strength: false,
+            elo: DEFAULT_ELO,
+        }
+    }
+
+    /// Handles commands from `input` until `quit` or end of input, then
+    /// waits for any running search to finish.
+    pub fn run(&mut self, input: impl BufRead) {
+        for line in input.lines() {
+            let Ok(line) = line else { break };
+            if !self.handle(&line) {
+                break;
+            }
+        }
+        self.stop_search();
+    }
+
+    /// Handles one command line. Returns `false` after `quit`.
+    pub fn handle(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        let Some(command) = tokens.next() else {
+            return true;
+        };
+        match command {
+            "uci" => self.identify(),
+            "isready" => send(&self.out, "readyok"),
+            "ucinewgame" => {
+                self.searcher().clear();
+                self.set_position(Board::new(), &[]);
+            }
+            "position" => self.position(tokens),
+            "go" => self.go(tokens),
+            "stop" => self.stop_search(),
+            "setoption" => self.set_option(line),
+            "quit" => {
+                self.stop_search();
+                return false;
+            }
+            "debug" | "ponderhit" | "register" => {}
+            _ => send(&self.out, &format!("info string unknown command {command}")),
+        }
+        true
+    }
+
+    fn identify(&self) {
+        send(&self.out, concat!("id name chess-engine ", env!("CARGO_PKG_VERSION")));
+        send(&self.out, "id author the chess-engine developers");
+        send(
+            &self.out,
+            &format!("option name Hash type spin default {DEFAULT_HASH_MB} min 1 max {MAX_HASH_MB}"),
+        );
+        send(&self.out, "option name Clear Hash type button");
+        send(&self.out, &format!("option name MultiPV type spin default 1 min 1 max {MAX_MULTI_PV}"));
+        send(&self.out, &format!("option name Threads type spin default 1 min 1 max {MAX_THREADS}"));
+        send(&self.out, &format!("option name Skill Level type spin default {MAX_LEVEL} min 0 max {MAX_LEVEL}"));
+        send(&self.out, "option name UCI_LimitStrength type check default false");
+        send(&self.out, &format!("option name UCI_Elo type spin default {DEFAULT_ELO} min {MIN_ELO} max {MAX_ELO}"));
+        send(&self.out, "option name EvalParams type string default <empty>");
+        #[cfg(feature = "nnue")]
+        send(&self.out, "option name EvalFile type string default <empty>");
+        #[cfg(feature = "syzygy")]
+        send(&self.out, "option name SyzygyPath type string default <empty>");
+        send(&self.out, "uciok");
+    }
+
+    /// The idle searcher, stopping a running search first.
+    fn searcher(&mut self) -> &mut Searcher {
+        self.stop_search();
+        self.searcher.as_mut().expect("searcher is idle after stop_search")
+    }
+
+    /// Stops the running search, if any, and waits for its `bestmove`.
+    fn stop_search(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            self.searcher = Some(stop_worker(worker, &self.stop));
+        }
+    }
+
+    fn set_position(&mut self, board: Board, moves: &[&str]) {
+        self.stop_search();
+        self.board = board;
+        self.history.clear();
+        for uci in moves {
+            let key = self.board.polyglot_key();
+            if !Move::from_uci(uci).is_some_and(|mv| self.board.make_move(mv)) {
+                send(&self.out, &format!("info string illegal move {uci}"));
+                return;
+            }
+            self.history.push(key);
+        }
+    }
+
+    fn position<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) {
+        let board = match tokens.next() {
+            Some("startpos") => Some(Board::new()),
+            Some("fen") => {
+                let fen: Vec<&str> = tokens.by_ref().take_while(|&t| t != "moves").collect();
+                Board::from_fen(&fen.join(" "))
+            }
+            _ => None,
+        };
+        let Some(board) = board else {
+            send(&self.out, "info string invalid position");
+            return;
+        };
+        let moves: Vec<&str> = tokens.skip_while(|&t| t == "moves").collect();
+        self.set_position(board, &moves);
+    }
+
+    fn go<'a>(&mut self, tokens: impl Iterator<Item = &'a str>) {
+        self.stop_search();
+        let params = GoParams::parse(tokens);
+        let limits = params.limits(self.board.side_to_move());
+        let infinite = params.is_infinite(&limits);
+
+        let mut searcher = self.searcher.take().expect("searcher is idle");
+        searcher.set_game_history(self.history.clone());
+        let board = self.board.clone();
+        let out = Arc::clone(&self.out);
+        let stop = Arc::clone(&self.stop);
+        let multi_pv = self.multi_pv;
+        self.worker = Some(std::thread::spawn(move || {
+            let result = searcher.search_multi_pv(&board, limits, multi_pv, |r| {
+                for index in 0..r.lines.len() {
+                    send(&out, &info_line(r, index));
+                }
+            });
+            // UCI forbids `bestmove` before `stop` in infinite mode.
+            while infinite && !stop.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            send(&out, &bestmove_line(&result));
+            searcher
+        }));
+    }
+
+    fn set_option(&mut self, line: &str) {
+        // setoption name <id> [value <x>]; ids may contain spaces.
+        let rest = line.trim().strip_prefix("setoption").unwrap_or_default().trim();
+        let rest = rest.strip_prefix("name").unwrap_or(rest).trim();
+        let (name, value) = rest
+            .split_once(" value")
+            .map_or((rest, ""), |(name, value)| (name.trim(), value.trim()));
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(mb) => self.searcher().set_hash_size(mb.clamp(1, MAX_HASH_MB)),
+                Err(_) => send(&self.out, &format!("info string invalid Hash value {value}")),
+            },
+            "clear hash" => self.searcher().clear(),
+            "multipv" => match value.parse::<usize>() {
+                Ok(n) => self.multi_pv = n.clamp(1, MAX_MULTI_PV),
+                Err(_) => send(&self.out, &format!("info string invalid MultiPV value {value}")),
+            },
+            "threads" => match value.parse::<usize>() {
+                Ok(n) => self.searcher().set_threads(n.clamp(1, MAX_THREADS)),
+                Err(_) => send(&self.out, &format!("info string invalid Threads value {value}")),
+            },
+            "skill level" => match value.parse::<u8>() {
+                Ok(level) => {
+                    self.skill_level = level.min(MAX_LEVEL);
+                    self.apply_skill();
+                }
+                Err(_) => send(&self.out, &format!("info string invalid Skill Level value {value}")),
+            },
+            "uci_limitstrength" => match value.parse::<bool>() {
+                Ok(limit) => {
+                    self.limit_strength = limit;
+                    self.apply_skill();
+                }
+                Err(_) => send(&self.out, &format!("info string invalid UCI_LimitStrength value {value}")),
+            },
+            "uci_elo" => match value.parse::<u32>() {
+                Ok(elo) => {
+                    self.elo = elo.clamp(MIN_ELO, MAX_ELO);
+                    self.apply_skill();
+                }
+                Err(_) => send(&self.out, &format!("info string invalid UCI_Elo value {value}")),
+            },
+            "evalparams" => self.load_eval_params(value),
+            #[cfg(feature = "nnue")]
+            "evalfile" => self.load_network(value),
+            #[cfg(feature = "syzygy")]
+            "syzygypath" => self.load_syzygy(value),
+            _ => send(&self.out, &format!("info string unknown option {name}")),
+        }
+    }
+
+    /// Hands the strength from the skill options to the searcher.
+    fn apply_skill(&mut self) {
+        let skill = if self.limit_strength { Skill::from_elo(self.elo) } else { Skill::new(self.skill_level) };
+        self.searcher().set_skill(skill);
+    }
+
+    fn load_eval_params(&mut self, path: &str) {
+        if path.is_empty() || path == "<empty>" {
+            self.searcher().set_eval_params(Arc::new(EvalParams::DEFAULT));
+            return;
+        }
+        match EvalParams::load(path) {
+            Ok(params) => {
+                self.searcher().set_eval_params(Arc::new(params));
+                send(&self.out, &format!("info string loaded evaluation parameters {path}"));
+            }
+            Err(e) => send(&self.out, &format!("info string cannot load {path}: {e}")),
+        }
+    }
+
+    #[cfg(feature = "nnue")]
+    fn load_network(&mut self, path: &str) {
+        if path.is_empty() || path == "<empty>" {
+            self.searcher().set_network(None);
+            return;
+        }
+        match crate::nnue::Network::load(path) {
+            Ok(net) => {
+                self.searcher().set_network(Some(Arc::new(net)));
+                send(&self.out, &format!("info string loaded network {path}"));
+            }
+            Err(e) => send(&self.out, &format!("info string cannot load {path}: {e}")),
+        }
+    }
+
+    #[cfg(feature = "syzygy")]
+    fn load_syzygy(&mut self, path: &str) {
+        if path.is_empty() || path == "<empty>" {
+            self.searcher().set_syzygy(None);
+            return;
+        }
+        match crate::syzygy::Syzygy::open(path) {
+            Ok(tb) => {
+                send(&self.out, &format!("info string found {} tablebases up to {} pieces", tb.len(), tb.max_pieces()));
+                self.searcher().set_syzygy(Some(Arc::new(tb)));
+            }
+            Err(e) => send(&self.out, &format!("info string cannot load {path}: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A cloneable sink whose contents the test can read back.
+    #[derive(Clone, Default)]
+    pub struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Output {
+        pub fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    fn session() -> (Uci<Output>, Output) {
+        let out = Output::default();
+        (Uci::new(out.clone()), out)
+    }
+
+    fn run(commands: &str) -> Vec<String> {
+        let (mut uci, out) = session();
+        uci.run(commands.as_bytes());
+        out.lines()
+    }
+
+    #[test]
+    fn handshake() {
+        let lines = run("uci\nisready\nquit\n");
+        assert!(lines[0].starts_with("id name chess-engine"));
+        assert!(lines.iter().any(|l| l.starts_with("option name Hash type spin")));
+        assert_eq!(lines[lines.len() - 2], "uciok");
+        assert_eq!(lines[lines.len() - 1], "readyok");
+    }
+
+    #[test]
+    fn position_with_moves() {
+        let (mut uci, _) = session();
+        uci.handle("position startpos moves e2e4 e7e5 g1f3");
+        assert_eq!(uci.board.to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+        assert_eq!(uci.history.len(), 3);
+
+        uci.handle("position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves e1g1");
+        assert_eq!(uci.board.to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+    }
+
+    #[test]
+    fn illegal_move_is_reported() {
+        let (mut uci, out) = session();
+        uci.handle("position startpos moves e2e5");
+        assert_eq!(uci.board.to_fen(), Board::new().to_fen());
+        assert_eq!(out.lines(), ["info string illegal move e2e5"]);
+    }
+
+    #[test]
+    fn go_depth_reports_info_and_bestmove() {
+        let lines = run("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 3\n");
+        assert!(lines.iter().any(|l| l.starts_with("info depth 1 ") && l.contains(" pv ")));
+        assert!(lines.iter().any(|l| l.contains("score mate 1")));
+        assert_eq!(lines.last().unwrap(), "bestmove a1a8");
+    }
+
+    #[test]
+    fn go_without_moves() {
+        let lines = run("position fen 7k/5QQ1/8/8/8/8/8/K7 b - - 0 1\ngo depth 2\n");
+        assert_eq!(lines.last().unwrap(), "bestmove 0000");
+    }
+
+    #[test]
+    fn infinite_waits_for_stop() {
+        let (mut uci, out) = session();
+        uci.handle("position startpos");
+        uci.handle("go infinite");
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!out.lines().iter().any(|l| l.starts_with("bestmove")));
+        uci.handle("isready");
+        assert!(out.lines().iter().any(|l| l == "readyok"));
+        uci.handle("stop");
+        let lines = out.lines();
+        let best = lines.last().unwrap();
+        let uci_move = best.split_whitespace().nth(1).unwrap();
+        assert!(Board::new().generate_legal_moves().iter().any(|m| m.to_uci() == uci_move), "{best}");
+    }
+
+    #[test]
+    fn stop_right_after_go() {
+        let (mut uci, out) = session();
+        uci.handle("go infinite");
+        uci.handle("stop");
+        assert_eq!(out.lines().iter().filter(|l| l.starts_with("bestmove")).count(), 1);
+    }
+
+    #[test]
+    fn clock_limits() {
+        let p = GoParams::parse("wtime 60000 btime 1000 winc 1000 binc 0".split(' '));
+        let white = p.limits(Color::White);
+        assert_eq!((white.soft_time, white.time), (Some(Duration::from_millis(2750)), Some(Duration::from_secs(11))));
+        assert_eq!(p.limits(Color::Black).soft_time, Some(Duration::from_millis(33)));
+        let p = GoParams::parse("wtime 100 btime 100 movestogo 1".split(' '));
+        assert_eq!(p.limits(Color::White).time, Some(Duration::from_millis(70)));
+        let p = GoParams::parse("movetime 500 depth 4 wtime 1000".split(' '));
+        assert_eq!(p.limits(Color::White).time, Some(Duration::from_millis(500)));
+        assert_eq!(p.limits(Color::White).soft_time, None);
+        assert_eq!(p.limits(Color::White).depth, Some(4));
+        assert!(!p.is_infinite(&p.limits(Color::White)));
+        let p = GoParams::parse(std::iter::empty());
+        assert!(p.is_infinite(&p.limits(Color::White)));
+    }
+
+    #[test]
+    fn set_options() {
+        let (mut uci, out) = session();
+        uci.handle("setoption name Hash value 1");
+        assert_eq!(uci.searcher().tt().size_mb(), 1);
+        uci.handle("setoption name Clear Hash");
+        uci.handle("setoption name Threads value 3");
+        assert_eq!(uci.searcher().threads(), 3);
+        uci.handle("setoption name Skill Level value 5");
+        assert_eq!(uci.searcher().skill(), Skill::new(5));
+        uci.handle("setoption name UCI_Elo value 2000");
+        assert_eq!(uci.searcher().skill(), Skill::new(5));
+        uci.handle("setoption name UCI_LimitStrength value true");
+        assert_eq!(uci.searcher().skill(), Skill::from_elo(2000));
+        uci.handle("setoption name UCI_LimitStrength value false");
+        assert_eq!(uci.searcher().skill(), Skill::new(5));
+        uci.handle("setoption name Nonsense value 3");
+        assert_eq!(out.lines(), ["info string unknown option Nonsense"]);
+    }
+
+    #[test]
+    fn eval_params_option() {
+        let path = std::env::temp_dir().join(format!("chess-engine-params-{}.json", std::process::id()));
+        std::fs::write(&path, "{\"bishop_pair\": [7, 8]}").unwrap();
+        let (mut uci, out) = session();
+        uci.handle(&format!("setoption name EvalParams value {}", path.display()));
+        assert_eq!(uci.searcher().eval_params().bishop_pair, crate::eval::s(7, 8));
+        std::fs::write(&path, "bishop_pair 7").unwrap();
+        uci.handle(&format!("setoption name EvalParams value {}", path.display()));
+        assert_eq!(uci.searcher().eval_params().bishop_pair, crate::eval::s(7, 8));
+        uci.handle("setoption name EvalParams value <empty>");
+        assert_eq!(uci.searcher().eval_params(), &EvalParams::DEFAULT);
+        std::fs::remove_file(&path).unwrap();
+        let lines = out.lines();
+        assert!(lines[0].starts_with("info string loaded evaluation parameters "), "{lines:?}");
+        assert!(lines[1].ends_with("invalid evaluation parameters"), "{lines:?}");
+    }
+
+    #[test]
+    fn multi_pv_reports_ranked_lines() {
+        let lines = run("setoption name MultiPV value 3\nposition startpos\ngo depth 2\n");
+        let depth2: Vec<&String> = lines.iter().filter(|l| l.starts_with("info depth 2 ")).collect();
+        assert_eq!(depth2.len(), 3);
+        for (i, line) in depth2.iter().enumerate() {
+            assert!(line.contains(&format!(" multipv {} ", i + 1)), "{line}");
+        }
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn quit_stops_the_session() {
+        let (mut uci, out) = session();
+        assert!(uci.handle("go infinite"));
+        assert!(!uci.handle("quit"));
+        assert!(out.lines().last().unwrap().starts_with("bestmove"));
+    }
+}
@@ -19,8 +19,14 @@ use bindings::exports::chess::engine::engine::{
 use bindings::chess::types::types as wit_types;
 
 use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::Duration;
 
 use crate::board::Board;
+use crate::eval::EvalParams;
+use crate::search::{mate_in, SearchLimits, SearchResult, Searcher};
+use crate::skill::Skill;
+use crate::timeman::TimeControl;
 use crate::types::{
     CastlingRights, Color, GameState, Move, Piece, PieceType, Square,
 };
@@ -38,25 +44,36 @@ pub struct GameResource {
 struct GameInner {
     board: Board,
     history: Vec<(String, String)>, // (uci_move, resulting_fen)
+    keys: Vec<u64>,                 // polyglot keys of the positions before each move
+    searcher: Option<Searcher>,     // created on first search: the hash table is large
+    skill: Skill,
+    params: Arc<EvalParams>,
+}
+
+impl GameInner {
+    fn new(board: Board) -> Self {
+        Self {
+            board,
+            history: Vec::new(),
+            keys: Vec::new(),
+            searcher: None,
+            skill: Skill::default(),
+            params: Arc::new(EvalParams::DEFAULT),
+        }
+    }
 }
 
 impl GuestGame for GameResource {
     fn new() -> Self {
         Self {
-            inner: RefCell::new(GameInner {
-                board: Board::new(),
-                history: Vec::new(),
-            }),
+            inner: RefCell::new(GameInner::new(Board::new())),
         }
     }
 
     fn from_fen(fen: String) -> Result<wit::Game, wit_types::EngineError> {
         match Board::from_fen(&fen) {
             Some(board) => Ok(wit::Game::new(Self {
-                inner: RefCell::new(GameInner {
-                    board,
-                    history: Vec::new(),
-                }),
+                inner: RefCell::new(GameInner::new(board)),
             })),
             None => Err(wit_types::EngineError::InvalidFen),
         }
@@ -105,7 +122,9 @@ impl GuestGame for GameResource {
             return Err(wit_types::EngineError::IllegalMove);
         };
 
+        let key = inner.board.polyglot_key();
         if inner.board.make_move(mv) {
+            inner.keys.push(key);
             let fen = inner.board.to_fen();
             inner.history.push((uci, fen));
             Ok(())
@@ -147,10 +166,81 @@ impl GuestGame for GameResource {
         history_to_wit(&self.inner.borrow().history)
     }
 
+    fn best_move(&self, max_depth: u8, max_time_ms: u32) -> Option<String> {
+        self.search(1, fixed_limits(max_depth, max_time_ms)).best_move.map(|m| m.to_uci())
+    }
+
+    fn best_move_on_clock(&self, clock: wit::Clock) -> Option<String> {
+        let clock = TimeControl::new(
+            Duration::from_millis(u64::from(clock.remaining_ms)),
+            Duration::from_millis(u64::from(clock.increment_ms)),
+            clock.moves_to_go,
+        );
+        self.search(1, SearchLimits::clock(clock)).best_move.map(|m| m.to_uci())
+    }
+
+    fn analyze(&self, lines: u8, max_depth: u8, max_time_ms: u32) -> Vec<wit::AnalysisLine> {
+        let result = self.search(usize::from(lines), fixed_limits(max_depth, max_time_ms));
+        if result.best_move.is_none() {
+            return Vec::new();
+        }
+        result
+            .lines
+            .into_iter()
+            .map(|line| wit::AnalysisLine {
+                uci_move: line.pv[0].to_uci(),
+                score_cp: line.score,
+                mate_in: mate_in(line.score),
+                depth: line.depth,
+                pv: line.pv.iter().map(Move::to_uci).collect(),
+            })
+            .collect()
+    }
+
+    fn set_skill_level(&self, level: u8) {
+        self.inner.borrow_mut().skill = Skill::new(level);
+    }
+
+    fn set_eval_params(&self, params: String) -> bool {
+        let parsed = if params.trim().is_empty() { Some(EvalParams::DEFAULT) } else { EvalParams::parse(&params) };
+        let Some(parsed) = parsed else {
+            return false;
+        };
+        self.inner.borrow_mut().params = Arc::new(parsed);
+        true
+    }
+
     fn reset(&self) {
         let mut inner = self.inner.borrow_mut();
         inner.board = Board::new();
         inner.history.clear();
+        inner.keys.clear();
+        if let Some(searcher) = &mut inner.searcher {
+            searcher.clear();
+        }
+    }
+}
+
+impl GameResource {
+    /// Searches the current position for the best `lines` moves, with the
+    /// game's positions so far counting as repetitions.
+    fn search(&self, lines: usize, limits: SearchLimits) -> SearchResult {
+        let mut inner = self.inner.borrow_mut();
+        let inner = &mut *inner;
+        let searcher = inner.searcher.get_or_insert_with(Searcher::new);
+        searcher.set_skill(inner.skill);
+        searcher.set_eval_params(Arc::clone(&inner.params));
+        searcher.set_game_history(inner.keys.clone());
+        searcher.search_multi_pv(&inner.board, limits, lines, |_| {})
+    }
+}
+
+/// Limits for `best-move` and `analyze`: a depth and an optional time (0 = none).
+fn fixed_limits(max_depth: u8, max_time_ms: u32) -> SearchLimits {
+    SearchLimits {
+        depth: Some(max_depth),
+        time: (max_time_ms > 0).then(|| Duration::from_millis(u64::from(max_time_ms))),
+        ..SearchLimits::default()
     }
 }
 
@@ -0,0 +1,507 @@
+//! Chess Engine Communication Protocol (`XBoard`/CECP version 2) front end
+//! for the native `chess-engine` binary, selected when the first command is
+//! `xboard`.
+//!
+//! [`Xboard`] shares the worker-thread design of [`crate::uci::Uci`]: the
+//! engine's move is printed by the search thread as soon as it is found and
+//! applied to the session's board when the next command arrives. Commands
+//! that abandon the game (`new`, `force`, `setboard`, `undo`, `remove`,
+//! `result`, `quit`) cancel a running search without moving; `?` makes the
+//! engine move at once.
+//!
+//! Supported commands: `protover`, `new`, `setboard`, `usermove` (and bare
+//! moves), `go`, `playother`, `force`, `undo`, `remove`, `level`, `st`, `sd`,
+//! `time`, `otim`, `post`, `nopost`, `ping`, `cores`, `?`, `result`, and
+//! `quit`. The engine reports the result when a move ends the game.
+
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+
+use crate::board::Board;
+use crate::search::{mate_in, SearchResult, Searcher};
+use crate::types::{Color, GameState, Move};
+use crate::uci::MAX_THREADS;
+use crate::uci::{send, stop_worker, GoParams};
+
+/// Thinking time per move when the GUI sets no time control at all.
+const DEFAULT_MOVE_TIME_MS: u64 = 5000;
+
+/// Thinking output scores mates as `±(MATE_SCORE + moves)`.
+const MATE_SCORE: i32 = 100_000;
+
+/// How to end a running search.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Finish {
+    /// Wait for the engine's move.
+    Wait,
+    /// Stop thinking and play the best move so far (`?`).
+    MoveNow,
+    /// Stop thinking without moving.
+    Cancel,
+}
+
+/// A CECP session writing its responses to `W`.
+pub struct Xboard<W> {
+    board: Board,
+    /// Positions before `board`, oldest first, for `undo` and repetitions.
+    previous: Vec<Board>,
+    /// The side the engine plays; `None` in force mode.
+    engine: Option<Color>,
+    post: bool,
+    /// `sd` depth limit.
+    depth: Option<u8>,
+    /// `st` time per move, in milliseconds.
+    move_time: Option<u64>,
+    /// `level` moves per time control (0 for the whole game).
+    moves_per_control: u32,
+    /// `level` increment, in milliseconds.
+    increment: u64,
+    /// The engine's clock from `time` (or the `level` base time until the
+    /// first `time`), in milliseconds.
+    clock: Option<u64>,
+    searcher: Option<Searcher>,
+    worker: Option<JoinHandle<(Searcher, Option<Move>)>>,
+    stop: Arc<AtomicBool>,
+    cancel: Arc<AtomicBool>,
+    out: Arc<Mutex<W>>,
+}
+
+/// The result to announce if the game is over in `board`, whose earlier
+/// positions have the polyglot keys `previous`.
+fn game_result(board: &Board, previous: &[u64]) -> Option<&'static str> {
+    match board.game_state() {
+        GameState::Checkmate if board.side_to_move() == Color::White => Some("0-1 {Black mates}"),
+        GameState::Checkmate => Some("1-0 {White mates}"),
+        GameState::Stalemate => Some("1/2-1/2 {Stalemate}"),
+        GameState::Draw if board.halfmove_clock() >= 100 => Some("1/2-1/2 {Draw by fifty move rule}"),
+        GameState::Draw => Some("1/2-1/2 {Insufficient material}"),
+        GameState::InProgress => {
+            let key = board.polyglot_key();
+            let seen = previous.iter().filter(|&&k| k == key).count();
+            (seen >= 2).then_some("1/2-1/2 {Draw by repetition}")
+        }
+    }
+}
+
+/// Formats a completed iteration as a thinking line: ply, score, time in
+/// centiseconds, nodes, and principal variation.
+fn thinking_line(r: &SearchResult) -> String {
+    let score = mate_in(r.score).map_or(r.score, |m| if m > 0 { MATE_SCORE + m } else { -MATE_SCORE + m });
+    let pv: Vec<String> = r.pv.iter().map(Move::to_uci).collect();
+    format!("{} {score} {} {} {}", r.depth, r.time.as_millis() / 10, r.nodes, pv.join(" "))
+}
+
+/// Parses a `level` base time, `minutes` or `minutes:seconds`, into milliseconds.
+fn parse_base(base: &str) -> Option<u64> {
+    let (minutes, seconds) = base.split_once(':').unwrap_or((base, "0"));
+    Some((minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?) * 1000)
+}
+
+/// Parses a non-negative number of seconds, possibly fractional, into milliseconds.
+fn parse_seconds(s: &str) -> Option<u64> {
+    let seconds = s.parse::<f64>().ok().filter(|v| v.is_finite() && *v >= 0.0)?;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Some((seconds * 1000.0).round() as u64)
+}
+
+impl<W: Write + Send + 'static> Xboard<W> {
+    /// A session at the starting position with the engine playing Black.
+    pub fn new(out: W) -> Self {
+        let searcher = Searcher::new();
+        Self {
+            board: Board::new(),
+            previous: Vec::new(),
+            engine: Some(Color::Black),
+            post: false,
+            depth: None,
+            move_time: None,
+            moves_per_control: 0,
+            increment: 0,
+            clock: None,
+            stop: searcher.stop_handle(),
+            searcher: Some(searcher),
+            worker: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            out: Arc::new(Mutex::new(out)),
+        }
+    }
+
+    /// Handles commands from `input` until `quit` or end of input.
+    pub fn run(&mut self, input: impl BufRead) {
+        for line in input.lines() {
+            let Ok(line) = line else { break };
+            if !self.handle(&line) {
+                break;
+            }
+        }
+        self.finish_search(Finish::Cancel);
+    }
+
+    /// Handles one command line. Returns `false` after `quit`.
+    pub fn handle(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        let args = args.trim();
+        match command {
+            "" | "xboard" | "accepted" | "rejected" | "random" | "easy" | "hard" | "computer"
+            | "name" | "rating" | "ics" | "otim" | "draw" | "white" | "black" => {}
+            "protover" => self.features(),
+            "ping" => send(&self.out, &format!("pong {args}")),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "?" => self.finish_search(Finish::MoveNow),
+            "quit" => {
+                self.finish_search(Finish::Cancel);
+                return false;
+            }
+            "new" => self.new_game(),
+            "setboard" => self.set_board(args),
+            "force" | "result" => {
+                self.finish_search(Finish::Cancel);
+                self.engine = None;
+            }
+            "undo" => self.take_back(1),
+            "remove" => self.take_back(2),
+            "usermove" => self.user_move(args),
+            "go" => {
+                self.finish_search(Finish::Wait);
+                self.engine = Some(self.board.side_to_move());
+                self.think();
+            }
+            "playother" => {
+                self.finish_search(Finish::Wait);
+                self.engine = Some(self.board.side_to_move().opposite());
+            }
+            "level" | "st" | "sd" | "time" => {
+                self.finish_search(Finish::Wait);
+                if self.time_control(command, args).is_none() {
+                    send(&self.out, &format!("Error (bad argument): {line}"));
+                }
+            }
+            "cores" => {
+                self.finish_search(Finish::Wait);
+                match (args.parse::<usize>(), &mut self.searcher) {
+                    (Ok(n), Some(searcher)) => searcher.set_threads(n.clamp(1, MAX_THREADS)),
+                    _ => send(&self.out, &format!("Error (bad argument): {line}")),
+                }
+            }
+            _ if Move::from_uci(command).is_some() => self.user_move(command),
+            _ => send(&self.out, &format!("Error (unknown command): {command}")),
+        }
+        true
+    }
+
+    fn features(&self) {
+        send(
+            &self.out,
+            concat!(
+                "feature ping=1 setboard=1 playother=1 san=0 usermove=1 time=1 draw=0 ",
+                "sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 smp=1 ",
+                "myname=\"chess-engine ",
+                env!("CARGO_PKG_VERSION"),
+                "\" done=1"
+            ),
+        );
+    }
+
+    fn time_control(&mut self, command: &str, args: &str) -> Option<()> {
+        match command {
+            "level" => {
+                let mut fields = args.split_whitespace();
+                let mps = fields.next()?.parse().ok()?;
+                let base = parse_base(fields.next()?)?;
+                let inc = parse_seconds(fields.next()?)?;
+                self.moves_per_control = mps;
+                self.increment = inc;
+                self.clock = Some(base);
+                self.move_time = None;
+            }
+            "st" => self.move_time = Some(parse_seconds(args)?),
+            "sd" => self.depth = Some(args.parse::<u32>().ok()?.clamp(1, u32::from(u8::MAX)).try_into().ok()?),
+            _ => self.clock = Some(args.parse::<u64>().ok()? * 10),
+        }
+        Some(())
+    }
+
+    /// Ends the running search, if any, applying a move it has announced.
+    fn finish_search(&mut self, how: Finish) {
+        let Some(worker) = self.worker.take() else {
+            return;
+        };
+        let (searcher, played) = match how {
+            Finish::Wait => worker.join().expect("search thread panicked"),
+            Finish::MoveNow => stop_worker(worker, &self.stop),
+            Finish::Cancel => {
+                // The worker checks the flag under the same lock before
+                // announcing its move, so the move is either out or dropped.
+                {
+                    let _out = self.out.lock().unwrap_or_else(PoisonError::into_inner);
+                    self.cancel.store(true, Ordering::Relaxed);
+                }
+                stop_worker(worker, &self.stop)
+            }
+        };
+        self.searcher = Some(searcher);
+        if let Some(mv) = played {
+            self.previous.push(self.board.clone());
+            self.board.make_move(mv);
+        }
+    }
+
+    fn new_game(&mut self) {
+        self.finish_search(Finish::Cancel);
+        self.board = Board::new();
+        self.previous.clear();
+        self.engine = Some(Color::Black);
+        self.depth = None;
+        self.move_time = None;
+        if let Some(searcher) = &mut self.searcher {
+            searcher.clear();
+        }
+    }
+
+    fn set_board(&mut self, fen: &str) {
+        self.finish_search(Finish::Cancel);
+        match Board::from_fen(fen) {
+            Some(board) => {
+                self.board = board;
+                self.previous.clear();
+            }
+            None => send(&self.out, "tellusererror Illegal position"),
+        }
+    }
+
+    fn take_back(&mut self, plies: usize) {
+        self.finish_search(Finish::Cancel);
+        for _ in 0..plies {
+            if let Some(board) = self.previous.pop() {
+                self.board = board;
+            }
+        }
+    }
+
+    fn previous_keys(&self) -> Vec<u64> {
+        self.previous.iter().map(Board::polyglot_key).collect()
+    }
+
+    fn user_move(&mut self, uci: &str) {
+        self.finish_search(Finish::Wait);
+        let before = self.board.clone();
+        if !Move::from_uci(uci).is_some_and(|mv| self.board.make_move(mv)) {
+            send(&self.out, &format!("Illegal move: {uci}"));
+            return;
+        }
+        self.previous.push(before);
+        if let Some(result) = game_result(&self.board, &self.previous_keys()) {
+            send(&self.out, result);
+            return;
+        }
+        self.think();
+    }
+
+    /// Search parameters from the time control and the engine's clock.
+    fn go_params(&self) -> GoParams {
+        let us = self.board.side_to_move();
+        let mut params = GoParams { depth: self.depth, movetime: self.move_time, ..GoParams::default() };
+        params.time[us.index()] = self.clock;
+        params.inc[us.index()] = self.increment;
+        if self.moves_per_control > 0 {
+            let played = u32::from(self.board.fullmove_number().saturating_sub(1));
+            params.movestogo = Some(self.moves_per_control - played % self.moves_per_control);
+        }
+        if params.depth.is_none() && params.movetime.is_none() && params.time[us.index()].is_none() {
+            params.movetime = Some(DEFAULT_MOVE_TIME_MS);
+        }
+        params
+    }
+
+    /// Starts searching if it is the engine's turn and the game is not over.
+    fn think(&mut self) {
+        let keys = self.previous_keys();
+        if self.engine != Some(self.board.side_to_move()) || game_result(&self.board, &keys).is_some() {
+            return;
+        }
+        let limits = self.go_params().limits(self.board.side_to_move());
+        let mut searcher = self.searcher.take().expect("searcher is idle");
+        searcher.set_game_history(keys.clone());
+        let mut board = self.board.clone();
+        let out = Arc::clone(&self.out);
+        let post = self.post;
+        self.cancel.store(false, Ordering::Relaxed);
+        let cancel = Arc::clone(&self.cancel);
+        self.worker = Some(std::thread::spawn(move || {
+            let result = searcher.search_with(&board, limits, |r| {
+                if post {
+                    send(&out, &thinking_line(r));
+                }
+            });
+            let mut out = out.lock().unwrap_or_else(PoisonError::into_inner);
+            if cancel.load(Ordering::Relaxed) {
+                return (searcher, None);
+            }
+            let played = result.best_move.inspect(|&mv| {
+                let mut keys = keys;
+                keys.push(board.polyglot_key());
+                board.make_move(mv);
+                let _ = writeln!(out, "move {}", mv.to_uci());
+                if let Some(result) = game_result(&board, &keys) {
+                    let _ = writeln!(out, "{result}");
+                }
+                let _ = out.flush();
+            });
+            (searcher, played)
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uci::tests::Output;
+
+    fn session() -> (Xboard<Output>, Output) {
+        let out = Output::default();
+        (Xboard::new(out.clone()), out)
+    }
+
+    #[test]
+    fn handshake() {
+        let (mut xb, out) = session();
+        xb.handle("xboard");
+        xb.handle("protover 2");
+        xb.handle("ping 7");
+        let lines = out.lines();
+        assert!(lines[0].starts_with("feature ") && lines[0].ends_with("done=1"));
+        assert!(lines[0].contains("usermove=1") && lines[0].contains("setboard=1"));
+        assert_eq!(lines[1], "pong 7");
+        assert!(lines[0].contains("smp=1"));
+        xb.handle("cores 2");
+        assert_eq!(xb.searcher.as_ref().unwrap().threads(), 2);
+        xb.handle("cores many");
+        assert_eq!(out.lines().last().unwrap(), "Error (bad argument): cores many");
+    }
+
+    #[test]
+    fn engine_answers_user_move() {
+        let (mut xb, out) = session();
+        xb.handle("new");
+        xb.handle("sd 2");
+        xb.handle("usermove e2e4");
+        xb.handle("time 10000"); // waits for the engine's move
+        let lines = out.lines();
+        let reply = lines.iter().find_map(|l| l.strip_prefix("move ")).expect("engine moved");
+        let mut after_e4 = Board::new();
+        after_e4.make_move(Move::from_uci("e2e4").unwrap());
+        assert!(after_e4.generate_legal_moves().iter().any(|m| m.to_uci() == reply));
+
+        xb.handle("force");
+        assert_eq!(xb.previous.len(), 2, "the engine's move was applied");
+        assert_eq!(xb.board.side_to_move(), Color::White);
+    }
+
+    #[test]
+    fn force_mode_and_take_backs() {
+        let (mut xb, out) = session();
+        xb.handle("new");
+        xb.handle("force");
+        for mv in ["e2e4", "e7e5", "g1f3"] {
+            xb.handle(&format!("usermove {mv}"));
+        }
+        xb.handle("e2e5");
+        assert_eq!(out.lines(), ["Illegal move: e2e5"]);
+        xb.handle("undo");
+        assert_eq!(xb.board.side_to_move(), Color::White);
+        xb.handle("remove");
+        assert_eq!(xb.board.to_fen(), Board::new().to_fen());
+        xb.handle("remove");
+        assert_eq!(xb.board.to_fen(), Board::new().to_fen());
+    }
+
+    #[test]
+    fn go_from_setboard_reports_mate() {
+        let (mut xb, out) = session();
+        xb.handle("new");
+        xb.handle("force");
+        xb.handle("setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        xb.handle("sd 3");
+        xb.handle("post");
+        xb.handle("go");
+        xb.handle("time 10000");
+        xb.handle("ping 2");
+        let lines = out.lines();
+        // Thinking: ply, score (mate in 1), centiseconds, nodes, PV.
+        assert!(lines[0].starts_with("1 100001 ") && lines[0].ends_with(" a1a8"), "{lines:?}");
+        let tail = &lines[lines.len() - 3..];
+        assert_eq!(tail, ["move a1a8", "1-0 {White mates}", "pong 2"]);
+    }
+
+    #[test]
+    fn user_move_can_end_the_game() {
+        let (mut xb, out) = session();
+        xb.handle("setboard 7k/8/6K1/8/8/8/8/5Q2 w - - 0 1");
+        xb.handle("f1f7");
+        assert_eq!(out.lines(), ["1/2-1/2 {Stalemate}"]);
+    }
+
+    #[test]
+    fn bad_input() {
+        let (mut xb, out) = session();
+        xb.handle("setboard not a fen");
+        xb.handle("frobnicate");
+        xb.handle("level 40 x 0");
+        assert_eq!(
+            out.lines(),
+            [
+                "tellusererror Illegal position",
+                "Error (unknown command): frobnicate",
+                "Error (bad argument): level 40 x 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn time_controls() {
+        let (mut xb, _) = session();
+        assert_eq!(xb.go_params().movetime, Some(DEFAULT_MOVE_TIME_MS));
+
+        // The base time is the clock until the GUI sends one.
+        xb.handle("level 40 5 0");
+        let params = xb.go_params();
+        assert_eq!(params.movestogo, Some(40));
+        assert_eq!(params.time[Color::White.index()], Some(300_000));
+        assert_eq!(params.movetime, None);
+        xb.handle("time 12000");
+        assert_eq!(xb.go_params().time[Color::White.index()], Some(120_000));
+
+        xb.handle("level 0 2:30 1.5");
+        assert_eq!(xb.go_params().inc[Color::White.index()], 1500);
+        assert_eq!(xb.go_params().movestogo, None);
+
+        xb.handle("st 2");
+        xb.handle("sd 7");
+        assert_eq!(xb.go_params().movetime, Some(2000));
+        assert_eq!(xb.go_params().depth, Some(7));
+    }
+
+    #[test]
+    fn cancelled_search_does_not_move() {
+        let (mut xb, out) = session();
+        xb.handle("st 30");
+        xb.handle("go");
+        xb.handle("force");
+        assert!(!out.lines().iter().any(|l| l.starts_with("move ")));
+        assert_eq!(xb.board.to_fen(), Board::new().to_fen());
+    }
+
+    #[test]
+    fn move_now() {
+        let (mut xb, out) = session();
+        xb.handle("st 30");
+        xb.handle("go");
+        xb.handle("?");
+        assert!(out.lines().iter().any(|l| l.starts_with("move ")));
+        assert_eq!(xb.board.side_to_move(), Color::Black);
+    }
+}
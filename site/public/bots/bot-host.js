@@ -1,10 +1,16 @@
 let currentGame = null;
+let currentClock = null;
 let logCallback = console.log;
 
 export function setGame(game) {
   currentGame = game;
 }
 
+// Clock for the bot to move: { remainingMs, incrementMs, movesToGo? }, or null for untimed play.
+export function setClock(clock) {
+  currentClock = clock;
+}
+
 export function setLogCallback(callback) {
   logCallback = callback;
 }
@@ -36,6 +42,10 @@ export function getFen() {
   return requireGame().getFen();
 }
 
+export function getClock() {
+  return currentClock ?? undefined;
+}
+
 export function log(message) {
   logCallback(`[Bot] ${message}`);
 }
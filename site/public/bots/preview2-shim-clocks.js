@@ -0,0 +1,3 @@
+// Browser stubs for WASI clocks
+export function now() { return BigInt(Math.round(performance.now() * 1e6)); }
+export function resolution() { return 1000n; }
@@ -7,6 +7,18 @@ export type Piece = import('./chess-types-types.js').Piece;
 export type Square = import('./chess-types-types.js').Square;
 export type EngineError = import('./chess-types-types.js').EngineError;
 export type MoveHistoryEntry = import('./chess-types-types.js').MoveHistoryEntry;
+export interface AnalysisLine {
+  uciMove: Move,
+  scoreCp: number,
+  mateIn?: number,
+  depth: number,
+  pv: Array<Move>,
+}
+export interface Clock {
+  remainingMs: number,
+  incrementMs: number,
+  movesToGo?: number,
+}
 
 export class Game {
   constructor()
@@ -20,5 +32,10 @@ export class Game {
   getTurn(): Color;
   getPieceAt(sq: Square): Piece | undefined;
   getMoveHistory(): Array<MoveHistoryEntry>;
+  bestMove(maxDepth: number, maxTimeMs: number): Move | undefined;
+  bestMoveOnClock(clock: Clock): Move | undefined;
+  analyze(lines: number, maxDepth: number, maxTimeMs: number): Array<AnalysisLine>;
+  setSkillLevel(level: number): void;
+  setEvalParams(params: string): boolean;
   reset(): void;
 }
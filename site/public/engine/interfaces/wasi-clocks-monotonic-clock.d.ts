@@ -0,0 +1,5 @@
+/** @module Interface wasi:clocks/monotonic-clock@0.2.3 **/
+export function now(): Instant;
+export function resolution(): Duration;
+export type Instant = bigint;
+export type Duration = bigint;
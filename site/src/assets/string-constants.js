@@ -27,11 +27,13 @@ package chess:bot@0.1.0;
 
 interface host {
     use chess:types/types@0.1.0.{board-state, move, game-result};
+    record clock { remaining-ms: u32, increment-ms: u32, moves-to-go: option<u32> }
     get-board: func() -> board-state;
     get-legal-moves: func() -> list<move>;
     is-check: func() -> bool;
     get-game-result: func() -> game-result;
     get-fen: func() -> string;
+    get-clock: func() -> option<clock>;
     log: func(message: string);
 }
 
@@ -52,7 +54,7 @@ world chess-bot {
 
 export const pythonExample = `# Smart Bot — captures, center control, development.
 # Host functions: get_board(), get_legal_moves(),
-#   is_check(), get_fen(), log(msg)
+#   is_check(), get_fen(), get_clock(), log(msg)
 
 # Material values for MVV-LVA ordering
 PIECE_VALUES = {"queen": 9, "rook": 5, "bishop": 3, "knight": 3, "pawn": 1, "king": 0}
@@ -155,6 +155,7 @@ async function loadUploadedBot(botData, color) {
         isCheck: () => botHostModule.isCheck(),
         getGameResult: () => botHostModule.getGameResult(),
         getFen: () => botHostModule.getFen(),
+        getClock: () => botHostModule.getClock(),
         log: (msg) => botHostModule.log(msg),
       },
     });
@@ -504,6 +505,7 @@ export function setupBotUpload() {
           isCheck: () => botHostModule.isCheck(),
           getGameResult: () => botHostModule.getGameResult(),
           getFen: () => botHostModule.getFen(),
+          getClock: () => botHostModule.getClock(),
           log: (msg) => botHostModule.log(msg),
         },
       });
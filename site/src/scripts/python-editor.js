@@ -11,7 +11,7 @@ import { toast } from './toast.js';
 let editorView = null;
 let isFullscreen = false;
 
-const PYTHON_INPUT_NAMES = ["_board", "_legal_moves", "_is_check", "_fen", "_seed"];
+const PYTHON_INPUT_NAMES = ["_board", "_legal_moves", "_is_check", "_fen", "_clock", "_seed"];
 const PYTHON_SHIM = `
 _log_messages = []
 
@@ -27,6 +27,9 @@ def is_check():
 def get_fen():
   return _fen
 
+def get_clock():
+  return _clock
+
 def log(msg):
   _log_messages.append(str(msg))
 
@@ -243,7 +246,7 @@ export function getInitialCode() {
   return document.getElementById('python-code-editor').dataset.initialCode || '';
 }
 
-function getPythonInputs(game) {
+function getPythonInputs(game, clock) {
   const boardState = game.getBoardState();
   const legalMoves = game.getLegalMoves();
   return {
@@ -264,15 +267,20 @@ function getPythonInputs(game) {
     },
     _is_check: game.isCheck(),
     _fen: game.getFen(),
+    _clock: clock ? {
+      remaining_ms: clock.remainingMs,
+      increment_ms: clock.incrementMs,
+      moves_to_go: clock.movesToGo,
+    } : null,
     _seed: Math.floor(Math.random() * 2147483647),
   };
 }
 
-export function executePythonBot(code, method, game, addLogEntry, MontyClass) {
+export function executePythonBot(code, method, game, addLogEntry, MontyClass, clock = null) {
   if (!MontyClass) {
     throw new Error('Python runtime not loaded');
   }
-  const inputs = getPythonInputs(game);
+  const inputs = getPythonInputs(game, clock);
 
   let tail;
   if (method === 'on_game_start') {